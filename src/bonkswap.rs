@@ -5,16 +5,20 @@
 //! Instructions: 19
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
+    error::ProgramError,
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{CpiError, invoke_signed, max_in_for_exact_out, parse_token_account_balance};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("BSwp6bEBihVLdqJRKGgzjcGLHkcTuzmSo1TQkHepzH8p"));
 
@@ -48,12 +52,12 @@ pub const UPDATE_REWARD_TOKENS: [u8; 8] = [249, 236, 71, 74, 104, 58, 225, 28];
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct CreatepoolArgs {
-    pub lp_fee: [u8; 32],
-    pub buyback_fee: [u8; 32],
-    pub project_fee: [u8; 32],
-    pub mercanti_fee: [u8; 32],
-    pub initial_token_x: [u8; 32],
-    pub initial_token_y: [u8; 32],
+    pub lp_fee: u128,
+    pub buyback_fee: u128,
+    pub project_fee: u128,
+    pub mercanti_fee: u128,
+    pub initial_token_x: u64,
+    pub initial_token_y: u64,
     pub bump: u8,
 }
 
@@ -61,8 +65,8 @@ pub struct CreatepoolArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct CreateproviderArgs {
-    pub token_x_amount: [u8; 32],
-    pub token_y_amount: [u8; 32],
+    pub token_x_amount: u64,
+    pub token_y_amount: u64,
     pub bump: u8,
 }
 
@@ -77,16 +81,16 @@ pub struct CreatestateArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct AddtokensArgs {
-    pub delta_x: [u8; 32],
-    pub delta_y: [u8; 32],
+    pub delta_x: u64,
+    pub delta_y: u64,
 }
 
 /// Arguments for `swap`
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct SwapArgs {
-    pub delta_in: [u8; 32],
-    pub price_limit: [u8; 32],
+    pub delta_in: u64,
+    pub price_limit: u128,
     pub x_to_y: bool,
 }
 
@@ -94,14 +98,14 @@ pub struct SwapArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct WithdrawsharesArgs {
-    pub shares: [u8; 32],
+    pub shares: u64,
 }
 
 /// Arguments for `createFarm`
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct CreatefarmArgs {
-    pub supply: [u8; 32],
+    pub supply: u64,
     pub duration: u64,
     pub bump: u8,
 }
@@ -110,8 +114,8 @@ pub struct CreatefarmArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct CreatedualfarmArgs {
-    pub supply_marco: [u8; 32],
-    pub supply_project_first: [u8; 32],
+    pub supply_marco: u64,
+    pub supply_project_first: u64,
     pub duration: u64,
     pub bump: u8,
 }
@@ -120,9 +124,9 @@ pub struct CreatedualfarmArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct CreatetriplefarmArgs {
-    pub supply_marco: [u8; 32],
-    pub supply_project_first: [u8; 32],
-    pub supply_project_second: [u8; 32],
+    pub supply_marco: u64,
+    pub supply_project_first: u64,
+    pub supply_project_second: u64,
     pub duration: u64,
     pub bump: u8,
 }
@@ -131,9 +135,9 @@ pub struct CreatetriplefarmArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct AddsupplyArgs {
-    pub supply_marco: [u8; 32],
-    pub supply_project_first: [u8; 32],
-    pub supply_project_second: [u8; 32],
+    pub supply_marco: u64,
+    pub supply_project_first: u64,
+    pub supply_project_second: u64,
     pub duration: u64,
 }
 
@@ -141,10 +145,10 @@ pub struct AddsupplyArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct UpdatefeesArgs {
-    pub new_buyback_fee: [u8; 32],
-    pub new_project_fee: [u8; 32],
-    pub new_provider_fee: [u8; 32],
-    pub new_mercanti_fee: [u8; 32],
+    pub new_buyback_fee: u128,
+    pub new_project_fee: u128,
+    pub new_provider_fee: u128,
+    pub new_mercanti_fee: u128,
 }
 
 
@@ -1988,3 +1992,77 @@ pub fn update_reward_tokens<'a>(
     invoke_signed::<12>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Exact Out
+// ============================================
+// Bonkswap has a single `swap` instruction, exact-in only: it takes
+// `delta_in` and a `price_limit`, with no output amount. Exact-out legs are
+// emulated by sizing `delta_in` from the vault balances and passing it to
+// `swap` with the same accounts; the realised output is at least
+// `amount_out` as long as the reserves do not move before the swap lands.
+
+/// Input needed to receive `amount_out`, capped by `max_amount_in`
+///
+/// `pool_in_data` / `pool_out_data` are the pool's input and output vault
+/// token accounts (`pool_x_account` / `pool_y_account` for `x_to_y`), and
+/// `fee_bps` the pool's total swap fee. Returns
+/// `CpiError::SlippageExceeded` when the required input exceeds
+/// `max_amount_in` or the pool cannot fill `amount_out`, and
+/// `ProgramError::InvalidAccountData` when a vault cannot be read.
+pub fn exact_out_amount_in(
+    pool_in_data: &[u8],
+    pool_out_data: &[u8],
+    amount_out: u64,
+    max_amount_in: u64,
+    fee_bps: u64,
+) -> Result<u64, ProgramError> {
+    let reserve_in = parse_token_account_balance(pool_in_data).ok_or(ProgramError::InvalidAccountData)?;
+    let reserve_out = parse_token_account_balance(pool_out_data).ok_or(ProgramError::InvalidAccountData)?;
+    match max_in_for_exact_out(amount_out, reserve_in, reserve_out, fee_bps, 0) {
+        0 => Err(CpiError::SlippageExceeded.into()),
+        amount_in if amount_in > max_amount_in => Err(CpiError::SlippageExceeded.into()),
+        amount_in => Ok(amount_in),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    fn vault(amount: u64) -> [u8; 165] {
+        let mut data = [0u8; 165];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_exact_out_amount_in() {
+        let (x, y) = (vault(1_000_000), vault(1_000_000));
+        // 10_000 out at 30 bps needs 10_133 in
+        assert_eq!(exact_out_amount_in(&x, &y, 10_000, 10_133, 30), Ok(10_133));
+        assert_eq!(exact_out_amount_in(&x, &y, 10_000, 10_132, 30), Err(CpiError::SlippageExceeded.into()));
+        assert_eq!(exact_out_amount_in(&x, &y, 1_000_000, u64::MAX, 30), Err(CpiError::SlippageExceeded.into()));
+        assert_eq!(exact_out_amount_in(&x, &[0u8; 64], 1, u64::MAX, 30), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_swap_args_serialization() {
+        // `Token` wraps a u64 and `FixedPoint` a u128
+        let args = SwapArgs { delta_in: 10_133, price_limit: 1u128 << 80, x_to_y: true };
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 25);
+        assert_eq!(&bytes[0..8], &10_133u64.to_le_bytes());
+        assert_eq!(&bytes[8..24], &(1u128 << 80).to_le_bytes());
+        assert_eq!(bytes[24], 1);
+        assert_eq!(SWAP, crate::common::hash::anchor_discriminator("global", "swap"));
+
+        let pool = CreatepoolArgs {
+            lp_fee: 1, buyback_fee: 2, project_fee: 3, mercanti_fee: 4,
+            initial_token_x: 5, initial_token_y: 6, bump: 255,
+        };
+        assert_eq!(args_bytes(&pool).len(), 4 * 16 + 2 * 8 + 1);
+        assert_eq!(args_bytes(&WithdrawsharesArgs { shares: 42 }), &42u64.to_le_bytes());
+    }
+}
@@ -5,16 +5,19 @@
 //! Instructions: 29
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::invoke_signed;
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("boop8hVGQGqehUK2iVEMEnMrL5RbjywRzHKBmBE7ry4"));
 
@@ -728,6 +731,10 @@ impl<'a> CompleteAuthorityTransferAccounts<'a> {
 }
 
 /// Accounts for `create_meteora_pool`
+///
+/// Migration target: a Meteora DAMM v2 pool (`meteora_damm_v2::ID`,
+/// passed as `cp_amm_program`) seeded from a graduated curve's token and
+/// WSOL vaults. `token_b_mint` is always WSOL. Operator-signed.
 pub struct CreateMeteoraPoolAccounts<'a> {
     /// operator
     pub operator: &'a AccountView,
@@ -735,25 +742,25 @@ pub struct CreateMeteoraPoolAccounts<'a> {
     pub config: &'a AccountView,
     /// vault_authority
     pub vault_authority: &'a AccountView,
-    /// cp_amm_config
+    /// DAMM v2 config the new pool uses
     pub cp_amm_config: &'a AccountView,
-    /// pool_authority
+    /// DAMM v2 pool authority
     pub pool_authority: &'a AccountView,
-    /// pool
+    /// New DAMM v2 pool
     pub pool: &'a AccountView,
-    /// position
+    /// Liquidity position of the initial deposit
     pub position: &'a AccountView,
-    /// position_nft_mint
+    /// Position NFT mint; a fresh keypair that signs
     pub position_nft_mint: &'a AccountView,
-    /// position_nft_account
+    /// Position NFT token account
     pub position_nft_account: &'a AccountView,
     /// token_a_mint
     pub token_a_mint: &'a AccountView,
     /// token_b_mint
     pub token_b_mint: &'a AccountView,
-    /// token_a_vault
+    /// New pool's token vault
     pub token_a_vault: &'a AccountView,
-    /// token_b_vault
+    /// New pool's WSOL vault
     pub token_b_vault: &'a AccountView,
     /// bonding_curve
     pub bonding_curve: &'a AccountView,
@@ -812,20 +819,25 @@ impl<'a> CreateMeteoraPoolAccounts<'a> {
 }
 
 /// Accounts for `create_raydium_pool`
+///
+/// Migration target: a Raydium CP pool (`raydium_cp::ID`, passed as
+/// `cpmm_program`) seeded from a graduated curve's token and WSOL vaults.
+/// `token_1_mint` is always WSOL, and the pool-side PDAs use the seeds in
+/// `raydium_cp` (`POOL_LP_MINT_SEED`, `POOL_VAULT_SEED`, ...). Operator-signed.
 pub struct CreateRaydiumPoolAccounts<'a> {
     /// cpmm_program
     pub cpmm_program: &'a AccountView,
     /// Which config the pool belongs to.
     pub amm_config: &'a AccountView,
-    /// authority
+    /// Raydium CP vault and LP mint authority, seeds `[raydium_cp::AUTH_SEED]`
     pub authority: &'a AccountView,
-    /// pool_state
+    /// New pool, seeds `["pool", amm_config, token_0_mint, token_1_mint]` under Raydium CP
     pub pool_state: &'a AccountView,
     /// token_0_mint
     pub token_0_mint: &'a AccountView,
     /// Token_1 mint, the key must be greater than token_0 mint.
     pub token_1_mint: &'a AccountView,
-    /// lp_mint
+    /// New LP mint
     pub lp_mint: &'a AccountView,
     /// vault_authority
     pub vault_authority: &'a AccountView,
@@ -835,15 +847,15 @@ pub struct CreateRaydiumPoolAccounts<'a> {
     pub bonding_curve_vault: &'a AccountView,
     /// bonding_curve_wsol_vault
     pub bonding_curve_wsol_vault: &'a AccountView,
-    /// creator_lp_token
+    /// Receives the LP tokens of the initial deposit
     pub creator_lp_token: &'a AccountView,
-    /// token_0_vault
+    /// New pool's token vault
     pub token_0_vault: &'a AccountView,
-    /// token_1_vault
+    /// New pool's WSOL vault
     pub token_1_vault: &'a AccountView,
     /// create pool fee account
     pub create_pool_fee: &'a AccountView,
-    /// observation_state
+    /// New pool's observation account
     pub observation_state: &'a AccountView,
     /// operator
     pub operator: &'a AccountView,
@@ -1262,30 +1274,35 @@ impl<'a> DepositIntoRaydiumAccounts<'a> {
 }
 
 /// Accounts for `graduate`
+///
+/// Settles a completed bonding curve's SOL and token vaults (protocol fee,
+/// distributor allocation) so `create_raydium_pool` or
+/// `create_meteora_pool` can migrate it. Signed by a config operator, not
+/// by the token creator.
 pub struct GraduateAccounts<'a> {
-    /// mint
+    /// Token mint of the bonding curve
     pub mint: &'a AccountView,
-    /// wsol
+    /// WSOL mint
     pub wsol: &'a AccountView,
-    /// protocol_fee_recipient
+    /// Receives the protocol fee taken at graduation
     pub protocol_fee_recipient: &'a AccountView,
-    /// token_distributor
+    /// Owner of the token allocation set aside at graduation
     pub token_distributor: &'a AccountView,
-    /// token_distributor_token_account
+    /// ATA of `token_distributor` for `mint`
     pub token_distributor_token_account: &'a AccountView,
-    /// vault_authority
+    /// Vault authority PDA, seeds `["vault_authority"]`
     pub vault_authority: &'a AccountView,
-    /// bonding_curve_sol_vault
+    /// Native SOL raised by the curve, seeds `["bonding_curve_sol_vault", mint]`
     pub bonding_curve_sol_vault: &'a AccountView,
-    /// bonding_curve
+    /// Bonding curve PDA, seeds `["bonding_curve", mint]`
     pub bonding_curve: &'a AccountView,
-    /// bonding_curve_vault
+    /// Curve token vault, seeds `["bonding_curve_vault", mint]`
     pub bonding_curve_vault: &'a AccountView,
-    /// bonding_curve_wsol_account
+    /// Curve WSOL vault, seeds `["bonding_curve_wsol_vault", mint]`
     pub bonding_curve_wsol_account: &'a AccountView,
-    /// operator
+    /// Config operator; signs and pays
     pub operator: &'a AccountView,
-    /// config
+    /// Config PDA, seeds `["config"]`
     pub config: &'a AccountView,
     /// system_program
     pub system_program: &'a AccountView,
@@ -1655,6 +1672,12 @@ impl<'a> SplitPostGraduationTradingFeesAccounts<'a> {
 }
 
 /// Accounts for `split_trading_fees`
+///
+/// Sends the trading fees accrued on a bonding curve to the fee splitter
+/// program (`FEE_SPLITTER_PROGRAM_ID`), which credits the creator's share
+/// to `fee_splitter_creator_vault`. The creator then withdraws through the
+/// fee splitter program, which this crate does not wrap. Operator-signed;
+/// `split_post_graduation_trading_fees` does the same after migration.
 pub struct SplitTradingFeesAccounts<'a> {
     /// operator
     pub operator: &'a AccountView,
@@ -1668,7 +1691,7 @@ pub struct SplitTradingFeesAccounts<'a> {
     pub vault_authority: &'a AccountView,
     /// bonding_curve
     pub bonding_curve: &'a AccountView,
-    /// trading_fees_vault
+    /// Accrued curve trading fees, seeds `["trading_fees_vault", mint]`
     pub trading_fees_vault: &'a AccountView,
     /// fee_splitter_program
     pub fee_splitter_program: &'a AccountView,
@@ -1680,7 +1703,7 @@ pub struct SplitTradingFeesAccounts<'a> {
     pub associated_token_program: &'a AccountView,
     /// fee_splitter_config
     pub fee_splitter_config: &'a AccountView,
-    /// fee_splitter_creator_vault
+    /// Creator's fee vault in the fee splitter
     pub fee_splitter_creator_vault: &'a AccountView,
     /// fee_splitter_vault_authority
     pub fee_splitter_vault_authority: &'a AccountView,
@@ -2812,3 +2835,79 @@ pub fn update_config<'a>(
     invoke_signed::<3>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Graduation
+// ============================================
+
+/// Fee splitter program that holds creator fee shares
+pub const FEE_SPLITTER_PROGRAM_ID: Address = Address::new_from_array(five8_const::decode_32_const("boopEYztaEYSnajfMtjcRysyzyRcchgKsPboRZEbnJi"));
+
+/// Config PDA seed: `["config"]`
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Vault authority PDA seed: `["vault_authority"]`
+pub const VAULT_AUTHORITY_SEED: &[u8] = b"vault_authority";
+
+/// Bonding curve PDA seed prefix: `["bonding_curve", mint]`
+pub const BONDING_CURVE_SEED: &[u8] = b"bonding_curve";
+
+/// Curve token vault PDA seed prefix: `["bonding_curve_vault", mint]`
+pub const BONDING_CURVE_VAULT_SEED: &[u8] = b"bonding_curve_vault";
+
+/// Curve SOL vault PDA seed prefix: `["bonding_curve_sol_vault", mint]`
+pub const BONDING_CURVE_SOL_VAULT_SEED: &[u8] = b"bonding_curve_sol_vault";
+
+/// Curve WSOL vault PDA seed prefix: `["bonding_curve_wsol_vault", mint]`
+pub const BONDING_CURVE_WSOL_VAULT_SEED: &[u8] = b"bonding_curve_wsol_vault";
+
+/// Curve trading fee vault PDA seed prefix: `["trading_fees_vault", mint]`
+pub const TRADING_FEES_VAULT_SEED: &[u8] = b"trading_fees_vault";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{dump_account_roles, hash::anchor_discriminator, last_cpi, mock_account_views};
+    use pinocchio::account::RuntimeAccount;
+
+    #[test]
+    fn test_graduation_discriminators() {
+        assert_eq!(GRADUATE, anchor_discriminator("global", "graduate"));
+        assert_eq!(CREATE_RAYDIUM_POOL, anchor_discriminator("global", "create_raydium_pool"));
+        assert_eq!(CREATE_METEORA_POOL, anchor_discriminator("global", "create_meteora_pool"));
+        assert_eq!(SPLIT_TRADING_FEES, anchor_discriminator("global", "split_trading_fees"));
+        assert_eq!(
+            SPLIT_POST_GRADUATION_TRADING_FEES,
+            anchor_discriminator("global", "split_post_graduation_trading_fees")
+        );
+    }
+
+    #[test]
+    fn test_graduate_accounts() {
+        let mut raw: [RuntimeAccount; 15] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let accounts = GraduateAccounts {
+            mint: &v[0],
+            wsol: &v[1],
+            protocol_fee_recipient: &v[2],
+            token_distributor: &v[3],
+            token_distributor_token_account: &v[4],
+            vault_authority: &v[5],
+            bonding_curve_sol_vault: &v[6],
+            bonding_curve: &v[7],
+            bonding_curve_vault: &v[8],
+            bonding_curve_wsol_account: &v[9],
+            operator: &v[10],
+            config: &v[11],
+            system_program: &v[12],
+            token_program: &v[13],
+            associated_token_program: &v[14],
+        };
+        // Only the operator signs
+        assert_eq!(
+            dump_account_roles(&accounts.to_instruction_accounts()),
+            "r r w r w r w w w w ws r r r r"
+        );
+        graduate(&accounts, &[]).unwrap();
+        assert_eq!(last_cpi(), (ID, GRADUATE.to_vec()));
+    }
+}
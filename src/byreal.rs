@@ -5,16 +5,21 @@
 //! Instructions: 29
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::clmm_common::TickRange;
+use crate::common::math::mul_div;
+use crate::common::{invoke_signed, read_bytes, read_pubkey, read_u128_le, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("REALQqNEomY6cQGZJUGwywTBD2UmDT32rZcNnfxQ5N2"));
 
@@ -161,18 +166,8 @@ pub struct InitializeRewardArgs {
     pub param: [u8; 32],
 }
 
-/// Arguments for `open_position`
-#[derive(Clone, Copy)]
-#[repr(C, packed)]
-pub struct OpenPositionArgs {
-    pub tick_lower_index: i32,
-    pub tick_upper_index: i32,
-    pub tick_array_lower_start_index: i32,
-    pub tick_array_upper_start_index: i32,
-    pub liquidity: u128,
-    pub amount_0_max: u64,
-    pub amount_1_max: u64,
-}
+/// Arguments for `open_position`; build with `clmm_common::open_position_args`
+pub use crate::clmm_common::OpenPositionArgs;
 
 /// Arguments for `open_position_v2`
 #[derive(Clone, Copy)]
@@ -294,7 +289,7 @@ pub struct ClaimOffchainRewardAccounts<'a> {
     pub pool_id: &'a AccountView,
     /// token_mint
     pub token_mint: &'a AccountView,
-    /// 
+    /// claimer_token_account
     pub claimer_token_account: &'a AccountView,
     /// reward_vault_token_account
     pub reward_vault_token_account: &'a AccountView,
@@ -837,7 +832,7 @@ pub struct DepositOffchainRewardAccounts<'a> {
     pub pool_id: &'a AccountView,
     /// token_mint
     pub token_mint: &'a AccountView,
-    /// 
+    /// payer_token_account
     pub payer_token_account: &'a AccountView,
     /// reward_vault_token_account
     pub reward_vault_token_account: &'a AccountView,
@@ -2665,3 +2660,293 @@ pub fn withdraw_offchain_reward<'a>(
     invoke_signed::<9>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `PoolState`
+pub const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+
+/// Account discriminator for `AmmConfig`
+pub const AMM_CONFIG_DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+
+/// Account discriminator for `ObservationState`
+pub const OBSERVATION_STATE_DISCRIMINATOR: [u8; 8] = [122, 174, 197, 53, 129, 9, 165, 132];
+
+/// Denominator of all `AmmConfig` fee rates (1e6 = 100%)
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+/// Observations kept in the `ObservationState` ring buffer
+pub const OBSERVATION_NUM: usize = 100;
+
+/// Decoded `PoolState` account (fields needed for quoting)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolState {
+    pub amm_config: [u8; 32],
+    pub token_mint_0: [u8; 32],
+    pub token_mint_1: [u8; 32],
+    pub observation_key: [u8; 32],
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+}
+
+impl PoolState {
+    /// Minimum account size up to and including `tick_current`
+    pub const MIN_SIZE: usize = 273;
+
+    /// Parse a `PoolState` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        // discriminator, bump (u8), amm_config, owner, mints, vaults, observation_key, decimals, ...
+        if data.len() < Self::MIN_SIZE || data[0..8] != POOL_STATE_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            amm_config: read_pubkey(data, 9)?,
+            token_mint_0: read_pubkey(data, 73)?,
+            token_mint_1: read_pubkey(data, 105)?,
+            observation_key: read_pubkey(data, 201)?,
+            tick_spacing: u16::from_le_bytes(read_bytes(data, 235)?),
+            liquidity: read_u128_le(data, 237)?,
+            sqrt_price_x64: read_u128_le(data, 253)?,
+            tick_current: i32::from_le_bytes(read_bytes(data, 269)?),
+        })
+    }
+}
+
+/// Decoded `AmmConfig` account (fee tier, rates in `FEE_RATE_DENOMINATOR` units)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AmmConfig {
+    pub index: u16,
+    pub protocol_fee_rate: u32,
+    pub trade_fee_rate: u32,
+    pub tick_spacing: u16,
+    pub fund_fee_rate: u32,
+}
+
+impl AmmConfig {
+    /// Account size
+    pub const SIZE: usize = 117;
+
+    /// Parse an `AmmConfig` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        // discriminator, bump (u8), index, owner (pubkey), rates, ...
+        if data.len() < Self::SIZE || data[0..8] != AMM_CONFIG_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            index: u16::from_le_bytes(read_bytes(data, 9)?),
+            protocol_fee_rate: u32::from_le_bytes(read_bytes(data, 43)?),
+            trade_fee_rate: u32::from_le_bytes(read_bytes(data, 47)?),
+            tick_spacing: u16::from_le_bytes(read_bytes(data, 51)?),
+            fund_fee_rate: u32::from_le_bytes(read_bytes(data, 53)?),
+        })
+    }
+
+    /// Trade fee on `amount_in`, rounded up like the program
+    #[inline(always)]
+    pub fn trade_fee(&self, amount_in: u64) -> u64 {
+        (amount_in as u128 * self.trade_fee_rate as u128).div_ceil(FEE_RATE_DENOMINATOR as u128) as u64
+    }
+}
+
+/// One `Observation`: cumulative tick at a block time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Observation {
+    pub block_timestamp: u32,
+    pub tick_cumulative: i64,
+}
+
+/// Decoded `ObservationState` account (the pool's TWAP ring buffer)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObservationState {
+    pub initialized: bool,
+    /// Index of the most recent observation
+    pub observation_index: u16,
+    pub pool_id: [u8; 32],
+    pub observations: [Observation; OBSERVATION_NUM],
+}
+
+impl ObservationState {
+    /// Packed size of one `Observation` (timestamp, tick_cumulative, padding)
+    const OBSERVATION_SIZE: usize = 44;
+
+    /// Minimum account size up to the end of `observations`
+    pub const MIN_SIZE: usize = 51 + OBSERVATION_NUM * Self::OBSERVATION_SIZE;
+
+    /// Parse an `ObservationState` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        // discriminator, initialized (bool), recent_epoch (u64), observation_index (u16), pool_id, observations
+        if data.len() < Self::MIN_SIZE || data[0..8] != OBSERVATION_STATE_DISCRIMINATOR {
+            return None;
+        }
+        let mut observations = [Observation::default(); OBSERVATION_NUM];
+        for (i, observation) in observations.iter_mut().enumerate() {
+            let o = 51 + i * Self::OBSERVATION_SIZE;
+            observation.block_timestamp = u32::from_le_bytes(read_bytes(data, o)?);
+            observation.tick_cumulative = i64::from_le_bytes(read_bytes(data, o + 4)?);
+        }
+        Some(Self {
+            initialized: data[8] != 0,
+            observation_index: u16::from_le_bytes(read_bytes(data, 17)?),
+            pool_id: read_pubkey(data, 19)?,
+            observations,
+        })
+    }
+
+    /// Time-weighted average tick over at least the last `window` seconds
+    ///
+    /// Pairs the latest observation with the newest one at least `window`
+    /// seconds older. `None` if the buffer does not reach back that far.
+    pub fn twap_tick(&self, window: u32) -> Option<i32> {
+        let latest_index = self.observation_index as usize;
+        if !self.initialized || window == 0 || latest_index >= OBSERVATION_NUM {
+            return None;
+        }
+        let latest = self.observations[latest_index];
+        let target = latest.block_timestamp.checked_sub(window)?;
+        (1..OBSERVATION_NUM)
+            .map(|back| self.observations[(latest_index + OBSERVATION_NUM - back) % OBSERVATION_NUM])
+            .take_while(|o| o.block_timestamp != 0 && o.block_timestamp < latest.block_timestamp)
+            .find(|o| o.block_timestamp <= target)
+            .map(|older| {
+                let elapsed = (latest.block_timestamp - older.block_timestamp) as i64;
+                ((latest.tick_cumulative - older.tick_cumulative) / elapsed) as i32
+            })
+    }
+}
+
+// ============================================
+// Quote
+// ============================================
+
+/// Exact-input output while the swap stays in the current tick range
+///
+/// Charges the config's trade fee on the input, then moves the price along
+/// the pool's active liquidity. Crossing an initialized tick changes the
+/// liquidity, which this does not model, so only trust it for amounts that
+/// keep the price inside the current range. Returns 0 on invalid input.
+pub fn quote_exact_in(pool_state: &[u8], amm_config: &[u8], amount_in: u64, zero_for_one: bool) -> u64 {
+    let (Some(pool), Some(config)) = (PoolState::from_bytes(pool_state), AmmConfig::from_bytes(amm_config)) else {
+        return 0;
+    };
+    let (liquidity, sqrt_price) = (pool.liquidity, pool.sqrt_price_x64);
+    if liquidity == 0 || sqrt_price == 0 {
+        return 0;
+    }
+    let amount = amount_in.saturating_sub(config.trade_fee(amount_in)) as u128;
+    let out = if zero_for_one {
+        // sqrt_price' = L * sqrt_price / (L + amount * sqrt_price), rounded up
+        // amount_1 = L * (sqrt_price - sqrt_price')
+        mul_div(amount, sqrt_price, 1 << 64, false)
+            .and_then(|delta| mul_div(liquidity, sqrt_price, liquidity.checked_add(delta)?, true))
+            .and_then(|next| mul_div(liquidity, sqrt_price - next, 1 << 64, false))
+    } else {
+        // sqrt_price' = sqrt_price + amount / L, rounded down
+        // amount_0 = L * (sqrt_price' - sqrt_price) / (sqrt_price' * sqrt_price)
+        mul_div(amount, 1 << 64, liquidity, false)
+            .and_then(|delta| sqrt_price.checked_add(delta))
+            .and_then(|next| {
+                let per_next = mul_div(liquidity, next - sqrt_price, next, false)?;
+                mul_div(per_next, 1 << 64, sqrt_price, false)
+            })
+    };
+    out.unwrap_or(0).min(u64::MAX as u128) as u64
+}
+
+// ============================================
+// Position
+// ============================================
+
+/// Ticks per `TickArrayState`, the `N` of `clmm_common::open_position_args`
+pub const TICK_ARRAY_SIZE: usize = 60;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    fn pool_fixture(sqrt_price_x64: u128) -> [u8; PoolState::MIN_SIZE] {
+        let mut data = [0u8; PoolState::MIN_SIZE];
+        data[0..8].copy_from_slice(&POOL_STATE_DISCRIMINATOR);
+        data[9..41].copy_from_slice(&[3u8; 32]);
+        data[201..233].copy_from_slice(&[4u8; 32]);
+        data[235..237].copy_from_slice(&10u16.to_le_bytes());
+        data[237..253].copy_from_slice(&1_000_000_000_000_000u128.to_le_bytes());
+        data[253..269].copy_from_slice(&sqrt_price_x64.to_le_bytes());
+        data[269..273].copy_from_slice(&(-7i32).to_le_bytes());
+        data
+    }
+
+    fn config_fixture(trade_fee_rate: u32) -> [u8; AmmConfig::SIZE] {
+        let mut data = [0u8; AmmConfig::SIZE];
+        data[0..8].copy_from_slice(&AMM_CONFIG_DISCRIMINATOR);
+        data[9..11].copy_from_slice(&2u16.to_le_bytes());
+        data[43..47].copy_from_slice(&120_000u32.to_le_bytes());
+        data[47..51].copy_from_slice(&trade_fee_rate.to_le_bytes());
+        data[51..53].copy_from_slice(&10u16.to_le_bytes());
+        data[53..57].copy_from_slice(&40_000u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_pool_state_and_config() {
+        let pool = PoolState::from_bytes(&pool_fixture(1 << 64)).unwrap();
+        assert_eq!((pool.amm_config, pool.observation_key), ([3; 32], [4; 32]));
+        assert_eq!((pool.tick_spacing, pool.tick_current), (10, -7));
+        assert_eq!((pool.liquidity, pool.sqrt_price_x64), (1_000_000_000_000_000, 1 << 64));
+
+        let config = AmmConfig::from_bytes(&config_fixture(2_500)).unwrap();
+        assert_eq!(config, AmmConfig {
+            index: 2,
+            protocol_fee_rate: 120_000,
+            trade_fee_rate: 2_500,
+            tick_spacing: 10,
+            fund_fee_rate: 40_000,
+        });
+        // 0.25%, rounded up
+        assert_eq!(config.trade_fee(1_000_001), 2_501);
+
+        let mut bad = config_fixture(2_500);
+        bad[0] ^= 1;
+        assert!(AmmConfig::from_bytes(&bad).is_none());
+        assert!(PoolState::from_bytes(&bad).is_none());
+    }
+
+    #[test]
+    fn test_observation_twap() {
+        let mut data = [0u8; ObservationState::MIN_SIZE];
+        data[0..8].copy_from_slice(&OBSERVATION_STATE_DISCRIMINATOR);
+        data[8] = 1;
+        data[17..19].copy_from_slice(&2u16.to_le_bytes());
+        // tick 10 for 60s, then tick -20 for 60s
+        for (i, (ts, cumulative)) in [(1_000u32, 0i64), (1_060, 600), (1_120, -600)].into_iter().enumerate() {
+            let o = 51 + i * 44;
+            data[o..o + 4].copy_from_slice(&ts.to_le_bytes());
+            data[o + 4..o + 12].copy_from_slice(&cumulative.to_le_bytes());
+        }
+
+        let state = ObservationState::from_bytes(&data).unwrap();
+        assert_eq!(state.observations[1], Observation { block_timestamp: 1_060, tick_cumulative: 600 });
+        assert_eq!(state.twap_tick(60), Some(-20));
+        assert_eq!(state.twap_tick(90), Some(-5));
+        assert_eq!(state.twap_tick(121), None);
+
+        data[0] ^= 1;
+        assert!(ObservationState::from_bytes(&data).is_none());
+    }
+
+    #[test]
+    fn test_quote_uses_fee_tier() {
+        // Price 1.0, 1e15 liquidity, 0.25% fee tier
+        let pool = pool_fixture(1 << 64);
+        assert_eq!(quote_exact_in(&pool, &config_fixture(2_500), 1_000_000, true), 997_499);
+        assert_eq!(quote_exact_in(&pool, &config_fixture(2_500), 1_000_000, false), 997_499);
+        // 1% tier
+        assert_eq!(quote_exact_in(&pool, &config_fixture(10_000), 1_000_000, true), 989_999);
+
+        // Price 4.0: token 0 buys ~4x token 1
+        assert_eq!(quote_exact_in(&pool_fixture(2 << 64), &config_fixture(2_500), 1_000_000, true), 3_989_999);
+        assert_eq!(quote_exact_in(&pool, &pool, 1_000_000, true), 0);
+    }
+}
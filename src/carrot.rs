@@ -5,16 +5,20 @@
 //! Instructions: 42
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
+    error::ProgramError,
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{CpiError, invoke_signed, parse_token_account_balance};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("CarrotwivhMpDnm27EHmRLeQ683Z1PufuqEmBZvD282s"));
 
@@ -4068,3 +4072,123 @@ pub fn update_switchboard_oracle_price<'a>(
     invoke_signed::<12>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Basket Deposit
+// ============================================
+// A Carrot vault accepts a basket of underlying assets and mints one share
+// token. `issue` has no asset-index argument: the deposited underlying is
+// chosen by the accounts, namely `asset` (its mint), `vault_asset_ata` (the
+// vault's token account for it, writable) and `user_asset_ata` (writable,
+// debited). Shares are minted from `shares` (writable) into
+// `user_shares_ata` (writable). Pricing the deposit needs every asset in
+// the basket, so the vault's pricing accounts follow as remaining accounts.
+
+/// Accounts for `deposit` (`issue`)
+pub type DepositAccounts<'a> = IssueAccounts<'a>;
+
+/// Most remaining pricing accounts `deposit` forwards
+pub const MAX_PRICING_ACCOUNTS: usize = 16;
+
+/// Instruction data for `issue`: discriminator, then `IssueArgs { amount }`
+#[inline(always)]
+pub fn issue_data(amount: u64) -> [u8; 16] {
+    let mut data = [0u8; 16];
+    data[0..8].copy_from_slice(&ISSUE);
+    data[8..16].copy_from_slice(&amount.to_le_bytes());
+    data
+}
+
+/// Deposit `amount` of the underlying in `accounts.asset`, minting at least `min_shares`
+///
+/// `pricing_accounts` are appended read-only after the fixed accounts, in
+/// the order the vault expects. The shares received are measured as the
+/// balance increase of `user_shares_ata`; less than `min_shares` fails with
+/// `CpiError::SlippageExceeded`, which reverts the deposit.
+pub fn deposit<'a>(
+    accounts: &DepositAccounts<'a>,
+    amount: u64,
+    min_shares: u64,
+    pricing_accounts: &[&'a AccountView],
+    signers: &[Signer<'_, '_>],
+) -> Result<u64, ProgramError> {
+    const MAX_ACCOUNTS: usize = 11 + MAX_PRICING_ACCOUNTS;
+
+    if pricing_accounts.len() > MAX_PRICING_ACCOUNTS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let shares_before = shares_balance(accounts.user_shares_ata)?;
+
+    let data = issue_data(amount);
+    let fixed_accounts = accounts.to_instruction_accounts();
+    let fixed_views = accounts.to_views();
+    let len = 11 + pricing_accounts.len();
+    let account_views: [&'a AccountView; MAX_ACCOUNTS] = core::array::from_fn(|i| match i {
+        0..11 => fixed_views[i],
+        _ if i < len => pricing_accounts[i - 11],
+        _ => accounts.vault,
+    });
+    let instruction_accounts: [InstructionAccount<'a>; MAX_ACCOUNTS] = core::array::from_fn(|i| match i {
+        0..11 => fixed_accounts[i].clone(),
+        _ => InstructionAccount::readonly(account_views[i].address()),
+    });
+    let instruction = InstructionView {
+        program_id: &ID,
+        accounts: &instruction_accounts[..len],
+        data: &data,
+    };
+    crate::common::invoke_signed_with_bounds::<MAX_ACCOUNTS>(&instruction, &account_views[..len], signers)?;
+
+    let shares = shares_balance(accounts.user_shares_ata)?.saturating_sub(shares_before);
+    if shares < min_shares {
+        return Err(CpiError::SlippageExceeded.into());
+    }
+    Ok(shares)
+}
+
+/// Balance of the user's share token account
+#[inline(always)]
+fn shares_balance(account: &AccountView) -> Result<u64, ProgramError> {
+    let data = account.try_borrow()?;
+    parse_token_account_balance(&data).ok_or(ProgramError::InvalidAccountData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{MockAccount, mock_account_views};
+    use pinocchio::account::RuntimeAccount;
+
+    #[test]
+    fn test_issue_data() {
+        let data = issue_data(1_500_000);
+        assert_eq!(&data[0..8], &crate::common::hash::anchor_discriminator("global", "issue"));
+        assert_eq!(&data[8..16], &1_500_000u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_deposit_checks_min_shares() {
+        let mut raw: [RuntimeAccount; 10] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let mut shares_ata = MockAccount::<165>::new([9; 32]);
+        let shares_ata = shares_ata.view();
+        let accounts = DepositAccounts {
+            vault: &v[0],
+            shares: &v[1],
+            user_shares_ata: &shares_ata,
+            asset: &v[2],
+            vault_asset_ata: &v[3],
+            user_asset_ata: &v[4],
+            user: &v[5],
+            system_program: &v[6],
+            asset_token_program: &v[7],
+            shares_token_program: &v[8],
+            log_program: &v[9],
+        };
+        // Off-chain the CPI mints nothing
+        assert_eq!(deposit(&accounts, 1_000, 0, &[&v[0]], &[]), Ok(0));
+        assert_eq!(deposit(&accounts, 1_000, 1, &[], &[]), Err(CpiError::SlippageExceeded.into()));
+        let too_many = [&v[0]; MAX_PRICING_ACCOUNTS + 1];
+        assert_eq!(deposit(&accounts, 1_000, 0, &too_many, &[]), Err(ProgramError::InvalidArgument));
+    }
+}
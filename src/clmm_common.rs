@@ -0,0 +1,671 @@
+//! Tick-array types shared by the concentrated liquidity modules
+//!
+//! Whirlpool (`TickArray`, 88 ticks) and Raydium CLMM (`TickArrayState`,
+//! 60 ticks) store ticks differently but walk them the same way. Each
+//! module describes its account with a `TickArrayLayout`, and
+//! `TickArrayView` reads ticks straight out of the account data, so the
+//! tick walk and the tick-crossing `quote_exact_in` live in one place.
+//!
+//! Direction follows both programs: moving the price down (`a_to_b` /
+//! `zero_for_one`) searches ticks at or below the current tick, moving it up
+//! searches ticks strictly above.
+//!
+//! `first_array_with_liquidity` skips empty arrays when picking the ones a
+//! swap needs.
+//!
+//! `TickRange`, `round_tick_to_spacing` and `tick_array_start_index` cover
+//! the position side: snapping bounds to usable ticks and finding which tick
+//! arrays they fall in. `open_position_args` builds amm_v3 `open_position`
+//! args for Raydium CLMM and its PancakeSwap, Byreal and Stabble CLMM forks;
+//! Whirlpool snaps its bounds with `TickRange::snapped`.
+
+use crate::common::math::{U256, mul_div};
+use crate::common::{read_bytes, read_u128_le};
+
+/// Lowest tick either program accepts
+pub const MIN_TICK: i32 = -443_636;
+
+/// Highest tick either program accepts
+pub const MAX_TICK: i32 = 443_636;
+
+/// One tick, with the fields needed to cross it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tick {
+    /// Absolute tick index
+    pub index: i32,
+    pub initialized: bool,
+    /// Liquidity added when crossing left to right (removed right to left)
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+}
+
+/// Where a program keeps its ticks inside a tick-array account
+///
+/// Offsets inside a tick are relative to the start of that tick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TickArrayLayout {
+    pub discriminator: [u8; 8],
+    pub start_tick_index_offset: usize,
+    pub ticks_offset: usize,
+    pub tick_size: usize,
+    pub tick_count: usize,
+    pub liquidity_net_offset: usize,
+    pub liquidity_gross_offset: usize,
+    /// Offset of the `initialized` flag; `None` treats `liquidity_gross != 0`
+    /// as initialized
+    pub initialized_offset: Option<usize>,
+}
+
+/// Tick array read in place from its account data
+///
+/// Nothing is copied up front; each tick is decoded when asked for, so a
+/// view costs a few words of stack however many ticks the array holds.
+#[derive(Clone, Copy, Debug)]
+pub struct TickArrayView<'a> {
+    data: &'a [u8],
+    layout: &'a TickArrayLayout,
+    pub start_tick_index: i32,
+    pub tick_spacing: u16,
+}
+
+impl<'a> TickArrayView<'a> {
+    /// View `data` as a tick array of `layout`
+    ///
+    /// `tick_spacing` comes from the owning pool. `None` on a short account,
+    /// a wrong discriminator or a zero spacing.
+    pub fn new(data: &'a [u8], layout: &'a TickArrayLayout, tick_spacing: u16) -> Option<Self> {
+        let len = layout.ticks_offset + layout.tick_count * layout.tick_size;
+        if tick_spacing == 0 || data.len() < len || data[0..8] != layout.discriminator {
+            return None;
+        }
+        let start_tick_index = i32::from_le_bytes(read_bytes(data, layout.start_tick_index_offset)?);
+        Some(Self { data, layout, start_tick_index, tick_spacing })
+    }
+
+    /// Number of ticks in the array
+    #[inline(always)]
+    pub fn tick_count(&self) -> usize {
+        self.layout.tick_count
+    }
+
+    /// Tick at position `i`, `None` past the end
+    pub fn tick(&self, i: usize) -> Option<Tick> {
+        if i >= self.tick_count() {
+            return None;
+        }
+        let o = self.layout.ticks_offset + i * self.layout.tick_size;
+        let liquidity_net = i128::from_le_bytes(read_bytes(self.data, o + self.layout.liquidity_net_offset)?);
+        let liquidity_gross = read_u128_le(self.data, o + self.layout.liquidity_gross_offset)?;
+        let initialized = match self.layout.initialized_offset {
+            Some(flag) => *self.data.get(o + flag)? != 0,
+            None => liquidity_gross != 0,
+        };
+        Some(Tick {
+            index: self.start_tick_index + i as i32 * self.tick_spacing as i32,
+            initialized,
+            liquidity_net,
+            liquidity_gross,
+        })
+    }
+
+    /// Whether `tick_index` falls inside this array's range
+    #[inline(always)]
+    pub fn contains(&self, tick_index: i32) -> bool {
+        let span = self.tick_count() as i64 * self.tick_spacing as i64;
+        let offset = tick_index as i64 - self.start_tick_index as i64;
+        (0..span).contains(&offset)
+    }
+
+    /// Initialized ticks in swap order, starting from `tick_index`
+    ///
+    /// With `a_to_b` yields ticks `<= tick_index` in descending order,
+    /// otherwise ticks `> tick_index` in ascending order. Ticks outside this
+    /// array are simply not yielded; continue with the adjacent array.
+    pub fn initialized_ticks(&self, tick_index: i32, a_to_b: bool) -> impl Iterator<Item = Tick> + '_ {
+        // Ticks at positions `..split` have index <= tick_index
+        let offset = (tick_index as i64 - self.start_tick_index as i64).div_euclid(self.tick_spacing as i64);
+        let split = (offset + 1).clamp(0, self.tick_count() as i64) as usize;
+        let (down, up) = if a_to_b { (0..split, 0..0) } else { (0..0, split..self.tick_count()) };
+        down.rev().chain(up).filter_map(|i| self.tick(i)).filter(|t| t.initialized)
+    }
+
+    /// Next initialized tick in the swap direction, within this array
+    #[inline(always)]
+    pub fn next_initialized_tick(&self, tick_index: i32, a_to_b: bool) -> Option<Tick> {
+        self.initialized_ticks(tick_index, a_to_b).next()
+    }
+}
+
+/// Start index of the first array with an initialized tick in swap direction
+///
+/// Looks past empty arrays so swaps can be given arrays that hold liquidity
+/// instead of accounts the walk only steps through. `arrays` is raw
+/// tick-array account data in any order, read through `layout`; accounts
+/// that do not match it are skipped. Ticks are searched as in
+/// `initialized_ticks`, so the array holding `current_tick` counts only for
+/// ticks on the swap side of it. `None` when no array has liquidity in that
+/// direction.
+pub fn first_array_with_liquidity(
+    arrays: &[&[u8]],
+    layout: &TickArrayLayout,
+    tick_spacing: u16,
+    current_tick: i32,
+    a_to_b: bool,
+) -> Option<i32> {
+    let candidates = arrays.iter().filter_map(|data| {
+        let array = TickArrayView::new(data, layout, tick_spacing)?;
+        Some((array.next_initialized_tick(current_tick, a_to_b)?.index, array.start_tick_index))
+    });
+    let nearest = if a_to_b {
+        candidates.max_by_key(|&(tick, _)| tick)
+    } else {
+        candidates.min_by_key(|&(tick, _)| tick)
+    };
+    nearest.map(|(_, start_tick_index)| start_tick_index)
+}
+
+/// `sqrt(1.0001^tick)` as Q64.64, `None` outside `MIN_TICK..=MAX_TICK`
+///
+/// Same bit-by-bit product as Uniswap V3's `TickMath`, rounded down to
+/// Q64.64. Matches the programs' own tables to within a few units in the
+/// last place.
+pub fn sqrt_price_at_tick(tick: i32) -> Option<u128> {
+    // 2^128 / sqrt(1.0001)^(2^i)
+    const RATIOS: [u128; 19] = [
+        0xfffcb933bd6fad37aa2d162d1a594001,
+        0xfff97272373d413259a46990580e213a,
+        0xfff2e50f5f656932ef12357cf3c7fdcc,
+        0xffe5caca7e10e4e61c3624eaa0941cd0,
+        0xffcb9843d60f6159c9db58835c926644,
+        0xff973b41fa98c081472e6896dfb254c0,
+        0xff2ea16466c96a3843ec78b326b52861,
+        0xfe5dee046a99a2a811c461f1969c3053,
+        0xfcbe86c7900a88aedcffc83b479aa3a4,
+        0xf987a7253ac413176f2b074cf7815e54,
+        0xf3392b0822b70005940c7a398e4b70f3,
+        0xe7159475a2c29b7443b29c7fa6e889d9,
+        0xd097f3bdfd2022b8845ad8f792aa5825,
+        0xa9f746462d870fdf8a65dc1f90e061e5,
+        0x70d869a156d2a1b890bb3df62baf32f7,
+        0x31be135f97d08fd981231505542fcfa6,
+        0x9aa508b5b7a84e1c677de54f3e99bc9,
+        0x5d6af8dedb81196699c329225ee604,
+        0x2216e584f5fa1ea926041bedfe98,
+    ];
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return None;
+    }
+    let abs_tick = tick.unsigned_abs();
+    // Q128 price of -|tick|; `None` stands for exactly 1.0
+    let mut ratio: Option<u128> = None;
+    for (i, &factor) in RATIOS.iter().enumerate() {
+        if abs_tick & (1 << i) != 0 {
+            ratio = Some(match ratio {
+                Some(r) => U256::full_mul(r, factor).hi,
+                None => factor,
+            });
+        }
+    }
+    let Some(ratio) = ratio else {
+        return Some(1 << 64);
+    };
+    if tick < 0 {
+        return Some(ratio >> 64);
+    }
+    let (inverse, _) = U256 { hi: u128::MAX, lo: u128::MAX }.div_rem(U256::from_u128(ratio))?;
+    Some((inverse.hi << 64) | (inverse.lo >> 64))
+}
+
+/// Pool price and active liquidity a quote starts from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapState {
+    pub sqrt_price_x64: u128,
+    pub liquidity: u128,
+    pub tick_current: i32,
+}
+
+/// Denominator of the per-swap fee rate (1e6 = 100%)
+const FEE_RATE_DENOMINATOR: u128 = 1_000_000;
+
+/// `L * (upper - lower) / (upper * lower)`: token A between two prices
+#[inline(always)]
+fn amount_a_delta(lower: u128, upper: u128, liquidity: u128, round_up: bool) -> Option<u128> {
+    let per_upper = mul_div(liquidity, upper.saturating_sub(lower), upper, round_up)?;
+    mul_div(per_upper, 1 << 64, lower, round_up)
+}
+
+/// `L * (upper - lower)`: token B between two prices
+#[inline(always)]
+fn amount_b_delta(lower: u128, upper: u128, liquidity: u128, round_up: bool) -> Option<u128> {
+    mul_div(liquidity, upper.saturating_sub(lower), 1 << 64, round_up)
+}
+
+/// One swap step toward `target`: `(next_sqrt_price, amount_in_with_fee, amount_out)`
+fn swap_step(
+    sqrt_price: u128,
+    target: u128,
+    liquidity: u128,
+    remaining: u128,
+    fee_rate: u128,
+    a_to_b: bool,
+) -> Option<(u128, u128, u128)> {
+    let remaining_less_fee = mul_div(remaining, FEE_RATE_DENOMINATOR - fee_rate, FEE_RATE_DENOMINATOR, false)?;
+    let max_in = if a_to_b {
+        amount_a_delta(target, sqrt_price, liquidity, true)?
+    } else {
+        amount_b_delta(sqrt_price, target, liquidity, true)?
+    };
+    let (next, amount_in) = if remaining_less_fee >= max_in {
+        let fee = mul_div(max_in, fee_rate, FEE_RATE_DENOMINATOR - fee_rate, true)?;
+        (target, (max_in + fee).min(remaining))
+    } else if a_to_b {
+        // sqrt_price' = L * sqrt_price / (L + amount * sqrt_price), rounded up
+        let delta = mul_div(remaining_less_fee, sqrt_price, 1 << 64, false)?;
+        let next = mul_div(liquidity, sqrt_price, liquidity.checked_add(delta)?, true)?;
+        (next.max(target), remaining)
+    } else {
+        // sqrt_price' = sqrt_price + amount / L, rounded down
+        let next = sqrt_price.checked_add(mul_div(remaining_less_fee, 1 << 64, liquidity, false)?)?;
+        (next.min(target), remaining)
+    };
+    let amount_out = if a_to_b {
+        amount_b_delta(next, sqrt_price, liquidity, false)?
+    } else {
+        amount_a_delta(sqrt_price, next, liquidity, false)?
+    };
+    Some((next, amount_in, amount_out))
+}
+
+/// Exact-input output of a swap walking the given tick arrays
+///
+/// `arrays` is the raw data of the tick-array accounts the swap would
+/// pass, in any order; accounts that do not match `layout` are ignored.
+/// Charges `fee_rate` (hundredths of a bip) on each step like both
+/// programs, and updates the active liquidity at every initialized tick it
+/// crosses. `None` on invalid input or when the arrays run out before
+/// `amount_in` is used up.
+pub fn quote_exact_in(
+    state: SwapState,
+    fee_rate: u32,
+    arrays: &[&[u8]],
+    layout: &TickArrayLayout,
+    tick_spacing: u16,
+    amount_in: u64,
+    a_to_b: bool,
+) -> Option<u64> {
+    let fee_rate = fee_rate as u128;
+    if fee_rate >= FEE_RATE_DENOMINATOR || state.sqrt_price_x64 == 0 {
+        return None;
+    }
+    let views = || arrays.iter().filter_map(|data| TickArrayView::new(data, layout, tick_spacing));
+    // Last tick the arrays cover in swap direction
+    let last_tick = if a_to_b {
+        views().map(|array| array.start_tick_index).min()?
+    } else {
+        views().map(|array| array.start_tick_index + (array.tick_count() as i32 - 1) * tick_spacing as i32).max()?
+    };
+
+    let SwapState { mut sqrt_price_x64, mut liquidity, mut tick_current } = state;
+    let mut remaining = amount_in as u128;
+    let mut amount_out = 0u128;
+    while remaining > 0 {
+        let candidates = views().filter_map(|array| array.next_initialized_tick(tick_current, a_to_b));
+        let next = if a_to_b {
+            candidates.max_by_key(|tick| tick.index)
+        } else {
+            candidates.min_by_key(|tick| tick.index)
+        };
+        let target_tick = next.map_or(last_tick, |tick| tick.index).clamp(MIN_TICK, MAX_TICK);
+        if (a_to_b && target_tick > tick_current) || (!a_to_b && target_tick <= tick_current) {
+            return None;
+        }
+
+        let target = sqrt_price_at_tick(target_tick)?;
+        let (next_sqrt_price, step_in, step_out) =
+            swap_step(sqrt_price_x64, target, liquidity, remaining, fee_rate, a_to_b)?;
+        sqrt_price_x64 = next_sqrt_price;
+        remaining -= step_in;
+        amount_out = amount_out.checked_add(step_out)?;
+
+        if sqrt_price_x64 == target {
+            // Crossed `target_tick`
+            if let Some(tick) = next.filter(|tick| tick.index == target_tick) {
+                let net = if a_to_b { tick.liquidity_net.checked_neg()? } else { tick.liquidity_net };
+                liquidity = liquidity.checked_add_signed(net)?;
+            }
+            tick_current = if a_to_b { target_tick - 1 } else { target_tick };
+        }
+    }
+    amount_out.try_into().ok()
+}
+
+/// Start index of the `N`-tick array holding `tick_index`
+///
+/// Arrays start on multiples of `N * tick_spacing`; negative ticks round
+/// toward negative infinity, so `-1` lands in the array ending at `0`.
+/// `None` for a zero `tick_spacing`, which no pool has.
+#[inline(always)]
+pub fn tick_array_start_index<const N: usize>(tick_index: i32, tick_spacing: u16) -> Option<i32> {
+    let span = N as i32 * tick_spacing as i32;
+    Some(tick_index.checked_div_euclid(span)? * span)
+}
+
+/// Snap `tick` to a multiple of `tick_spacing`
+///
+/// Positions whose bounds are not multiples of the pool's spacing are
+/// rejected on-chain. Rounds toward negative infinity, or toward positive
+/// infinity with `round_up`; `-5` with spacing `10` becomes `-10` or `0`.
+/// Results stay on the usable ticks inside `MIN_TICK..=MAX_TICK`, so
+/// `-443_636` with spacing `64` becomes `-443_584`, not `-443_648`. A zero
+/// spacing returns `tick` unchanged.
+#[inline(always)]
+pub fn round_tick_to_spacing(tick: i32, tick_spacing: u16, round_up: bool) -> i32 {
+    if tick_spacing == 0 {
+        return tick;
+    }
+    let spacing = tick_spacing as i32;
+    let max_usable = MAX_TICK / spacing * spacing;
+    let tick = tick.clamp(MIN_TICK, MAX_TICK);
+    let down = tick.div_euclid(spacing) * spacing;
+    let rounded = if round_up && down != tick { down + spacing } else { down };
+    rounded.clamp(-max_usable, max_usable)
+}
+
+/// Lower and upper tick of a liquidity position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TickRange {
+    pub lower: i32,
+    pub upper: i32,
+}
+
+impl TickRange {
+    /// Range covering at least `lower..upper` on usable ticks
+    ///
+    /// Widens outward: `lower` rounds down and `upper` rounds up.
+    #[inline(always)]
+    pub fn snapped(lower: i32, upper: i32, tick_spacing: u16) -> Self {
+        Self {
+            lower: round_tick_to_spacing(lower, tick_spacing, false),
+            upper: round_tick_to_spacing(upper, tick_spacing, true),
+        }
+    }
+
+    /// Whether both ticks sit on `tick_spacing` and `lower < upper`
+    #[inline(always)]
+    pub fn is_valid(&self, tick_spacing: u16) -> bool {
+        let spacing = tick_spacing as i32;
+        spacing != 0 && self.lower < self.upper && self.lower % spacing == 0 && self.upper % spacing == 0
+    }
+
+    /// Start indices of the `N`-tick arrays holding the lower and upper tick
+    ///
+    /// `None` for a zero `tick_spacing`.
+    #[inline(always)]
+    pub fn tick_array_start_indices<const N: usize>(&self, tick_spacing: u16) -> Option<(i32, i32)> {
+        Some((
+            tick_array_start_index::<N>(self.lower, tick_spacing)?,
+            tick_array_start_index::<N>(self.upper, tick_spacing)?,
+        ))
+    }
+}
+
+/// Arguments for amm_v3 `open_position`
+///
+/// Raydium CLMM and its PancakeSwap and Byreal forks share this layout and
+/// re-export it.
+#[derive(Clone, Copy)]
+#[repr(C, packed)]
+pub struct OpenPositionArgs {
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub tick_array_lower_start_index: i32,
+    pub tick_array_upper_start_index: i32,
+    pub liquidity: u128,
+    pub amount_0_max: u64,
+    pub amount_1_max: u64,
+}
+
+/// amm_v3 `open_position` args over `range` with `liquidity`
+///
+/// `range` is widened to the pool's `tick_spacing` with `TickRange::snapped`
+/// and the start indices of the `N`-tick arrays holding its bounds are
+/// derived from it; they must match the `tick_array_lower` /
+/// `tick_array_upper` accounts. `None` for a zero `tick_spacing`.
+#[inline(always)]
+pub fn open_position_args<const N: usize>(
+    range: TickRange,
+    tick_spacing: u16,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+) -> Option<OpenPositionArgs> {
+    let range = TickRange::snapped(range.lower, range.upper, tick_spacing);
+    let (lower_start, upper_start) = range.tick_array_start_indices::<N>(tick_spacing)?;
+    Some(OpenPositionArgs {
+        tick_lower_index: range.lower,
+        tick_upper_index: range.upper,
+        tick_array_lower_start_index: lower_start,
+        tick_array_upper_start_index: upper_start,
+        liquidity,
+        amount_0_max,
+        amount_1_max,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    /// 8 ticks of 33 bytes: initialized (bool), liquidity_net, liquidity_gross
+    const LAYOUT: TickArrayLayout = TickArrayLayout {
+        discriminator: [7; 8],
+        start_tick_index_offset: 8,
+        ticks_offset: 12,
+        tick_size: 33,
+        tick_count: 8,
+        liquidity_net_offset: 1,
+        liquidity_gross_offset: 17,
+        initialized_offset: Some(0),
+    };
+
+    const ARRAY_LEN: usize = 12 + 8 * 33;
+
+    /// Spacing-10 array at `start_tick_index` with `(position, liquidity_net)` ticks
+    fn array_data(start_tick_index: i32, ticks: &[(usize, i128)]) -> [u8; ARRAY_LEN] {
+        let mut data = [0u8; ARRAY_LEN];
+        data[0..8].copy_from_slice(&LAYOUT.discriminator);
+        data[8..12].copy_from_slice(&start_tick_index.to_le_bytes());
+        for &(i, net) in ticks {
+            let o = 12 + i * 33;
+            data[o] = 1;
+            data[o + 1..o + 17].copy_from_slice(&net.to_le_bytes());
+            data[o + 17..o + 33].copy_from_slice(&net.unsigned_abs().to_le_bytes());
+        }
+        data
+    }
+
+    fn view(data: &[u8]) -> TickArrayView<'_> {
+        TickArrayView::new(data, &LAYOUT, 10).unwrap()
+    }
+
+    #[test]
+    fn test_next_initialized_tick_down() {
+        let data = array_data(-40, &[(1, 100), (4, 100), (6, 100)]);
+        let array = view(&data);
+        // Inclusive of the current tick when moving down
+        assert_eq!(array.next_initialized_tick(0, true).map(|t| t.index), Some(0));
+        assert_eq!(array.next_initialized_tick(-1, true).map(|t| t.index), Some(-30));
+        assert_eq!(array.next_initialized_tick(-31, true), None);
+
+        let mut walk = array.initialized_ticks(35, true).map(|t| t.index);
+        assert_eq!((walk.next(), walk.next(), walk.next(), walk.next()), (Some(20), Some(0), Some(-30), None));
+    }
+
+    #[test]
+    fn test_next_initialized_tick_up() {
+        let data = array_data(-40, &[(1, 100), (4, -100), (6, 100)]);
+        let array = view(&data);
+        // Exclusive of the current tick when moving up
+        assert_eq!(array.next_initialized_tick(0, false).map(|t| t.index), Some(20));
+        assert_eq!(array.next_initialized_tick(-100, false).map(|t| t.index), Some(-30));
+        assert_eq!(array.next_initialized_tick(20, false), None);
+        assert_eq!(array.tick(4), Some(Tick { index: 0, initialized: true, liquidity_net: -100, liquidity_gross: 100 }));
+        assert_eq!(array.tick(8), None);
+
+        assert!(array.contains(-40) && array.contains(39));
+        assert!(!array.contains(40) && !array.contains(-41));
+    }
+
+    #[test]
+    fn test_tick_array_view_rejects() {
+        let mut data = array_data(0, &[]);
+        assert!(TickArrayView::new(&data, &LAYOUT, 0).is_none());
+        assert!(TickArrayView::new(&data[..ARRAY_LEN - 1], &LAYOUT, 10).is_none());
+        data[0] ^= 1;
+        assert!(TickArrayView::new(&data, &LAYOUT, 10).is_none());
+    }
+
+    #[test]
+    fn test_first_array_with_liquidity() {
+        // Liquidity at -160 and 90 only; arrays passed out of order
+        let data = [
+            array_data(80, &[(1, 1)]),
+            array_data(-160, &[(0, 1)]),
+            array_data(0, &[]),
+            array_data(160, &[]),
+            array_data(-80, &[]),
+        ];
+        let arrays = data.each_ref().map(|data| &data[..]);
+        let first = |current_tick, a_to_b| first_array_with_liquidity(&arrays, &LAYOUT, 10, current_tick, a_to_b);
+        assert_eq!(first(15, true), Some(-160));
+        assert_eq!(first(15, false), Some(80));
+
+        // The current array counts only on the swap side of the current tick
+        assert_eq!(first(95, true), Some(80));
+        assert_eq!(first(90, true), Some(80));
+        assert_eq!(first(90, false), None);
+        assert_eq!(first(-161, true), None);
+        assert_eq!(first(-161, false), Some(-160));
+
+        // Accounts of another layout are skipped
+        assert_eq!(first_array_with_liquidity(&[&data[1][..100], &data[0]], &LAYOUT, 10, 15, true), None);
+        assert_eq!(first_array_with_liquidity(&[], &LAYOUT, 10, 0, false), None);
+    }
+
+    #[test]
+    fn test_sqrt_price_at_tick() {
+        assert_eq!(sqrt_price_at_tick(0), Some(1 << 64));
+        // sqrt(1.0001) and 1 / sqrt(1.0001), rounded down
+        assert_eq!(sqrt_price_at_tick(1), Some(18_447_666_387_855_959_850));
+        assert_eq!(sqrt_price_at_tick(-1), Some(18_445_821_805_675_392_311));
+        // Whirlpool's MIN_SQRT_PRICE; MAX_SQRT_PRICE is 79226673515401279992447579055
+        assert_eq!(sqrt_price_at_tick(MIN_TICK), Some(4_295_048_016));
+        let max = sqrt_price_at_tick(MAX_TICK).unwrap();
+        assert!(max.abs_diff(79_226_673_515_401_279_992_447_579_055) < 10);
+        assert_eq!(sqrt_price_at_tick(MIN_TICK - 1), None);
+        assert_eq!(sqrt_price_at_tick(MAX_TICK + 1), None);
+        assert!(sqrt_price_at_tick(-5_633) < sqrt_price_at_tick(-5_632));
+    }
+
+    const L: u128 = 1_000_000_000_000_000;
+
+    /// Price 1.0 at tick 0 with `L` active
+    const STATE: SwapState = SwapState { sqrt_price_x64: 1 << 64, liquidity: L, tick_current: 0 };
+
+    #[test]
+    fn test_quote_exact_in_within_range() {
+        // Position over -30..30 holding all of L
+        let (below, above) = (array_data(-80, &[(5, L as i128)]), array_data(0, &[(3, -(L as i128))]));
+        let arrays: [&[u8]; 2] = [&above, &below];
+        // Same as the single-range closed form at 0.25%
+        assert_eq!(quote_exact_in(STATE, 2_500, &arrays, &LAYOUT, 10, 1_000_000, true), Some(997_499));
+        assert_eq!(quote_exact_in(STATE, 2_500, &arrays, &LAYOUT, 10, 1_000_000, false), Some(997_499));
+        assert_eq!(quote_exact_in(STATE, 1_000_000, &arrays, &LAYOUT, 10, 1_000_000, true), None);
+        assert_eq!(quote_exact_in(STATE, 2_500, &[], &LAYOUT, 10, 1_000_000, true), None);
+    }
+
+    #[test]
+    fn test_quote_exact_in_crosses_ticks() {
+        // L / 4 over -70..70 plus 3L / 4 over -30..30
+        let (wide, narrow) = ((L / 4) as i128, (3 * L / 4) as i128);
+        let below = array_data(-80, &[(1, wide), (5, narrow)]);
+        let above = array_data(0, &[(3, -narrow), (7, -wide)]);
+        let quote = |arrays: &[&[u8]], amount| quote_exact_in(STATE, 0, arrays, &LAYOUT, 10, amount, true);
+
+        // Without the narrow position's ticks all of L stays active
+        let flat = array_data(-80, &[(1, L as i128)]);
+        // 1.5e12 takes the price to about -30, where 3/4 of the liquidity
+        // drops out; the next 3e11 trades against L / 4 only
+        let crossed = quote(&[&below, &above], 1_800_000_000_000).unwrap();
+        let uncrossed = quote(&[&flat, &above], 1_800_000_000_000).unwrap();
+        assert!(crossed < uncrossed);
+        // Closed form in floating point: 1_796_499_310_630
+        assert_eq!(crossed, 1_796_499_310_627);
+        assert!(crossed > quote(&[&below, &above], 1_500_000_000_000).unwrap());
+
+        // Past -80 the arrays run out
+        assert_eq!(quote(&[&below, &above], 100_000_000_000_000), None);
+        // -70 is beyond the arrays given
+        assert_eq!(quote(&[&above], 4_000_000_000_000), None);
+    }
+
+    #[test]
+    fn test_tick_range() {
+        assert_eq!(tick_array_start_index::<60>(0, 10), Some(0));
+        assert_eq!(tick_array_start_index::<60>(599, 10), Some(0));
+        assert_eq!(tick_array_start_index::<60>(600, 10), Some(600));
+        assert_eq!(tick_array_start_index::<60>(-1, 10), Some(-600));
+        assert_eq!(tick_array_start_index::<60>(-600, 10), Some(-600));
+        assert_eq!(tick_array_start_index::<60>(-600, 0), None);
+
+        let range = TickRange { lower: -500, upper: 1200 };
+        assert!(range.is_valid(10));
+        assert!(!range.is_valid(64) && !range.is_valid(0));
+        assert!(!TickRange { lower: 10, upper: 10 }.is_valid(10));
+        assert_eq!(range.tick_array_start_indices::<60>(10), Some((-600, 1200)));
+        assert_eq!(range.tick_array_start_indices::<60>(0), None);
+    }
+
+    #[test]
+    fn test_round_tick_to_spacing() {
+        assert_eq!(round_tick_to_spacing(25, 10, false), 20);
+        assert_eq!(round_tick_to_spacing(25, 10, true), 30);
+        assert_eq!(round_tick_to_spacing(-5, 10, false), -10);
+        assert_eq!(round_tick_to_spacing(-5, 10, true), 0);
+        assert_eq!(round_tick_to_spacing(-20, 10, false), -20);
+        assert_eq!(round_tick_to_spacing(-20, 10, true), -20);
+        // Clamped to the usable ticks inside MIN_TICK..=MAX_TICK
+        assert_eq!(round_tick_to_spacing(MIN_TICK, 64, false), -443_584);
+        assert_eq!(round_tick_to_spacing(MAX_TICK, 64, true), 443_584);
+        assert_eq!(round_tick_to_spacing(i32::MAX, 64, true), 443_584);
+        assert_eq!(round_tick_to_spacing(MIN_TICK, 1, false), MIN_TICK);
+        assert_eq!(round_tick_to_spacing(7, 0, true), 7);
+
+        let range = TickRange::snapped(-95, 1_201, 10);
+        assert_eq!(range, TickRange { lower: -100, upper: 1_210 });
+        assert!(range.is_valid(10));
+    }
+
+    #[test]
+    fn test_open_position_args() {
+        // Bounds widen to spacing 60; 60-tick arrays span 3600 ticks
+        let args =
+            open_position_args::<60>(TickRange { lower: -130, upper: 3_590 }, 60, 1u128 << 64, 1_000, 2_000).unwrap();
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 48);
+        assert_eq!(&bytes[0..4], &(-180i32).to_le_bytes());
+        assert_eq!(&bytes[4..8], &3_600i32.to_le_bytes());
+        assert_eq!(&bytes[8..12], &(-3_600i32).to_le_bytes());
+        assert_eq!(&bytes[12..16], &3_600i32.to_le_bytes());
+        assert_eq!(&bytes[16..32], &(1u128 << 64).to_le_bytes());
+        assert_eq!(&bytes[32..40], &1_000u64.to_le_bytes());
+        assert_eq!(&bytes[40..48], &2_000u64.to_le_bytes());
+
+        // Full range stays inside the tick bounds
+        let args = open_position_args::<60>(TickRange { lower: MIN_TICK, upper: MAX_TICK }, 64, 1, 0, 0).unwrap();
+        assert_eq!({ args.tick_lower_index }, -443_584);
+        assert_eq!({ args.tick_upper_index }, 443_584);
+        assert!(open_position_args::<60>(TickRange { lower: -130, upper: 3_590 }, 0, 1, 0, 0).is_none());
+    }
+}
@@ -0,0 +1,2302 @@
+//! Shared helpers used across DEX modules
+//!
+//! Types and utilities that are not tied to a single program:
+//! - `Dex`: identifies each supported program; `jupiter_label` /
+//!   `dex_from_jupiter_label` map it to Jupiter route plan labels
+//! - `account_spec`: canonical account lists of the reverse-engineered programs
+//! - `CpiError`: pre-flight check failures, convertible to `ProgramError`;
+//!   `is_retryable` classifies DEX error codes for resubmission;
+//!   `validate_token_program` catches Token / Token-2022 mixups,
+//!   `require_decimals` a wrong decimals assumption, `validate_writable`
+//!   read-only accounts in writable slots, `assert_min_out_reasonable` a
+//!   near-zero slippage floor; `pool_tvl_estimate` values
+//!   reserves in USD for minimum-liquidity filters
+//! - `QuoteResult`: output, fees, impact and slippage floor of a quote;
+//!   `max_in_for_exact_out` bounds the input of exact-out swaps,
+//!   `quote_both` quotes a constant-product pool in both directions
+//! - `Lamports` / `TokenAmount`: keep SOL and token amounts apart;
+//!   `checked_sum` totals per-token amount lists, `checked_reserve_after`
+//!   steps reserves through multi-step quotes
+//! - Bounds-checked byte readers (`read_pubkey`, `read_u64_le`, ...) for
+//!   account parsers
+//! - Sysvar parsers (`parse_clock`, `find_prior_swap`) for reading state
+//!   without a syscall, and `minimum_balance` for funding new accounts
+//! - Borsh-compatible option types for `#[repr(C, packed)]` argument structs
+//! - SPL token account / mint readers (`Mint`, Token-2022 `transfer_fee`)
+//!   shared by the quote helpers; `is_native_sol_account` spots WSOL accounts
+//! - `PdaCache`: memoizes derived addresses within one instruction;
+//!   `parse_or_derive_authority` reads a stored pool authority or derives it
+//! - `u128_to_words_le` / `words_le_to_u128`: u128 <-> two u64 words
+//! - `math`: fixed-point helpers for pool pricing
+//! - `fee`: fee rates over bps / 1e6 / 1e9 denominators, normalized to bps
+//! - `decay`: exponential penalty for the age of oracle or pool data
+//! - `hash`: `const` SHA-256 and Anchor discriminators
+//! - `invoke_signed`: the CPI entry point of every module; `log` prints each
+//!   instruction with the `debug-logs` feature; `InstructionBuilder` and
+//!   `append_remaining` assemble variable-length account lists;
+//!   `pda_signer!` builds PDA signers from seeds and a bump,
+//!   `require_accounts!` checks counts of variable-length account lists
+
+pub mod decay;
+pub mod fee;
+pub mod hash;
+pub mod log;
+pub mod math;
+
+use pinocchio::{
+    cpi::Signer,
+    error::ProgramError,
+    instruction::{InstructionAccount, InstructionView},
+    AccountView, Address, ProgramResult,
+};
+
+// ============================================
+// DEX Identifier
+// ============================================
+
+/// Supported DEX programs, one variant per module
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dex {
+    Bonkswap,
+    BoopFun,
+    Byreal,
+    Carrot,
+    DefiTuna,
+    DynamicBondingCurve,
+    GooseFxGamma,
+    Guacswap,
+    Heaven,
+    HeliumNetwork,
+    HumidiFi,
+    MetaDao,
+    Meteora,
+    MeteoraDammV2,
+    MeteoraDlmm,
+    Moonit,
+    OpenbookV2,
+    PancakeSwap,
+    Perena,
+    Perps,
+    PumpFun,
+    PumpFunAmm,
+    RaydiumAmm,
+    RaydiumClmm,
+    RaydiumCp,
+    RaydiumLaunchlab,
+    SaberDecimals,
+    SolfiV2,
+    StabbleClmm,
+    StabbleStableSwap,
+    StabbleWeightedSwap,
+    Vertigo,
+    Virtuals,
+    Whirlpool,
+    Woofi,
+}
+
+impl Dex {
+    /// All supported DEXes, in module order
+    pub const ALL: [Dex; 35] = [
+        Dex::Bonkswap,
+        Dex::BoopFun,
+        Dex::Byreal,
+        Dex::Carrot,
+        Dex::DefiTuna,
+        Dex::DynamicBondingCurve,
+        Dex::GooseFxGamma,
+        Dex::Guacswap,
+        Dex::Heaven,
+        Dex::HeliumNetwork,
+        Dex::HumidiFi,
+        Dex::MetaDao,
+        Dex::Meteora,
+        Dex::MeteoraDammV2,
+        Dex::MeteoraDlmm,
+        Dex::Moonit,
+        Dex::OpenbookV2,
+        Dex::PancakeSwap,
+        Dex::Perena,
+        Dex::Perps,
+        Dex::PumpFun,
+        Dex::PumpFunAmm,
+        Dex::RaydiumAmm,
+        Dex::RaydiumClmm,
+        Dex::RaydiumCp,
+        Dex::RaydiumLaunchlab,
+        Dex::SaberDecimals,
+        Dex::SolfiV2,
+        Dex::StabbleClmm,
+        Dex::StabbleStableSwap,
+        Dex::StabbleWeightedSwap,
+        Dex::Vertigo,
+        Dex::Virtuals,
+        Dex::Whirlpool,
+        Dex::Woofi,
+    ];
+
+    /// Program ID of this DEX
+    #[inline(always)]
+    pub const fn program_id(self) -> &'static Address {
+        match self {
+            Dex::Bonkswap => &crate::bonkswap::ID,
+            Dex::BoopFun => &crate::boop_fun::ID,
+            Dex::Byreal => &crate::byreal::ID,
+            Dex::Carrot => &crate::carrot::ID,
+            Dex::DefiTuna => &crate::defituna::ID,
+            Dex::DynamicBondingCurve => &crate::dynamic_bonding_curve::ID,
+            Dex::GooseFxGamma => &crate::goosefx_gamma::ID,
+            Dex::Guacswap => &crate::guacswap::ID,
+            Dex::Heaven => &crate::heaven::ID,
+            Dex::HeliumNetwork => &crate::helium_network::ID,
+            Dex::HumidiFi => &crate::humidifi::PROGRAM_ID,
+            Dex::MetaDao => &crate::metadao::ID,
+            Dex::Meteora => &crate::meteora::ID,
+            Dex::MeteoraDammV2 => &crate::meteora_damm_v2::ID,
+            Dex::MeteoraDlmm => &crate::meteora_dlmm::ID,
+            Dex::Moonit => &crate::moonit::ID,
+            Dex::OpenbookV2 => &crate::openbook_v2::ID,
+            Dex::PancakeSwap => &crate::pancakeswap::ID,
+            Dex::Perena => &crate::perena::ID,
+            Dex::Perps => &crate::perps::ID,
+            Dex::PumpFun => &crate::pump_fun::ID,
+            Dex::PumpFunAmm => &crate::pump_fun_amm::ID,
+            Dex::RaydiumAmm => &crate::raydium_amm::PROGRAM_ID,
+            Dex::RaydiumClmm => &crate::raydium_clmm::ID,
+            Dex::RaydiumCp => &crate::raydium_cp::ID,
+            Dex::RaydiumLaunchlab => &crate::raydium_launchlab::ID,
+            Dex::SaberDecimals => &crate::saber_decimals::ID,
+            Dex::SolfiV2 => &crate::solfi_v2::PROGRAM_ID,
+            Dex::StabbleClmm => &crate::stabble_clmm::ID,
+            Dex::StabbleStableSwap => &crate::stabble_stable_swap::ID,
+            Dex::StabbleWeightedSwap => &crate::stabble_weighted_swap::ID,
+            Dex::Vertigo => &crate::vertigo::ID,
+            Dex::Virtuals => &crate::virtuals::ID,
+            Dex::Whirlpool => &crate::whirlpool::ID,
+            Dex::Woofi => &crate::woofi::ID,
+        }
+    }
+
+    /// Look up the DEX owning a program ID
+    #[inline]
+    pub fn from_program_id(program_id: &Address) -> Option<Self> {
+        Self::ALL.into_iter().find(|dex| dex.program_id() == program_id)
+    }
+}
+
+/// Jupiter AMM label of a DEX, as shown in Jupiter route plans
+///
+/// Taken from Jupiter's program-id-to-label map; labels are display names
+/// and may be renamed upstream, so check unknown ones against the live map.
+#[inline]
+pub const fn jupiter_label(dex: Dex) -> &'static str {
+    match dex {
+        Dex::Bonkswap => "Bonkswap",
+        Dex::BoopFun => "Boop.fun",
+        Dex::Byreal => "Byreal",
+        Dex::Carrot => "Carrot",
+        Dex::DefiTuna => "DefiTuna",
+        Dex::DynamicBondingCurve => "Dynamic Bonding Curve",
+        Dex::GooseFxGamma => "GooseFX GAMMA",
+        Dex::Guacswap => "Guacswap",
+        Dex::Heaven => "Heaven",
+        Dex::HeliumNetwork => "Helium Network",
+        Dex::HumidiFi => "HumidiFi",
+        Dex::MetaDao => "MetaDAO",
+        Dex::Meteora => "Meteora",
+        Dex::MeteoraDammV2 => "Meteora DAMM v2",
+        Dex::MeteoraDlmm => "Meteora DLMM",
+        Dex::Moonit => "Moonit",
+        Dex::OpenbookV2 => "OpenBook V2",
+        Dex::PancakeSwap => "PancakeSwap",
+        Dex::Perena => "Perena",
+        Dex::Perps => "Perps",
+        Dex::PumpFun => "Pump.fun",
+        Dex::PumpFunAmm => "Pump.fun Amm",
+        Dex::RaydiumAmm => "Raydium",
+        Dex::RaydiumClmm => "Raydium CLMM",
+        Dex::RaydiumCp => "Raydium CP",
+        Dex::RaydiumLaunchlab => "Raydium Launchlab",
+        Dex::SaberDecimals => "Saber (Decimals)",
+        Dex::SolfiV2 => "SolFi V2",
+        Dex::StabbleClmm => "Stabble CLMM",
+        Dex::StabbleStableSwap => "Stabble Stable Swap",
+        Dex::StabbleWeightedSwap => "Stabble Weighted Swap",
+        Dex::Vertigo => "Vertigo",
+        Dex::Virtuals => "Virtuals",
+        Dex::Whirlpool => "Whirlpool",
+        Dex::Woofi => "Woofi",
+    }
+}
+
+/// DEX behind a Jupiter AMM label, ignoring ASCII case
+///
+/// `None` for venues without a module here, e.g. "SolFi" (v1).
+#[inline]
+pub fn dex_from_jupiter_label(label: &str) -> Option<Dex> {
+    Dex::ALL.into_iter().find(|&dex| jupiter_label(dex).eq_ignore_ascii_case(label))
+}
+
+// ============================================
+// Account Specs
+// ============================================
+
+/// Instructions with a codified account spec
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Op {
+    Swap,
+    /// Second swap layout, for programs that have one (HumidiFi)
+    SwapV2,
+}
+
+/// Name and access of one instruction account, in instruction order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccountRole {
+    pub name: &'static str,
+    pub writable: bool,
+    pub signer: bool,
+}
+
+impl AccountRole {
+    pub const fn readonly(name: &'static str) -> Self {
+        Self { name, writable: false, signer: false }
+    }
+
+    pub const fn writable(name: &'static str) -> Self {
+        Self { name, writable: true, signer: false }
+    }
+
+    pub const fn readonly_signer(name: &'static str) -> Self {
+        Self { name, writable: false, signer: true }
+    }
+
+    pub const fn writable_signer(name: &'static str) -> Self {
+        Self { name, writable: true, signer: true }
+    }
+}
+
+/// Canonical account list of `op` on `dex`
+///
+/// Lets tools validate account arrays or generate bindings without
+/// duplicating the hand-written `*Accounts` structs. Empty when no spec is
+/// recorded for the pair yet.
+pub fn account_spec(dex: Dex, op: Op) -> &'static [AccountRole] {
+    match (dex, op) {
+        (Dex::SolfiV2, Op::Swap) => &crate::solfi_v2::SWAP_ACCOUNT_SPEC,
+        (Dex::HumidiFi, Op::Swap) => &crate::humidifi::SWAP_V1_ACCOUNT_SPEC,
+        (Dex::HumidiFi, Op::SwapV2) => &crate::humidifi::SWAP_V2_ACCOUNT_SPEC,
+        _ => &[],
+    }
+}
+
+// ============================================
+// CPI Invocation
+// ============================================
+
+/// `pinocchio::cpi::invoke_signed`, logging the instruction with `debug-logs`
+///
+/// Every module invokes through here so one feature flag covers all CPIs.
+#[inline(always)]
+pub fn invoke_signed<const ACCOUNTS: usize>(
+    instruction: &InstructionView,
+    account_views: &[&AccountView; ACCOUNTS],
+    signers: &[Signer],
+) -> ProgramResult {
+    log::log_instruction(instruction);
+    #[cfg(test)]
+    record_cpi(instruction);
+    pinocchio::cpi::invoke_signed::<ACCOUNTS>(instruction, account_views, signers)
+}
+
+/// `pinocchio::cpi::invoke_signed_with_bounds`, logging like [`invoke_signed`]
+#[inline(always)]
+pub fn invoke_signed_with_bounds<const MAX_ACCOUNTS: usize>(
+    instruction: &InstructionView,
+    account_views: &[&AccountView],
+    signers: &[Signer],
+) -> ProgramResult {
+    log::log_instruction(instruction);
+    #[cfg(test)]
+    record_cpi(instruction);
+    pinocchio::cpi::invoke_signed_with_bounds::<MAX_ACCOUNTS>(instruction, account_views, signers)
+}
+
+#[cfg(test)]
+std::thread_local! {
+    static LAST_CPI: core::cell::RefCell<Option<(Address, std::vec::Vec<u8>)>> = const { core::cell::RefCell::new(None) };
+}
+
+/// Keep the program and data of the CPI this thread just issued
+///
+/// Off-chain the CPI itself is a no-op that returns `Ok`, so tests assert
+/// what would have been sent instead.
+#[cfg(test)]
+fn record_cpi(instruction: &InstructionView) {
+    let cpi = (instruction.program_id.clone(), instruction.data.to_vec());
+    LAST_CPI.with(|last| *last.borrow_mut() = Some(cpi));
+}
+
+/// Program and instruction data of the last CPI issued on this thread
+#[cfg(test)]
+pub(crate) fn last_cpi() -> (Address, std::vec::Vec<u8>) {
+    LAST_CPI.with(|last| last.borrow_mut().take()).expect("no CPI issued")
+}
+
+/// Placeholder for the unused tail of an `InstructionBuilder`
+static UNUSED_ACCOUNT: Address = Address::new_from_array([0; 32]);
+
+/// Account list of up to `MAX` entries for variable-length instructions
+///
+/// Holds an instruction's fixed accounts followed by a runtime number of
+/// remaining accounts (event heaps, extra tick arrays, transfer-hook
+/// accounts); `accounts()` is the slice to put in the `InstructionView`
+/// and pairs with `invoke_signed_with_bounds::<MAX>`.
+pub struct InstructionBuilder<'a, const MAX: usize> {
+    accounts: [InstructionAccount<'a>; MAX],
+    len: usize,
+}
+
+impl<'a, const MAX: usize> InstructionBuilder<'a, MAX> {
+    /// Start from an instruction's fixed accounts
+    ///
+    /// Fails with `InvalidArgument` when `fixed` does not fit in `MAX`.
+    #[inline(always)]
+    pub fn new(fixed: &[InstructionAccount<'a>]) -> Result<Self, ProgramError> {
+        if fixed.len() > MAX {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let accounts = core::array::from_fn(|i| match fixed.get(i) {
+            Some(account) => account.clone(),
+            None => InstructionAccount::readonly(&UNUSED_ACCOUNT),
+        });
+        Ok(Self { accounts, len: fixed.len() })
+    }
+
+    /// The accounts pushed so far, in order
+    #[inline(always)]
+    pub fn accounts(&self) -> &[InstructionAccount<'a>] {
+        &self.accounts[..self.len]
+    }
+
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Append `(address, is_writable, is_signer)` remaining accounts to `base`
+///
+/// Appends all of `extras` or none: fails with `InvalidArgument`, leaving
+/// `base` unchanged, when they would overflow its capacity.
+#[inline(always)]
+pub fn append_remaining<'a, const MAX: usize>(
+    base: &mut InstructionBuilder<'a, MAX>,
+    extras: &[(&'a Address, bool, bool)],
+) -> ProgramResult {
+    if extras.len() > MAX - base.len {
+        return Err(ProgramError::InvalidArgument);
+    }
+    for &(address, is_writable, is_signer) in extras {
+        base.accounts[base.len] = InstructionAccount::new(address, is_writable, is_signer);
+        base.len += 1;
+    }
+    Ok(())
+}
+
+/// Bind `$name` to a PDA `Signer` for `seeds` followed by `bump`
+///
+/// Expands to `let` statements in the calling block, so the seed array
+/// outlives the signer without being spelled out by hand:
+///
+/// ```ignore
+/// pda_signer!(signer = [b"vault", market.address().as_ref()], bump);
+/// solfi_v2::swap(&accounts, &args, &[signer])?;
+/// ```
+#[macro_export]
+macro_rules! pda_signer {
+    ($name:ident = [$($seed:expr),* $(,)?], $bump:expr) => {
+        let bump = [$bump];
+        let seeds = [$($crate::common::__cpi::Seed::from($seed),)* $crate::common::__cpi::Seed::from(&bump)];
+        let $name = $crate::common::__cpi::Signer::from(&seeds);
+    };
+}
+pub use crate::pda_signer;
+
+/// Return `CpiError::MissingAccount` unless `accounts` holds at least `min`
+///
+/// For remaining accounts and optional trailing accounts (referrals,
+/// pricing accounts) whose count is only known at runtime. Works in any
+/// function whose error type converts from `CpiError`:
+///
+/// ```ignore
+/// require_accounts!(remaining_accounts, 2 * tokens.len());
+/// ```
+#[macro_export]
+macro_rules! require_accounts {
+    ($accounts:expr, $min:expr) => {
+        if $accounts.len() < $min {
+            return Err($crate::common::CpiError::MissingAccount.into());
+        }
+    };
+}
+pub use crate::require_accounts;
+
+#[doc(hidden)]
+pub use pinocchio::cpi as __cpi;
+
+// ============================================
+// Errors
+// ============================================
+
+/// Failures from the pre-flight checks in this crate
+///
+/// Converted to `ProgramError::Custom` with the discriminant as the code.
+/// Codes start at 9000 to stay clear of Anchor's built-in (< 6000) and
+/// user (6000+) ranges of the calling program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum CpiError {
+    /// A pool reserve is zero
+    EmptyReserve = 9000,
+    /// A pool reserve is non-zero but below the required minimum
+    InsufficientReserve,
+    /// Two accounts that must differ have the same address
+    DuplicateAccount,
+    /// The trade would move the pool price more than allowed
+    PriceImpactTooHigh,
+    /// The Clock sysvar data could not be parsed
+    InvalidClock,
+    /// The current time is past the caller's deadline
+    DeadlineExceeded,
+    /// A swap delivered less than its `min_out`
+    SlippageExceeded,
+    /// A token account or mint is not owned by the `token_program` passed
+    IncorrectTokenProgram,
+    /// Fewer accounts were passed than the instruction needs
+    MissingAccount,
+    /// A mint's decimals differ from the caller's assumption, or the mint
+    /// could not be parsed
+    DecimalsMismatch,
+    /// An account the instruction writes was passed read-only
+    AccountNotWritable,
+    /// A `min_out` sits further below the quote than the caller allows
+    MinOutTooLow,
+}
+
+impl From<CpiError> for ProgramError {
+    #[inline(always)]
+    fn from(error: CpiError) -> Self {
+        ProgramError::Custom(error as u32)
+    }
+}
+
+/// Whether a swap that failed with custom `error_code` on `dex` can succeed
+/// if resubmitted unchanged
+///
+/// Retryable errors are transient: stale oracle or pool data that the
+/// next slot's update fixes. Everything else is fatal, including slippage
+/// (the quote has to be redone) and any code not known here.
+pub fn is_retryable(error_code: u32, dex: Dex) -> bool {
+    match dex {
+        // Stale data (0x17) and oracle expiry (23) share the same code
+        Dex::SolfiV2 => {
+            error_code == crate::solfi_v2::ERROR_STALE_DATA || error_code == crate::solfi_v2::ERROR_ORACLE_EXPIRED
+        }
+        // OracleStalePrice
+        Dex::Carrot => error_code == 6003,
+        // OracleStale
+        Dex::OpenbookV2 => error_code == 6024,
+        // StaleOraclePrice
+        Dex::Perps => error_code == 6003,
+        _ => false,
+    }
+}
+
+// ============================================
+// Amounts
+// ============================================
+
+/// An amount of native SOL, in lamports
+///
+/// Distinct from `TokenAmount` so a lamport balance (rent, wrap amount) is
+/// never passed where token base units are expected. Conversions are
+/// explicit: construct with `Lamports(n)`, read with `.get()`, and use
+/// `as_wsol` when the lamports become WSOL.
+///
+/// ```compile_fail
+/// use dex_pinocchio_cpi::common::{Lamports, TokenAmount};
+/// let wrapped: TokenAmount = Lamports(1_000);
+/// ```
+///
+/// ```compile_fail
+/// use dex_pinocchio_cpi::common::Lamports;
+/// let lamports: Lamports = 1_000u64;
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Lamports(pub u64);
+
+impl Lamports {
+    #[inline(always)]
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    #[inline(always)]
+    pub const fn checked_add(self, other: Lamports) -> Option<Lamports> {
+        match self.0.checked_add(other.0) {
+            Some(sum) => Some(Lamports(sum)),
+            None => None,
+        }
+    }
+
+    /// The same amount as WSOL, which has 9 decimals like SOL
+    #[inline(always)]
+    pub const fn as_wsol(self) -> TokenAmount {
+        TokenAmount(self.0)
+    }
+}
+
+/// An amount of an SPL token, in the mint's base units
+///
+/// ```compile_fail
+/// use dex_pinocchio_cpi::common::{Lamports, TokenAmount};
+/// let rent: Lamports = TokenAmount(2_039_280);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct TokenAmount(pub u64);
+
+impl TokenAmount {
+    #[inline(always)]
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    #[inline(always)]
+    pub const fn saturating_sub(self, other: TokenAmount) -> TokenAmount {
+        TokenAmount(self.0.saturating_sub(other.0))
+    }
+}
+
+/// Sum of `amounts`, or `None` if it overflows a u64
+///
+/// For the per-token amount lists of multi-token deposits and withdrawals
+/// (`stabble_weighted_swap::deposit`, ...), where the total is only used
+/// for validation and logging since the tokens have different units.
+#[inline(always)]
+pub fn checked_sum(amounts: &[u64]) -> Option<u64> {
+    amounts.iter().try_fold(0u64, |sum, amount| sum.checked_add(*amount))
+}
+
+/// Reserve after adding (`is_add`) or removing `delta`, or `None` on
+/// overflow or underflow
+///
+/// For simulating pool state across the steps of a quote (DLMM bin walks,
+/// multi-hop routes). A step that would drain more than the reserve holds
+/// must end the quote, not clamp to zero and keep going.
+#[inline(always)]
+pub fn checked_reserve_after(reserve: u64, delta: u64, is_add: bool) -> Option<u64> {
+    if is_add { reserve.checked_add(delta) } else { reserve.checked_sub(delta) }
+}
+
+// ============================================
+// Pre-flight Checks
+// ============================================
+
+/// Reject swaps against empty or dust pools
+///
+/// Quoting against a near-empty pool gives a price that moves almost
+/// entirely on the first unit of input, so routers should skip pools where
+/// either side holds less than `min_reserve` (in that side's base units).
+#[inline(always)]
+pub fn reserve_sanity(reserve_in: u64, reserve_out: u64, min_reserve: u64) -> Result<(), CpiError> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CpiError::EmptyReserve);
+    }
+    if reserve_in < min_reserve || reserve_out < min_reserve {
+        return Err(CpiError::InsufficientReserve);
+    }
+    Ok(())
+}
+
+/// USD value of both sides of a pool, as x64 fixed-point
+///
+/// `price_*_usd_x64` is the USD price of one whole token (`10^decimals` base
+/// units) scaled by 2^64, as supplied by an off-chain feed. Rounded down and
+/// saturating at `u128::MAX`; compare against `min_usd as u128 * ONE_X64` to
+/// skip thin pools alongside `reserve_sanity`.
+#[inline(always)]
+pub fn pool_tvl_estimate(
+    reserve_a: u64,
+    decimals_a: u8,
+    price_a_usd_x64: u128,
+    reserve_b: u64,
+    decimals_b: u8,
+    price_b_usd_x64: u128,
+) -> u128 {
+    let side_value = |reserve: u64, decimals: u8, price_x64: u128| match 10u128.checked_pow(decimals as u32) {
+        Some(scale) => math::mul_div(reserve as u128, price_x64, scale, false).unwrap_or(u128::MAX),
+        // More decimals than a u64 reserve can reach one whole token
+        None => 0,
+    };
+    side_value(reserve_a, decimals_a, price_a_usd_x64)
+        .saturating_add(side_value(reserve_b, decimals_b, price_b_usd_x64))
+}
+
+/// Approximate price impact of a constant-product swap, in bps
+///
+/// The execution price of `amount_in` against `reserve_in` is worse than
+/// the spot price by `amount_in / (reserve_in + amount_in)`, independent of
+/// the output reserve and before fees. Rounded up; 10_000 for an empty pool.
+#[inline(always)]
+pub fn price_impact_bps(amount_in: u64, reserve_in: u64) -> u64 {
+    if amount_in == 0 {
+        return 0;
+    }
+    let denominator = reserve_in as u128 + amount_in as u128;
+    (amount_in as u128 * 10_000).div_ceil(denominator) as u64
+}
+
+/// Reject trades whose price impact exceeds `max_impact_bps`
+#[inline(always)]
+pub fn check_price_impact(amount_in: u64, reserve_in: u64, max_impact_bps: u64) -> Result<(), CpiError> {
+    if price_impact_bps(amount_in, reserve_in) > max_impact_bps {
+        return Err(CpiError::PriceImpactTooHigh);
+    }
+    Ok(())
+}
+
+/// Reject a `min_out` more than `max_deviation_bps` below `quoted_out`
+///
+/// Guards against swaps submitted with `min_out = 0` or a stale, far-off
+/// floor, which most programs accept as "no slippage limit". A floor
+/// exactly `max_deviation_bps` below the quote (rounded up) passes.
+#[inline(always)]
+pub fn assert_min_out_reasonable(min_out: u64, quoted_out: u64, max_deviation_bps: u16) -> Result<(), CpiError> {
+    let kept_bps = 10_000u128.saturating_sub(max_deviation_bps as u128);
+    let lowest = (quoted_out as u128 * kept_bps).div_ceil(10_000);
+    if (min_out as u128) < lowest {
+        return Err(CpiError::MinOutTooLow);
+    }
+    Ok(())
+}
+
+/// Reject calls where accounts that must differ share an address
+///
+/// Pass only the subset that must be unique, e.g. user source and user
+/// destination token accounts; some programs legitimately reuse an account
+/// in several slots.
+#[inline(always)]
+pub fn validate_distinct_accounts(accounts: &[&Address]) -> Result<(), CpiError> {
+    for (i, a) in accounts.iter().enumerate() {
+        if accounts[i + 1..].iter().any(|b| a == b) {
+            return Err(CpiError::DuplicateAccount);
+        }
+    }
+    Ok(())
+}
+
+/// Whether the runtime passed `view` as writable to the current instruction
+#[inline(always)]
+pub fn is_writable(view: &AccountView) -> bool {
+    view.is_writable()
+}
+
+/// Whether `view` signed the transaction
+///
+/// PDAs never do; they sign a CPI through seeds instead, so `false` here
+/// does not mean the account cannot sign an inner instruction.
+#[inline(always)]
+pub fn is_signer(view: &AccountView) -> bool {
+    view.is_signer()
+}
+
+/// Reject calls where an account the instruction writes is read-only here
+///
+/// `required` and `views` are the `to_instruction_accounts()` and
+/// `to_views()` of a module's account struct. A CPI cannot upgrade a
+/// read-only account to writable, so this catches the mistake before the
+/// runtime's privilege-escalation error. Signers are not compared, since
+/// PDAs sign through seeds.
+#[inline(always)]
+pub fn validate_writable(required: &[InstructionAccount], views: &[&AccountView]) -> Result<(), CpiError> {
+    if required.iter().zip(views).any(|(account, view)| account.is_writable && !is_writable(view)) {
+        return Err(CpiError::AccountNotWritable);
+    }
+    Ok(())
+}
+
+/// Reject calls where a token account or mint is not owned by `token_program`
+///
+/// Catches SPL Token / Token-2022 mixups before the CPI, where they would
+/// fail inside the DEX with a less specific error. `token_program` itself
+/// must be one of the two token programs.
+#[inline(always)]
+pub fn validate_token_program(token_program: &Address, accounts: &[&AccountView]) -> Result<(), CpiError> {
+    if token_program_for_mint(token_program).is_none()
+        || accounts.iter().any(|account| !account.owned_by(token_program))
+    {
+        return Err(CpiError::IncorrectTokenProgram);
+    }
+    Ok(())
+}
+
+// ============================================
+// Quote Results
+// ============================================
+
+/// Full breakdown of a quoted swap
+///
+/// `fee_paid` is the output given up to fees, in output-token units, so
+/// `amount_out + fee_paid` is what a fee-free swap would return.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QuoteResult {
+    pub amount_out: u64,
+    pub fee_paid: u64,
+    pub price_impact_bps: u64,
+    /// `amount_out` reduced by the caller's slippage tolerance, rounded down
+    pub min_out_after_slippage: u64,
+}
+
+impl QuoteResult {
+    /// Build a result, deriving the slippage floor from `slippage_bps`
+    #[inline(always)]
+    pub fn new(amount_out: u64, fee_paid: u64, price_impact_bps: u64, slippage_bps: u64) -> Self {
+        let keep_bps = 10_000 - slippage_bps.min(10_000);
+        let min_out_after_slippage = (amount_out as u128 * keep_bps as u128 / 10_000) as u64;
+        Self { amount_out, fee_paid, price_impact_bps, min_out_after_slippage }
+    }
+}
+
+/// Maximum input for an exact-out swap on a constant-product pool
+///
+/// Inverts the forward quote (fee taken from the input in bps, then
+/// `out = reserve_out * net_in / (reserve_in + net_in)`) and pads the
+/// result by `slippage_bps`. Every step rounds up, so quoting the unpadded
+/// input forward yields at least `amount_out`. Returns `0` when the pool
+/// cannot fill `amount_out` (empty reserve, `amount_out >= reserve_out`,
+/// `fee_bps >= 10_000`, or the input would not fit in a `u64`), which makes
+/// the swap's `max_amount_in` check fail instead of overpaying.
+#[inline(always)]
+pub fn max_in_for_exact_out(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u64,
+    slippage_bps: u64,
+) -> u64 {
+    if amount_out == 0 || reserve_in == 0 || amount_out >= reserve_out || fee_bps >= 10_000 {
+        return 0;
+    }
+    // u64 * u64 fits in u128
+    let net_in = (reserve_in as u128 * amount_out as u128).div_ceil((reserve_out - amount_out) as u128);
+    if net_in > u64::MAX as u128 {
+        return 0;
+    }
+    let gross_in = (net_in * 10_000).div_ceil((10_000 - fee_bps) as u128);
+    if gross_in > u64::MAX as u128 {
+        return 0;
+    }
+    let padded = (gross_in * (10_000 + slippage_bps.min(10_000)) as u128).div_ceil(10_000);
+    padded.min(u64::MAX as u128) as u64
+}
+
+/// Constant-product output of selling `amount` each way: `(a -> b, b -> a)`
+///
+/// Same formula and rounding as `solfi_v2::calculate_output_with_fee`
+/// (fee taken from the input in bps, rounded down), with the fee applied
+/// once for both directions. A direction with an empty reserve yields 0.
+#[inline(always)]
+pub fn quote_both(reserve_a: u64, reserve_b: u64, amount: u64, fee_bps: u64) -> (u64, u64) {
+    let net_in = amount as u128 * 10_000u64.saturating_sub(fee_bps) as u128 / 10_000;
+    let out = |reserve_in: u64, reserve_out: u64| {
+        if reserve_in == 0 {
+            return 0;
+        }
+        // reserve_out * net_in < 2^128; the denominator is non-zero
+        (reserve_out as u128 * net_in / (reserve_in as u128 + net_in)) as u64
+    };
+    (out(reserve_a, reserve_b), out(reserve_b, reserve_a))
+}
+
+// ============================================
+// Byte Readers
+// ============================================
+
+/// Read `N` bytes at `offset`, or `None` if the slice is too short
+#[inline(always)]
+pub fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Option<[u8; N]> {
+    data.get(offset..offset.checked_add(N)?)?.try_into().ok()
+}
+
+/// Read a 32-byte pubkey at `offset`
+#[inline(always)]
+pub fn read_pubkey(data: &[u8], offset: usize) -> Option<[u8; 32]> {
+    read_bytes(data, offset)
+}
+
+/// Read a little-endian `u64` at `offset`
+#[inline(always)]
+pub fn read_u64_le(data: &[u8], offset: usize) -> Option<u64> {
+    read_bytes(data, offset).map(u64::from_le_bytes)
+}
+
+/// Read a little-endian `u128` at `offset`
+#[inline(always)]
+pub fn read_u128_le(data: &[u8], offset: usize) -> Option<u128> {
+    read_bytes(data, offset).map(u128::from_le_bytes)
+}
+
+// ============================================
+// Sysvars
+// ============================================
+
+/// Clock Sysvar
+pub const CLOCK_SYSVAR: Address = Address::new_from_array(
+    five8_const::decode_32_const("SysvarC1ock11111111111111111111111111111111")
+);
+
+/// Fields of the Clock sysvar needed for staleness and deadline checks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Clock {
+    pub slot: u64,
+    pub epoch: u64,
+    pub unix_timestamp: i64,
+}
+
+/// Clock sysvar layout
+///
+/// - [0..8]   slot
+/// - [8..16]  epoch_start_timestamp
+/// - [16..24] epoch
+/// - [24..32] leader_schedule_epoch
+/// - [32..40] unix_timestamp
+pub struct ClockLayout;
+
+impl ClockLayout {
+    pub const SLOT_OFFSET: usize = 0;
+    pub const EPOCH_OFFSET: usize = 16;
+    pub const UNIX_TIMESTAMP_OFFSET: usize = 32;
+    /// Account size
+    pub const SIZE: usize = 40;
+}
+
+/// Parse the Clock sysvar account data
+///
+/// Lets callers that already receive the Clock account read the current
+/// slot/timestamp without a `sol_get_clock_sysvar` syscall.
+#[inline(always)]
+pub fn parse_clock(clock_data: &[u8]) -> Option<Clock> {
+    if clock_data.len() < ClockLayout::SIZE {
+        return None;
+    }
+    let slot = ClockLayout::SLOT_OFFSET;
+    let epoch = ClockLayout::EPOCH_OFFSET;
+    let unix_timestamp = ClockLayout::UNIX_TIMESTAMP_OFFSET;
+    Some(Clock {
+        slot: read_u64_le(clock_data, slot)?,
+        epoch: read_u64_le(clock_data, epoch)?,
+        unix_timestamp: i64::from_le_bytes(read_bytes(clock_data, unix_timestamp)?),
+    })
+}
+
+/// Reject swaps executed after `deadline` (unix seconds, inclusive)
+///
+/// Most DEX programs take no deadline argument, so a transaction that sits
+/// in a queue and lands late still executes at whatever price it finds.
+/// `None` disables the check without reading the clock.
+#[inline(always)]
+pub fn check_deadline(clock_data: &[u8], deadline: Option<i64>) -> Result<(), CpiError> {
+    let Some(deadline) = deadline else {
+        return Ok(());
+    };
+    let clock = parse_clock(clock_data).ok_or(CpiError::InvalidClock)?;
+    if clock.unix_timestamp > deadline {
+        return Err(CpiError::DeadlineExceeded);
+    }
+    Ok(())
+}
+
+/// Rent Sysvar
+pub const RENT_SYSVAR: Address = Address::new_from_array(
+    five8_const::decode_32_const("SysvarRent111111111111111111111111111111111")
+);
+
+/// Bytes of account metadata charged rent on top of the data
+pub const ACCOUNT_STORAGE_OVERHEAD: u64 = 128;
+
+/// Default `lamports_per_byte_year` of the Rent sysvar
+pub const LAMPORTS_PER_BYTE_YEAR: u64 = 3_480;
+
+/// Default `exemption_threshold` of the Rent sysvar, in years
+pub const EXEMPTION_THRESHOLD_YEARS: u64 = 2;
+
+/// Rent-exempt minimum for an account holding `data_len` bytes
+///
+/// Uses the default Rent parameters, which have never changed on mainnet;
+/// init CPIs can fund accounts with this instead of reading the sysvar.
+#[inline(always)]
+pub const fn minimum_balance(data_len: usize) -> u64 {
+    (ACCOUNT_STORAGE_OVERHEAD + data_len as u64) * LAMPORTS_PER_BYTE_YEAR * EXEMPTION_THRESHOLD_YEARS
+}
+
+/// Instructions Sysvar
+pub const INSTRUCTIONS_SYSVAR: Address = Address::new_from_array(
+    five8_const::decode_32_const("Sysvar1nstructions1111111111111111111111111")
+);
+
+/// Program ID of top-level instruction `index` in the Instructions sysvar
+///
+/// Layout: `u16` count, `u16` offset per instruction, and at each offset a
+/// `u16` account count, 33 bytes per account (flags + pubkey), then the
+/// program ID. The last 2 bytes hold the index of the executing instruction.
+fn instruction_program_id(instructions_data: &[u8], index: usize) -> Option<[u8; 32]> {
+    let offset = u16::from_le_bytes(read_bytes(instructions_data, 2 + index * 2)?) as usize;
+    let num_accounts = u16::from_le_bytes(read_bytes(instructions_data, offset)?) as usize;
+    read_pubkey(instructions_data, offset + 2 + num_accounts * 33)
+}
+
+/// Index of the latest instruction before the current one that calls `dex`
+///
+/// The sysvar only records top-level instructions, so this sees the
+/// transaction's direct swaps (or an aggregator routing into `dex` when
+/// `dex` is that aggregator), not CPIs made inside other instructions.
+/// `None` if there is no such instruction or the data is malformed.
+pub fn find_prior_swap(instructions_data: &[u8], dex: Dex) -> Option<usize> {
+    let current = u16::from_le_bytes(read_bytes(instructions_data, instructions_data.len().checked_sub(2)?)?) as usize;
+    let count = u16::from_le_bytes(read_bytes(instructions_data, 0)?) as usize;
+    let program_id = dex.program_id().to_bytes();
+    (0..current.min(count))
+        .rev()
+        .find(|&index| instruction_program_id(instructions_data, index) == Some(program_id))
+}
+
+// ============================================
+// SPL Token Accounts
+// ============================================
+
+/// System program
+pub const SYSTEM_PROGRAM_ID: Address = Address::new_from_array(
+    five8_const::decode_32_const("11111111111111111111111111111111")
+);
+
+/// SPL Token program
+pub const TOKEN_PROGRAM_ID: Address = Address::new_from_array(
+    five8_const::decode_32_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA")
+);
+
+/// SPL Token-2022 program
+pub const TOKEN_2022_PROGRAM_ID: Address = Address::new_from_array(
+    five8_const::decode_32_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb")
+);
+
+/// Wrapped SOL mint
+pub const NATIVE_MINT: Address = Address::new_from_array(
+    five8_const::decode_32_const("So11111111111111111111111111111111111111112")
+);
+
+/// SPL token account / mint layout offsets (shared by Token and Token-2022)
+///
+/// Token account:
+/// - [0..32]  mint
+/// - [32..64] owner
+/// - [64..72] amount (u64)
+/// - [72..108] delegate (COption<Pubkey>)
+/// - [108]    state (0 = uninitialized, 1 = initialized, 2 = frozen)
+/// - [109..121] is_native (COption<u64>, rent-exempt reserve of a WSOL account)
+///
+/// Mint:
+/// - [0..36]  mint_authority (COption<Pubkey>)
+/// - [36..44] supply (u64)
+/// - [44]     decimals
+/// - [45]     is_initialized
+/// - [46..82] freeze_authority (COption<Pubkey>)
+pub struct TokenLayout;
+
+impl TokenLayout {
+    /// Token account amount offset
+    pub const AMOUNT_OFFSET: usize = 64;
+    /// Token account `AccountState` offset
+    pub const STATE_OFFSET: usize = 108;
+    /// `AccountState::Frozen`
+    pub const STATE_FROZEN: u8 = 2;
+    /// Token account `is_native` offset (`COption<u64>`)
+    pub const IS_NATIVE_OFFSET: usize = 109;
+    /// Token account base size
+    pub const ACCOUNT_SIZE: usize = 165;
+    /// Mint decimals offset
+    pub const DECIMALS_OFFSET: usize = 44;
+    /// Mint base size
+    pub const MINT_SIZE: usize = 82;
+    /// Token-2022 `AccountType` byte, after the base state padded to `ACCOUNT_SIZE`
+    pub const ACCOUNT_TYPE_OFFSET: usize = 165;
+    /// Token-2022 extension TLV data start
+    pub const EXTENSIONS_OFFSET: usize = 166;
+    /// Token-2022 `AccountType::Mint`
+    pub const ACCOUNT_TYPE_MINT: u8 = 1;
+}
+
+/// Decoded SPL mint (Token or Token-2022 base state)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mint {
+    pub mint_authority: Option<[u8; 32]>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<[u8; 32]>,
+}
+
+impl Mint {
+    /// Parse the base mint state (first 82 bytes)
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < TokenLayout::MINT_SIZE {
+            return None;
+        }
+        Some(Self {
+            mint_authority: parse_coption_key(&data[0..36])?,
+            supply: read_u64_le(data, 36)?,
+            decimals: data[TokenLayout::DECIMALS_OFFSET],
+            is_initialized: data[45] != 0,
+            freeze_authority: parse_coption_key(&data[46..82])?,
+        })
+    }
+
+    /// Token-2022 extension TLV data, if the mint has any
+    ///
+    /// Returns `None` for base SPL mints (exactly 82 bytes) and for data
+    /// whose account type byte is not `Mint`.
+    pub fn extension_data(data: &[u8]) -> Option<&[u8]> {
+        if data.len() <= TokenLayout::ACCOUNT_TYPE_OFFSET
+            || data[TokenLayout::ACCOUNT_TYPE_OFFSET] != TokenLayout::ACCOUNT_TYPE_MINT
+        {
+            return None;
+        }
+        Some(&data[TokenLayout::EXTENSIONS_OFFSET..])
+    }
+}
+
+/// Token-2022 extension type of `TransferFeeConfig`
+pub const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+
+/// Find a Token-2022 mint extension's value by type
+///
+/// Walks the TLV entries (`u16` type, `u16` length, value) after the base
+/// mint. Returns `None` for base SPL mints or if the extension is absent.
+pub fn find_mint_extension(mint_data: &[u8], extension_type: u16) -> Option<&[u8]> {
+    let mut tlv = Mint::extension_data(mint_data)?;
+    while tlv.len() >= 4 {
+        let ty = u16::from_le_bytes([tlv[0], tlv[1]]);
+        let len = u16::from_le_bytes([tlv[2], tlv[3]]) as usize;
+        let value = tlv.get(4..4 + len)?;
+        if ty == extension_type {
+            return Some(value);
+        }
+        // Type 0 is `Uninitialized`: the rest of the buffer is padding
+        if ty == 0 {
+            return None;
+        }
+        tlv = &tlv[4 + len..];
+    }
+    None
+}
+
+/// One side of a Token-2022 `TransferFeeConfig`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferFee {
+    /// First epoch this fee applies
+    pub epoch: u64,
+    pub maximum_fee: u64,
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    /// Fee withheld when transferring `amount` (rounded up, capped)
+    #[inline(always)]
+    pub fn calculate_fee(&self, amount: u64) -> u64 {
+        if self.transfer_fee_basis_points == 0 || amount == 0 {
+            return 0;
+        }
+        let fee = (amount as u128 * self.transfer_fee_basis_points as u128).div_ceil(10_000);
+        fee.min(self.maximum_fee as u128) as u64
+    }
+}
+
+/// Parse `(older, newer)` transfer fees from a Token-2022 mint
+///
+/// Returns `None` for mints without the `TransferFeeConfig` extension.
+pub fn parse_transfer_fee_config(mint_data: &[u8]) -> Option<(TransferFee, TransferFee)> {
+    // authority (32), withdraw authority (32), withheld amount (8), older (18), newer (18)
+    let ext = find_mint_extension(mint_data, EXTENSION_TRANSFER_FEE_CONFIG)?;
+    if ext.len() < 108 {
+        return None;
+    }
+    let fee = |o: usize| -> Option<TransferFee> {
+        Some(TransferFee {
+            epoch: read_u64_le(ext, o)?,
+            maximum_fee: read_u64_le(ext, o + 8)?,
+            transfer_fee_basis_points: u16::from_le_bytes(read_bytes(ext, o + 16)?),
+        })
+    };
+    Some((fee(72)?, fee(90)?))
+}
+
+/// Transfer fee charged on `amount` of this mint at `epoch`
+///
+/// Zero for base SPL mints and Token-2022 mints without a transfer fee.
+/// Pass `u64::MAX` as `epoch` when the clock is unavailable to get the
+/// higher of the two configured fees, which never under-estimates.
+pub fn transfer_fee(mint_data: &[u8], epoch: u64, amount: u64) -> u64 {
+    let Some((older, newer)) = parse_transfer_fee_config(mint_data) else {
+        return 0;
+    };
+    if epoch == u64::MAX {
+        return older.calculate_fee(amount).max(newer.calculate_fee(amount));
+    }
+    if epoch >= newer.epoch { newer } else { older }.calculate_fee(amount)
+}
+
+/// Parse a `COption<Pubkey>` (u32 tag + 32 bytes)
+#[inline(always)]
+fn parse_coption_key(data: &[u8]) -> Option<Option<[u8; 32]>> {
+    match u32::from_le_bytes(read_bytes(data, 0)?) {
+        0 => Some(None),
+        1 => Some(Some(read_pubkey(data, 4)?)),
+        _ => None,
+    }
+}
+
+/// Parse the balance of an SPL token account
+#[inline(always)]
+pub fn parse_token_account_balance(data: &[u8]) -> Option<u64> {
+    read_u64_le(data, TokenLayout::AMOUNT_OFFSET)
+}
+
+/// Token program to pass for a mint, from the mint account's owner
+///
+/// Swap accounts named `token_program` must be the program that owns the
+/// mint being moved; passing SPL Token for a Token-2022 mint (or the
+/// reverse) fails with `IncorrectProgramId`. Returns `None` when the owner
+/// is neither token program, i.e. the account is not a mint.
+#[inline(always)]
+pub fn token_program_for_mint(mint_owner: &Address) -> Option<Address> {
+    if mint_owner == &TOKEN_PROGRAM_ID {
+        Some(TOKEN_PROGRAM_ID)
+    } else if mint_owner == &TOKEN_2022_PROGRAM_ID {
+        Some(TOKEN_2022_PROGRAM_ID)
+    } else {
+        None
+    }
+}
+
+/// Whether an SPL token account is frozen
+///
+/// Transfers from or into a frozen account fail inside the token program,
+/// which surfaces as an opaque error from the DEX; check source and
+/// destination before swapping. Uninitialized or truncated data is not
+/// reported as frozen.
+#[inline(always)]
+pub fn is_account_frozen(token_account_data: &[u8]) -> bool {
+    token_account_data.get(TokenLayout::STATE_OFFSET) == Some(&TokenLayout::STATE_FROZEN)
+}
+
+/// Whether an SPL token account is a native (WSOL) account
+///
+/// Reads the `is_native` tag, which the token program sets for accounts of
+/// `NATIVE_MINT` only. Their balance tracks lamports (after `SyncNative`)
+/// and closing them returns the SOL, so unwrap logic should close these and
+/// leave other accounts alone. Truncated data is reported as non-native.
+#[inline(always)]
+pub fn is_native_sol_account(token_account_data: &[u8]) -> bool {
+    read_bytes::<4>(token_account_data, TokenLayout::IS_NATIVE_OFFSET).map(u32::from_le_bytes) == Some(1)
+}
+
+/// Parse the decimals of an SPL mint
+#[inline(always)]
+pub fn parse_mint_decimals(data: &[u8]) -> Option<u8> {
+    if data.len() < TokenLayout::MINT_SIZE {
+        return None;
+    }
+    Some(data[TokenLayout::DECIMALS_OFFSET])
+}
+
+/// Return `CpiError::DecimalsMismatch` unless the mint has `expected` decimals
+///
+/// Guards price math that scales amounts by a hardcoded decimals count
+/// (e.g. 6 for USDC): a wrong assumption would misprice by a power of ten
+/// instead of failing. Short mint data is a mismatch too.
+#[inline(always)]
+pub fn require_decimals(mint_data: &[u8], expected: u8) -> Result<(), CpiError> {
+    match parse_mint_decimals(mint_data) {
+        Some(decimals) if decimals == expected => Ok(()),
+        _ => Err(CpiError::DecimalsMismatch),
+    }
+}
+
+/// Read both vault balances and both mint decimals in one call
+///
+/// Returns `(base_reserve, base_decimals, quote_reserve, quote_decimals)`.
+#[inline(always)]
+pub fn pool_reserves_with_mints(
+    base_vault_data: &[u8],
+    quote_vault_data: &[u8],
+    base_mint_data: &[u8],
+    quote_mint_data: &[u8],
+) -> Option<(u64, u8, u64, u8)> {
+    Some((
+        parse_token_account_balance(base_vault_data)?,
+        parse_mint_decimals(base_mint_data)?,
+        parse_token_account_balance(quote_vault_data)?,
+        parse_mint_decimals(quote_mint_data)?,
+    ))
+}
+
+// ============================================
+// PDA Cache
+// ============================================
+
+/// Cached derivation result
+#[derive(Clone, Debug)]
+struct PdaCacheEntry<'a> {
+    seeds: &'a [&'a [u8]],
+    program_id: Address,
+    address: Address,
+    bump: u8,
+}
+
+/// Fixed-capacity cache of derived PDAs
+///
+/// `find_program_address` costs ~1,500 CU per bump attempt; routers that
+/// derive the same authority several times in one instruction can look it
+/// up here instead. Entries borrow the seeds they were derived from and
+/// compare them, with the program ID, seed by seed on lookup, so a hit is
+/// always an exact match and an entry costs about 100 bytes. When full, the
+/// oldest entry is replaced.
+#[derive(Clone, Debug)]
+pub struct PdaCache<'a, const N: usize> {
+    entries: [Option<PdaCacheEntry<'a>>; N],
+    next: usize,
+}
+
+impl<const N: usize> Default for PdaCache<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, const N: usize> PdaCache<'a, N> {
+    /// Empty cache
+    pub const fn new() -> Self {
+        Self { entries: [const { None }; N], next: 0 }
+    }
+
+    /// Cached `(address, bump)` for these seeds, if any
+    pub fn get(&self, seeds: &[&[u8]], program_id: &Address) -> Option<(Address, u8)> {
+        self.entries
+            .iter()
+            .flatten()
+            .find(|entry| &entry.program_id == program_id && entry.seeds == seeds)
+            .map(|entry| (entry.address.clone(), entry.bump))
+    }
+
+    /// Store a derivation result
+    pub fn insert(&mut self, seeds: &'a [&'a [u8]], program_id: &Address, address: &Address, bump: u8) {
+        if N == 0 {
+            return;
+        }
+        self.entries[self.next] = Some(PdaCacheEntry {
+            seeds,
+            program_id: program_id.clone(),
+            address: address.clone(),
+            bump,
+        });
+        self.next = (self.next + 1) % N;
+    }
+
+    /// Cached result, or run `derive` and cache what it returns
+    pub fn get_or_insert_with<F>(&mut self, seeds: &'a [&'a [u8]], program_id: &Address, derive: F) -> (Address, u8)
+    where
+        F: FnOnce() -> (Address, u8),
+    {
+        if let Some(hit) = self.get(seeds, program_id) {
+            return hit;
+        }
+        let (address, bump) = derive();
+        self.insert(seeds, program_id, &address, bump);
+        (address, bump)
+    }
+
+    /// Cached `find_program_address`
+    #[cfg(any(target_os = "solana", target_arch = "bpf"))]
+    #[inline(always)]
+    pub fn find_program_address(&mut self, seeds: &'a [&'a [u8]], program_id: &Address) -> (Address, u8) {
+        self.get_or_insert_with(seeds, program_id, || Address::find_program_address(seeds, program_id))
+    }
+}
+
+// ============================================
+// Pool Authority
+// ============================================
+
+/// Pool authority stored in pool state at `offset`
+///
+/// Returns `None` if the data is too short or the field is all zeros
+/// (unset).
+#[inline(always)]
+pub fn parse_authority(data: &[u8], offset: usize) -> Option<[u8; 32]> {
+    read_pubkey(data, offset).filter(|authority| authority != &[0u8; 32])
+}
+
+/// Pool authority from pool state, or derived when it is not stored
+///
+/// Programs differ: some store the authority in the pool, others derive it
+/// from fixed seeds. Pass `offset: None` for the latter. An unset stored
+/// field also falls back to `derive`, which receives `seeds`; truncated
+/// data returns `None` without deriving.
+pub fn parse_or_derive_authority<F>(data: &[u8], offset: Option<usize>, seeds: &[&[u8]], derive: F) -> Option<[u8; 32]>
+where
+    F: FnOnce(&[&[u8]]) -> Option<Address>,
+{
+    if let Some(offset) = offset {
+        let stored = read_pubkey(data, offset)?;
+        if stored != [0u8; 32] {
+            return Some(stored);
+        }
+    }
+    derive(seeds).map(|authority| authority.to_bytes())
+}
+
+/// `parse_or_derive_authority`, deriving with `find_program_address`
+#[cfg(any(target_os = "solana", target_arch = "bpf"))]
+#[inline(always)]
+pub fn parse_or_find_authority(
+    data: &[u8],
+    offset: Option<usize>,
+    seeds: &[&[u8]],
+    program_id: &Address,
+) -> Option<[u8; 32]> {
+    parse_or_derive_authority(data, offset, seeds, |seeds| {
+        Address::try_find_program_address(seeds, program_id).map(|(authority, _)| authority)
+    })
+}
+
+// ============================================
+// u128 Words
+// ============================================
+
+/// Split a u128 into `(lo, hi)` u64 words, least significant first
+///
+/// Matches the byte order of a little-endian u128: `lo.to_le_bytes()`
+/// followed by `hi.to_le_bytes()` equals `x.to_le_bytes()`. Use this for
+/// encodings that store liquidity or sqrt prices as `[u64; 2]`.
+#[inline(always)]
+pub const fn u128_to_words_le(x: u128) -> (u64, u64) {
+    (x as u64, (x >> 64) as u64)
+}
+
+/// Join `(lo, hi)` u64 words back into a u128
+#[inline(always)]
+pub const fn words_le_to_u128(lo: u64, hi: u64) -> u128 {
+    ((hi as u128) << 64) | lo as u128
+}
+
+// ============================================
+// Borsh Option Encoding
+// ============================================
+
+/// Borsh-encoded `Option<bool>` for `#[repr(C, packed)]` argument structs
+///
+/// A Rust `Option<bool>` is a single niche-optimized byte, which does not
+/// match Borsh (`[0]` for `None`, `[1, value]` for `Some`). This type always
+/// occupies 2 bytes; `None` is written as `[0, 0]` and the trailing byte is
+/// ignored by Anchor's instruction deserializer.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OptionBool {
+    tag: u8,
+    value: u8,
+}
+
+impl OptionBool {
+    /// `None`
+    pub const NONE: Self = Self { tag: 0, value: 0 };
+
+    /// `Some(value)`
+    #[inline(always)]
+    pub const fn some(value: bool) -> Self {
+        Self { tag: 1, value: value as u8 }
+    }
+
+    /// Convert back to a Rust option
+    #[inline(always)]
+    pub const fn get(self) -> Option<bool> {
+        if self.tag == 0 { None } else { Some(self.value != 0) }
+    }
+}
+
+impl From<Option<bool>> for OptionBool {
+    #[inline(always)]
+    fn from(value: Option<bool>) -> Self {
+        match value {
+            Some(value) => Self::some(value),
+            None => Self::NONE,
+        }
+    }
+}
+
+/// Raw bytes of a packed argument struct, as copied into instruction data
+#[cfg(test)]
+pub(crate) fn args_bytes<T: Copy>(args: &T) -> &[u8] {
+    // SAFETY: argument structs are `#[repr(C, packed)]` plain data
+    unsafe { core::slice::from_raw_parts(args as *const T as *const u8, core::mem::size_of::<T>()) }
+}
+
+/// Seeds a `Signer` was built from, in order
+#[cfg(test)]
+pub(crate) fn signer_seeds<'a>(signer: &pinocchio::cpi::Signer<'a, '_>) -> &'a [pinocchio::cpi::Seed<'a>] {
+    // `Signer` layout: `#[repr(C)] { seeds: *const Seed, len: u64 }`
+    #[repr(C)]
+    struct RawSigner<'a> {
+        seeds: *const pinocchio::cpi::Seed<'a>,
+        len: u64,
+    }
+    // SAFETY: same layout, and callers keep the seeds alive past the signer
+    unsafe {
+        let raw = &*(signer as *const pinocchio::cpi::Signer as *const RawSigner);
+        core::slice::from_raw_parts(raw.seeds, raw.len as usize)
+    }
+}
+
+/// Fixed-capacity string for test snapshots (no allocator in this crate)
+#[cfg(test)]
+pub(crate) struct ArrayString<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+#[cfg(test)]
+impl<const N: usize> ArrayString<N> {
+    pub(crate) const fn new() -> Self {
+        Self { buf: [0u8; N], len: 0 }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap()
+    }
+}
+
+#[cfg(test)]
+impl<const N: usize> core::fmt::Write for ArrayString<N> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        if end > N {
+            return Err(core::fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl<const N: usize> core::fmt::Debug for ArrayString<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+impl<const N: usize> PartialEq<&str> for ArrayString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+/// Render each account's role, space separated, for snapshot asserts
+///
+/// `r` readonly, `w` writable, `rs` readonly signer, `ws` writable signer.
+#[cfg(test)]
+pub(crate) fn dump_account_roles(accounts: &[pinocchio::instruction::InstructionAccount]) -> ArrayString<256> {
+    use core::fmt::Write;
+
+    let mut out = ArrayString::new();
+    for (i, account) in accounts.iter().enumerate() {
+        let role = match (account.is_writable, account.is_signer) {
+            (false, false) => "r",
+            (true, false) => "w",
+            (false, true) => "rs",
+            (true, true) => "ws",
+        };
+        let separator = if i == 0 { "" } else { " " };
+        write!(out, "{separator}{role}").unwrap();
+    }
+    out
+}
+
+/// Whether `accounts` has exactly the access flags listed in `spec`
+#[cfg(test)]
+pub(crate) fn matches_account_spec(accounts: &[pinocchio::instruction::InstructionAccount], spec: &[AccountRole]) -> bool {
+    accounts.len() == spec.len()
+        && accounts
+            .iter()
+            .zip(spec)
+            .all(|(account, role)| account.is_writable == role.writable && account.is_signer == role.signer)
+}
+
+/// Back `N` unborrowed account views whose addresses are `[i + 1; 32]`
+///
+/// Off-chain, CPIs only validate addresses and borrow state before
+/// returning `Ok`, so these views can be passed through a full CPI call.
+#[cfg(test)]
+pub(crate) fn mock_account_views<const N: usize>(
+    raw: &mut [pinocchio::account::RuntimeAccount; N],
+) -> [pinocchio::AccountView; N] {
+    core::array::from_fn(|i| {
+        raw[i].address = Address::new_from_array([i as u8 + 1; 32]);
+        raw[i].borrow_state = pinocchio::account::NOT_BORROWED;
+        // SAFETY: `raw[i]` outlives the returned views in every test; data_len is 0
+        unsafe { pinocchio::AccountView::new_unchecked(&mut raw[i]) }
+    })
+}
+
+/// Runtime account header followed by `N` bytes of data, as the loader lays it out
+#[cfg(test)]
+#[repr(C)]
+pub(crate) struct MockAccount<const N: usize> {
+    pub raw: pinocchio::account::RuntimeAccount,
+    pub data: [u8; N],
+}
+
+#[cfg(test)]
+impl<const N: usize> MockAccount<N> {
+    pub fn new(address: [u8; 32]) -> Self {
+        let raw = pinocchio::account::RuntimeAccount {
+            address: Address::new_from_array(address),
+            borrow_state: pinocchio::account::NOT_BORROWED,
+            is_writable: 1,
+            data_len: N as u64,
+            ..Default::default()
+        };
+        Self { raw, data: [0; N] }
+    }
+
+    pub fn view(&mut self) -> pinocchio::AccountView {
+        // SAFETY: `data` directly follows `raw` and is `data_len` bytes long
+        unsafe { pinocchio::AccountView::new_unchecked(&mut self.raw) }
+    }
+}
+
+/// Base SPL mint: 6 decimals, mint authority set, no freeze authority
+#[cfg(test)]
+pub(crate) fn mint_fixture() -> [u8; TokenLayout::MINT_SIZE] {
+    let mut data = [0u8; TokenLayout::MINT_SIZE];
+    data[0..4].copy_from_slice(&1u32.to_le_bytes());
+    data[4..36].copy_from_slice(&[9u8; 32]);
+    data[36..44].copy_from_slice(&1_000_000_000_000u64.to_le_bytes());
+    data[44] = 6;
+    data[45] = 1;
+    data
+}
+
+/// Token-2022 mint with a `TransferFeeConfig` (older 0 bps, newer `bps` from epoch 500)
+#[cfg(test)]
+pub(crate) fn transfer_fee_mint_fixture(bps: u16, maximum_fee: u64) -> [u8; 278] {
+    let mut data = [0u8; 278];
+    data[..TokenLayout::MINT_SIZE].copy_from_slice(&mint_fixture());
+    data[TokenLayout::ACCOUNT_TYPE_OFFSET] = TokenLayout::ACCOUNT_TYPE_MINT;
+    data[166..168].copy_from_slice(&EXTENSION_TRANSFER_FEE_CONFIG.to_le_bytes());
+    data[168..170].copy_from_slice(&108u16.to_le_bytes());
+    let ext = &mut data[170..278];
+    ext[80..88].copy_from_slice(&u64::MAX.to_le_bytes());
+    ext[90..98].copy_from_slice(&500u64.to_le_bytes());
+    ext[98..106].copy_from_slice(&maximum_fee.to_le_bytes());
+    ext[106..108].copy_from_slice(&bps.to_le_bytes());
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jupiter_labels() {
+        assert_eq!(dex_from_jupiter_label("HumidiFi"), Some(Dex::HumidiFi));
+        assert_eq!(dex_from_jupiter_label("humidifi"), Some(Dex::HumidiFi));
+        assert_eq!(dex_from_jupiter_label("SolFi V2"), Some(Dex::SolfiV2));
+        assert_eq!(dex_from_jupiter_label("SolFi"), None);
+        assert_eq!(dex_from_jupiter_label("Whirlpool"), Some(Dex::Whirlpool));
+        assert_eq!(dex_from_jupiter_label("Raydium"), Some(Dex::RaydiumAmm));
+        assert_eq!(dex_from_jupiter_label("Raydium CLMM"), Some(Dex::RaydiumClmm));
+        assert_eq!(dex_from_jupiter_label("Raydium CP"), Some(Dex::RaydiumCp));
+        assert_eq!(dex_from_jupiter_label("Raydium Launchlab"), Some(Dex::RaydiumLaunchlab));
+        assert_eq!(dex_from_jupiter_label(""), None);
+
+        for dex in Dex::ALL {
+            assert_eq!(dex_from_jupiter_label(jupiter_label(dex)), Some(dex));
+        }
+    }
+
+    #[test]
+    fn test_dex_program_id_round_trip() {
+        for dex in Dex::ALL {
+            assert_eq!(Dex::from_program_id(dex.program_id()), Some(dex));
+        }
+        assert_eq!(Dex::from_program_id(&Address::new_from_array([0u8; 32])), None);
+    }
+
+    #[test]
+    fn test_reserve_sanity() {
+        assert_eq!(reserve_sanity(0, 1_000_000, 1_000), Err(CpiError::EmptyReserve));
+        assert_eq!(reserve_sanity(1_000_000, 0, 0), Err(CpiError::EmptyReserve));
+        assert_eq!(reserve_sanity(999, 1_000_000, 1_000), Err(CpiError::InsufficientReserve));
+        assert_eq!(reserve_sanity(1_000_000, 5, 1_000), Err(CpiError::InsufficientReserve));
+        assert_eq!(reserve_sanity(1_000, 1_000_000, 1_000), Ok(()));
+        assert_eq!(ProgramError::from(CpiError::InsufficientReserve), ProgramError::Custom(9001));
+    }
+
+    #[test]
+    fn test_assert_min_out_reasonable() {
+        // 1% tolerance on a 1_000_000 quote: floor 990_000
+        assert_eq!(assert_min_out_reasonable(990_000, 1_000_000, 100), Ok(()));
+        assert_eq!(assert_min_out_reasonable(989_999, 1_000_000, 100), Err(CpiError::MinOutTooLow));
+        assert_eq!(assert_min_out_reasonable(1_000_000, 1_000_000, 0), Ok(()));
+        assert_eq!(assert_min_out_reasonable(999_999, 1_000_000, 0), Err(CpiError::MinOutTooLow));
+
+        // Unlimited slippage is caught unless the caller allows 100%
+        assert_eq!(assert_min_out_reasonable(0, 5_000_000_000, 500), Err(CpiError::MinOutTooLow));
+        assert_eq!(assert_min_out_reasonable(0, 5_000_000_000, 10_000), Ok(()));
+        assert_eq!(assert_min_out_reasonable(0, 0, 0), Ok(()));
+
+        // Floor rounds up: 50 bps of 999 keeps 994.005
+        assert_eq!(assert_min_out_reasonable(994, 999, 50), Err(CpiError::MinOutTooLow));
+        assert_eq!(assert_min_out_reasonable(995, 999, 50), Ok(()));
+        assert_eq!(assert_min_out_reasonable(u64::MAX, u64::MAX, 1), Ok(()));
+        assert_eq!(ProgramError::from(CpiError::MinOutTooLow), ProgramError::Custom(9011));
+    }
+
+    #[test]
+    fn test_pool_tvl_estimate() {
+        use math::ONE_X64;
+
+        // 1_000 SOL at $150 and 150_000 USDC at $1
+        let sol_price = 150 * ONE_X64;
+        let usdc_price = ONE_X64;
+        let tvl = pool_tvl_estimate(1_000_000_000_000, 9, sol_price, 150_000_000_000, 6, usdc_price);
+        assert_eq!(tvl, 300_000 * ONE_X64);
+
+        // Fractional prices keep their precision: 2.5 whole tokens at $0.50
+        let half = ONE_X64 / 2;
+        assert_eq!(pool_tvl_estimate(2_500_000, 6, half, 0, 6, usdc_price), ONE_X64 + ONE_X64 / 4);
+
+        // A dust pool falls below a $1_000 threshold
+        let dust = pool_tvl_estimate(1_000_000, 9, sol_price, 150_000, 6, usdc_price);
+        assert_eq!(dust, ONE_X64 * 3 / 10);
+        assert!(dust < 1_000 * ONE_X64);
+
+        // Unpriced side contributes nothing; extreme inputs saturate
+        assert_eq!(pool_tvl_estimate(1_000_000_000_000, 9, sol_price, 5, 6, 0), 150_000 * ONE_X64);
+        assert_eq!(pool_tvl_estimate(u64::MAX, 0, u128::MAX, 1, 0, 1), u128::MAX);
+        assert_eq!(pool_tvl_estimate(u64::MAX, 255, u128::MAX, 0, 0, 0), 0);
+    }
+
+    #[test]
+    fn test_dump_account_roles() {
+        use pinocchio::instruction::InstructionAccount;
+
+        let a = Address::new_from_array([1u8; 32]);
+        let accounts = [
+            InstructionAccount::readonly(&a),
+            InstructionAccount::writable(&a),
+            InstructionAccount::readonly_signer(&a),
+            InstructionAccount::writable_signer(&a),
+        ];
+        assert_eq!(dump_account_roles(&accounts), "r w rs ws");
+        assert_eq!(dump_account_roles(&[]), "");
+    }
+
+    #[test]
+    fn test_price_impact_bps() {
+        // 0.1% of the reserve moves the price ~10 bps
+        assert_eq!(price_impact_bps(1_000, 1_000_000), 10);
+        assert_eq!(price_impact_bps(1, 1_000_000_000), 1);
+        // Swapping the whole reserve halves the execution price
+        assert_eq!(price_impact_bps(1_000_000, 1_000_000), 5_000);
+        assert_eq!(price_impact_bps(9_000_000, 1_000_000), 9_000);
+        assert_eq!(price_impact_bps(0, 1_000_000), 0);
+        assert_eq!(price_impact_bps(5, 0), 10_000);
+        assert_eq!(price_impact_bps(u64::MAX, u64::MAX), 5_000);
+
+        assert_eq!(check_price_impact(1_000, 1_000_000, 50), Ok(()));
+        assert_eq!(check_price_impact(100_000, 1_000_000, 50), Err(CpiError::PriceImpactTooHigh));
+    }
+
+    #[test]
+    fn test_validate_distinct_accounts() {
+        let a = Address::new_from_array([1u8; 32]);
+        let b = Address::new_from_array([2u8; 32]);
+        let c = Address::new_from_array([3u8; 32]);
+        assert_eq!(validate_distinct_accounts(&[&a, &b, &c]), Ok(()));
+        assert_eq!(validate_distinct_accounts(&[&a, &b, &a]), Err(CpiError::DuplicateAccount));
+        assert_eq!(validate_distinct_accounts(&[&c, &c]), Err(CpiError::DuplicateAccount));
+        assert_eq!(validate_distinct_accounts(&[&a]), Ok(()));
+        assert_eq!(validate_distinct_accounts(&[]), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_token_program() {
+        let mut vault = MockAccount::<0>::new([1u8; 32]);
+        let mut mint = MockAccount::<0>::new([2u8; 32]);
+        vault.raw.owner = TOKEN_PROGRAM_ID;
+        mint.raw.owner = TOKEN_PROGRAM_ID;
+        let (vault_view, mint_view) = (vault.view(), mint.view());
+        assert_eq!(validate_token_program(&TOKEN_PROGRAM_ID, &[&vault_view, &mint_view]), Ok(()));
+        assert_eq!(
+            validate_token_program(&TOKEN_2022_PROGRAM_ID, &[&vault_view, &mint_view]),
+            Err(CpiError::IncorrectTokenProgram)
+        );
+
+        // One Token-2022 mint among SPL accounts
+        mint.raw.owner = TOKEN_2022_PROGRAM_ID;
+        let mint_view = mint.view();
+        assert_eq!(
+            validate_token_program(&TOKEN_PROGRAM_ID, &[&vault_view, &mint_view]),
+            Err(CpiError::IncorrectTokenProgram)
+        );
+        assert_eq!(validate_token_program(&TOKEN_2022_PROGRAM_ID, &[&mint_view]), Ok(()));
+
+        // Not a token program, even with matching owners
+        vault.raw.owner = SYSTEM_PROGRAM_ID;
+        let vault_view = vault.view();
+        assert_eq!(
+            validate_token_program(&SYSTEM_PROGRAM_ID, &[&vault_view]),
+            Err(CpiError::IncorrectTokenProgram)
+        );
+        assert_eq!(validate_token_program(&TOKEN_PROGRAM_ID, &[]), Ok(()));
+    }
+
+    #[test]
+    fn test_pda_signer_seeds() {
+        let market = Address::new_from_array([7u8; 32]);
+        pda_signer!(signer = [b"vault", market.as_ref()], 254);
+        let seeds = signer_seeds(&signer);
+        assert_eq!(seeds.len(), 3);
+        assert_eq!(&*seeds[0], b"vault");
+        assert_eq!(&*seeds[1], &[7u8; 32]);
+        assert_eq!(&*seeds[2], &[254]);
+
+        // Bump only
+        pda_signer!(signer = [], 1);
+        let seeds = signer_seeds(&signer);
+        assert_eq!(seeds.len(), 1);
+        assert_eq!(&*seeds[0], &[1]);
+    }
+
+    #[test]
+    fn test_require_accounts() {
+        fn check(accounts: &[pinocchio::AccountView], min: usize) -> Result<usize, ProgramError> {
+            require_accounts!(accounts, min);
+            Ok(accounts.len())
+        }
+        fn check_cpi(accounts: &[&Address]) -> Result<(), CpiError> {
+            require_accounts!(accounts, 2);
+            Ok(())
+        }
+
+        let mut raw: [pinocchio::account::RuntimeAccount; 3] = Default::default();
+        let views = mock_account_views(&mut raw);
+        let missing = Err(ProgramError::Custom(CpiError::MissingAccount as u32));
+        assert_eq!(check(&views[..2], 3), missing);
+        assert_eq!(check(&views, 3), Ok(3));
+        assert_eq!(check(&views, 2), Ok(3));
+        assert_eq!(check(&[], 0), Ok(0));
+        assert_eq!(check(&[], 1), missing);
+
+        let a = Address::new_from_array([1u8; 32]);
+        assert_eq!(check_cpi(&[&a]), Err(CpiError::MissingAccount));
+        assert_eq!(check_cpi(&[&a, &a]), Ok(()));
+    }
+
+    #[test]
+    fn test_u128_words_round_trip() {
+        for x in [0u128, 1, u64::MAX as u128, 1u128 << 64, (7u128 << 64) | 9, u128::MAX] {
+            let (lo, hi) = u128_to_words_le(x);
+            assert_eq!(words_le_to_u128(lo, hi), x);
+
+            let mut bytes = [0u8; 16];
+            bytes[0..8].copy_from_slice(&lo.to_le_bytes());
+            bytes[8..16].copy_from_slice(&hi.to_le_bytes());
+            assert_eq!(bytes, x.to_le_bytes());
+        }
+        assert_eq!(u128_to_words_le((7u128 << 64) | 9), (9, 7));
+    }
+
+    #[test]
+    fn test_mint_base() {
+        let data = mint_fixture();
+        let mint = Mint::from_bytes(&data).unwrap();
+        assert_eq!(mint.mint_authority, Some([9u8; 32]));
+        assert_eq!(mint.supply, 1_000_000_000_000);
+        assert_eq!(mint.decimals, 6);
+        assert!(mint.is_initialized);
+        assert_eq!(mint.freeze_authority, None);
+        assert_eq!(Mint::extension_data(&data), None);
+
+        let mut bad_tag = data;
+        bad_tag[46] = 2;
+        assert!(Mint::from_bytes(&bad_tag).is_none());
+        assert!(Mint::from_bytes(&data[..81]).is_none());
+    }
+
+    #[test]
+    fn test_append_remaining() {
+        let (a, b, c, d) = (
+            Address::new_from_array([1; 32]),
+            Address::new_from_array([2; 32]),
+            Address::new_from_array([3; 32]),
+            Address::new_from_array([4; 32]),
+        );
+        let mut builder = InstructionBuilder::<4>::new(&[InstructionAccount::writable_signer(&a)]).unwrap();
+        assert_eq!(builder.len(), 1);
+        append_remaining(&mut builder, &[(&b, true, false), (&c, false, false)]).unwrap();
+        assert_eq!(dump_account_roles(builder.accounts()), "ws w r");
+        assert_eq!(builder.accounts()[2].address, &c);
+
+        // Over capacity: nothing is appended
+        assert_eq!(
+            append_remaining(&mut builder, &[(&d, false, true), (&d, true, true)]),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(builder.len(), 3);
+        append_remaining(&mut builder, &[(&d, false, true)]).unwrap();
+        assert_eq!(dump_account_roles(builder.accounts()), "ws w r rs");
+        assert_eq!(append_remaining(&mut builder, &[]), Ok(()));
+
+        assert!(InstructionBuilder::<1>::new(&[]).unwrap().is_empty());
+        assert!(InstructionBuilder::<1>::new(&[InstructionAccount::readonly(&a), InstructionAccount::readonly(&b)]).is_err());
+    }
+
+    #[test]
+    fn test_account_flags() {
+        let mut raw: [pinocchio::account::RuntimeAccount; 3] = Default::default();
+        raw[0].is_signer = 1;
+        raw[0].is_writable = 1;
+        raw[1].is_writable = 1;
+        let v = mock_account_views(&mut raw);
+        assert!(is_signer(&v[0]) && is_writable(&v[0]));
+        assert!(!is_signer(&v[1]) && is_writable(&v[1]));
+        assert!(!is_signer(&v[2]) && !is_writable(&v[2]));
+
+        let views = [&v[0], &v[1], &v[2]];
+        let required = [
+            InstructionAccount::writable_signer(v[0].address()),
+            InstructionAccount::writable(v[1].address()),
+            InstructionAccount::readonly(v[2].address()),
+        ];
+        assert_eq!(validate_writable(&required, &views), Ok(()));
+        // A PDA signer that is not a transaction signer still passes
+        let pda_signer = [InstructionAccount::writable_signer(v[1].address())];
+        assert_eq!(validate_writable(&pda_signer, &views[1..]), Ok(()));
+        let write_readonly = [InstructionAccount::writable(v[2].address())];
+        assert_eq!(validate_writable(&write_readonly, &views[2..]), Err(CpiError::AccountNotWritable));
+        assert_eq!(ProgramError::from(CpiError::AccountNotWritable), ProgramError::Custom(9010));
+    }
+
+    #[test]
+    fn test_require_decimals() {
+        let data = mint_fixture();
+        assert_eq!(require_decimals(&data, 6), Ok(()));
+        assert_eq!(require_decimals(&data, 9), Err(CpiError::DecimalsMismatch));
+        assert_eq!(require_decimals(&data[..TokenLayout::MINT_SIZE - 1], 6), Err(CpiError::DecimalsMismatch));
+        assert_eq!(ProgramError::from(CpiError::DecimalsMismatch), ProgramError::Custom(9009));
+    }
+
+    #[test]
+    fn test_mint_token_2022() {
+        // Base state, zero padding to 165, account type, then one TLV entry
+        let mut data = [0u8; 170];
+        data[..TokenLayout::MINT_SIZE].copy_from_slice(&mint_fixture());
+        data[TokenLayout::ACCOUNT_TYPE_OFFSET] = TokenLayout::ACCOUNT_TYPE_MINT;
+        data[166..170].copy_from_slice(&[1, 0, 0, 0]);
+
+        assert_eq!(Mint::from_bytes(&data).unwrap().decimals, 6);
+        assert_eq!(Mint::extension_data(&data), Some(&[1u8, 0, 0, 0][..]));
+
+        data[TokenLayout::ACCOUNT_TYPE_OFFSET] = 2;
+        assert_eq!(Mint::extension_data(&data), None);
+    }
+
+    #[test]
+    fn test_transfer_fee() {
+        let data = transfer_fee_mint_fixture(100, 5_000);
+        let (older, newer) = parse_transfer_fee_config(&data).unwrap();
+        assert_eq!(older.transfer_fee_basis_points, 0);
+        assert_eq!(newer, TransferFee { epoch: 500, maximum_fee: 5_000, transfer_fee_basis_points: 100 });
+
+        // 1% rounded up, capped at maximum_fee
+        assert_eq!(transfer_fee(&data, 500, 1_001), 11);
+        assert_eq!(transfer_fee(&data, 500, 10_000_000), 5_000);
+        assert_eq!(transfer_fee(&data, 499, 1_001), 0);
+        assert_eq!(transfer_fee(&data, u64::MAX, 1_001), 11);
+
+        // Base mints and mints without the extension are fee-free
+        assert_eq!(transfer_fee(&mint_fixture(), 500, 1_001), 0);
+        let mut other = data;
+        other[166] = 9;
+        assert_eq!(transfer_fee(&other, 500, 1_001), 0);
+    }
+
+    #[test]
+    fn test_parse_clock() {
+        let mut data = [0u8; ClockLayout::SIZE];
+        data[0..8].copy_from_slice(&312_345_678u64.to_le_bytes());
+        data[8..16].copy_from_slice(&1_700_000_000i64.to_le_bytes());
+        data[16..24].copy_from_slice(&723u64.to_le_bytes());
+        data[24..32].copy_from_slice(&724u64.to_le_bytes());
+        data[32..40].copy_from_slice(&1_700_123_456i64.to_le_bytes());
+
+        let clock = parse_clock(&data).unwrap();
+        assert_eq!(clock.slot, 312_345_678);
+        assert_eq!(clock.epoch, 723);
+        assert_eq!(clock.unix_timestamp, 1_700_123_456);
+        assert_eq!(parse_clock(&data[..39]), None);
+    }
+
+    #[test]
+    fn test_quote_result() {
+        let q = QuoteResult::new(1_000_001, 3_000, 25, 50);
+        assert_eq!((q.amount_out, q.fee_paid, q.price_impact_bps), (1_000_001, 3_000, 25));
+        assert_eq!(q.min_out_after_slippage, 995_000);
+        assert_eq!(QuoteResult::new(1_000, 0, 0, 0).min_out_after_slippage, 1_000);
+        assert_eq!(QuoteResult::new(1_000, 0, 0, 20_000).min_out_after_slippage, 0);
+        assert_eq!(QuoteResult::new(u64::MAX, 0, 0, 1).min_out_after_slippage, (u64::MAX as u128 * 9_999 / 10_000) as u64);
+    }
+
+    #[test]
+    fn test_quote_both() {
+        use crate::solfi_v2::calculate_output_with_fee;
+
+        for (reserve_a, reserve_b, amount, fee_bps) in [
+            (1_000_000u64, 1_000_000u64, 10_000u64, 30u64),
+            (5_000_000_000, 80_000_000, 12_345, 25),
+            (100_000_000_000, 15_000_000_000, 1_000_000_000, 0),
+            (u64::MAX, 3, u64::MAX, 10_000),
+            (0, 1_000, 500, 30),
+        ] {
+            assert_eq!(
+                quote_both(reserve_a, reserve_b, amount, fee_bps),
+                (
+                    calculate_output_with_fee(amount, reserve_a, reserve_b, fee_bps),
+                    calculate_output_with_fee(amount, reserve_b, reserve_a, fee_bps),
+                )
+            );
+        }
+        // 1_000_000 x 1_000_000 pool, 30 bps: 9_970 net in, 9_871 out either way
+        assert_eq!(quote_both(1_000_000, 1_000_000, 10_000, 30), (9_871, 9_871));
+        assert_eq!(quote_both(1_000, 1_000, 0, 30), (0, 0));
+    }
+
+    #[test]
+    fn test_max_in_for_exact_out() {
+        use crate::solfi_v2::calculate_output_with_fee;
+
+        for (amount_out, reserve_in, reserve_out, fee_bps) in [
+            (1u64, 1_000_000u64, 1_000_000u64, 30u64),
+            (12_345, 5_000_000_000, 80_000_000, 30),
+            (999_999, 1_000_000, 1_000_000, 25),
+            (500_000_000, 7_777_777_777, 1_000_000_000, 100),
+            (10, 3, 1_000, 0),
+        ] {
+            let max_in = max_in_for_exact_out(amount_out, reserve_in, reserve_out, fee_bps, 0);
+            // Enough to fill, and one unit less is not
+            assert!(calculate_output_with_fee(max_in, reserve_in, reserve_out, fee_bps) >= amount_out);
+            assert!(calculate_output_with_fee(max_in - 1, reserve_in, reserve_out, fee_bps) < amount_out);
+        }
+
+        // 1_000_000 x 1_000_000 pool, 30 bps: 10_000 out needs 10_102 net, 10_133 gross
+        assert_eq!(max_in_for_exact_out(10_000, 1_000_000, 1_000_000, 30, 0), 10_133);
+        assert_eq!(max_in_for_exact_out(10_000, 1_000_000, 1_000_000, 30, 50), 10_184);
+
+        assert_eq!(max_in_for_exact_out(0, 1_000, 1_000, 30, 0), 0);
+        assert_eq!(max_in_for_exact_out(1_000, 1_000, 1_000, 30, 0), 0);
+        assert_eq!(max_in_for_exact_out(1, 0, 1_000, 30, 0), 0);
+        assert_eq!(max_in_for_exact_out(1, 1_000, 1_000, 10_000, 0), 0);
+        assert_eq!(max_in_for_exact_out(u64::MAX - 1, u64::MAX, u64::MAX, 0, 0), 0);
+    }
+
+    #[test]
+    fn test_checked_sum() {
+        assert_eq!(checked_sum(&[]), Some(0));
+        assert_eq!(checked_sum(&[1, 2, 3]), Some(6));
+        assert_eq!(checked_sum(&[u64::MAX, 0]), Some(u64::MAX));
+        assert_eq!(checked_sum(&[u64::MAX - 1, 1, 1]), None);
+    }
+
+    #[test]
+    fn test_checked_reserve_after() {
+        assert_eq!(checked_reserve_after(1_000, 400, true), Some(1_400));
+        assert_eq!(checked_reserve_after(1_000, 400, false), Some(600));
+        assert_eq!(checked_reserve_after(1_000, 1_000, false), Some(0));
+        assert_eq!(checked_reserve_after(1_000, 1_001, false), None);
+        assert_eq!(checked_reserve_after(u64::MAX, 0, true), Some(u64::MAX));
+        assert_eq!(checked_reserve_after(u64::MAX - 5, 6, true), None);
+    }
+
+    #[test]
+    fn test_amount_wrappers() {
+        assert_eq!(core::mem::size_of::<Lamports>(), 8);
+        assert_eq!(core::mem::size_of::<TokenAmount>(), 8);
+        assert_eq!(Lamports(5).checked_add(Lamports(7)), Some(Lamports(12)));
+        assert_eq!(Lamports(u64::MAX).checked_add(Lamports(1)), None);
+        assert_eq!(Lamports(1_000).as_wsol(), TokenAmount(1_000));
+        assert_eq!(TokenAmount(3).saturating_sub(TokenAmount(5)), TokenAmount(0));
+        assert_eq!((Lamports(9).get(), TokenAmount(9).get()), (9, 9));
+    }
+
+    /// Instructions sysvar with one instruction per `(program_id, num_accounts)`
+    fn instructions_fixture(programs: &[(&Address, usize)], current: u16) -> ([u8; 1024], usize) {
+        let mut data = [0u8; 1024];
+        data[0..2].copy_from_slice(&(programs.len() as u16).to_le_bytes());
+        let mut offset = 2 + programs.len() * 2;
+        for (i, (program_id, num_accounts)) in programs.iter().enumerate() {
+            data[2 + i * 2..4 + i * 2].copy_from_slice(&(offset as u16).to_le_bytes());
+            data[offset..offset + 2].copy_from_slice(&(*num_accounts as u16).to_le_bytes());
+            offset += 2 + num_accounts * 33;
+            data[offset..offset + 32].copy_from_slice(program_id.as_array());
+            // empty instruction data
+            offset += 32 + 2;
+        }
+        data[offset..offset + 2].copy_from_slice(&current.to_le_bytes());
+        (data, offset + 2)
+    }
+
+    #[test]
+    fn test_find_prior_swap() {
+        let programs = [
+            (&crate::whirlpool::ID, 3),
+            (&SYSTEM_PROGRAM_ID, 2),
+            (&crate::whirlpool::ID, 4),
+            (&crate::raydium_cp::ID, 5),
+            (&crate::whirlpool::ID, 4),
+        ];
+        let (data, len) = instructions_fixture(&programs, 3);
+        let data = &data[..len];
+        // Latest match before the current instruction (index 3)
+        assert_eq!(find_prior_swap(data, Dex::Whirlpool), Some(2));
+        assert_eq!(find_prior_swap(data, Dex::RaydiumCp), None);
+        assert_eq!(find_prior_swap(data, Dex::SolfiV2), None);
+
+        let (data, len) = instructions_fixture(&programs, 0);
+        assert_eq!(find_prior_swap(&data[..len], Dex::Whirlpool), None);
+        assert_eq!(find_prior_swap(&[], Dex::Whirlpool), None);
+        assert_eq!(INSTRUCTIONS_SYSVAR.to_bytes(), crate::humidifi::INSTRUCTIONS_SYSVAR.to_bytes());
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(is_retryable(0x17, Dex::SolfiV2));
+        assert!(is_retryable(23, Dex::SolfiV2));
+        assert!(!is_retryable(24, Dex::SolfiV2));
+        // Same code, other program
+        assert!(!is_retryable(23, Dex::HumidiFi));
+
+        assert!(is_retryable(6024, Dex::OpenbookV2));
+        assert!(is_retryable(6003, Dex::Perps));
+        // Slippage is fatal: Raydium CP ExceededSlippage, Perps MaxPriceSlippage
+        assert!(!is_retryable(6005, Dex::RaydiumCp));
+        assert!(!is_retryable(6021, Dex::Perps));
+        assert!(!is_retryable(CpiError::SlippageExceeded as u32, Dex::Whirlpool));
+    }
+
+    #[test]
+    fn test_minimum_balance() {
+        assert_eq!(RENT_SYSVAR.to_bytes()[..4], [6, 167, 213, 23]);
+        assert_eq!(minimum_balance(0), 890_880);
+        // SPL mint, SPL token account
+        assert_eq!(minimum_balance(82), 1_461_600);
+        assert_eq!(minimum_balance(165), 2_039_280);
+        // 10 KiB tick array
+        assert_eq!(minimum_balance(10_240), 72_161_280);
+    }
+
+    #[test]
+    fn test_byte_readers() {
+        let mut data = [0u8; 64];
+        data[1..33].copy_from_slice(&[7u8; 32]);
+        data[33..41].copy_from_slice(&u64::MAX.to_le_bytes());
+        data[41..57].copy_from_slice(&(1u128 << 100 | 9).to_le_bytes());
+
+        assert_eq!(read_pubkey(&data, 1), Some([7u8; 32]));
+        assert_eq!(read_u64_le(&data, 33), Some(u64::MAX));
+        assert_eq!(read_u128_le(&data, 41), Some(1u128 << 100 | 9));
+        assert_eq!(read_u64_le(&data, 56), Some(0));
+        assert_eq!(read_bytes::<0>(&data, 64), Some([]));
+
+        assert_eq!(read_pubkey(&data, 33), None);
+        assert_eq!(read_u64_le(&data, 57), None);
+        assert_eq!(read_u128_le(&data, 49), None);
+        assert_eq!(read_u64_le(&data, 100), None);
+        assert_eq!(read_u64_le(&data, usize::MAX - 3), None);
+        assert_eq!(read_u64_le(&[], 0), None);
+    }
+
+    #[test]
+    fn test_check_deadline() {
+        let mut data = [0u8; ClockLayout::SIZE];
+        data[32..40].copy_from_slice(&1_700_000_000i64.to_le_bytes());
+
+        assert_eq!(check_deadline(&data, Some(1_700_000_001)), Ok(()));
+        assert_eq!(check_deadline(&data, Some(1_700_000_000)), Ok(()));
+        assert_eq!(check_deadline(&data, Some(1_699_999_999)), Err(CpiError::DeadlineExceeded));
+        assert_eq!(check_deadline(&data, None), Ok(()));
+        assert_eq!(check_deadline(&[], None), Ok(()));
+        assert_eq!(check_deadline(&data[..39], Some(1_700_000_000)), Err(CpiError::InvalidClock));
+    }
+
+    #[test]
+    fn test_token_program_for_mint() {
+        assert_eq!(token_program_for_mint(&TOKEN_PROGRAM_ID), Some(TOKEN_PROGRAM_ID));
+        assert_eq!(token_program_for_mint(&TOKEN_2022_PROGRAM_ID), Some(TOKEN_2022_PROGRAM_ID));
+        assert_eq!(token_program_for_mint(&SYSTEM_PROGRAM_ID), None);
+        assert_eq!(token_program_for_mint(&crate::solfi_v2::TOKEN_2022_PROGRAM), Some(TOKEN_2022_PROGRAM_ID));
+    }
+
+    #[test]
+    fn test_is_account_frozen() {
+        let mut data = [0u8; TokenLayout::ACCOUNT_SIZE];
+        assert!(!is_account_frozen(&data));
+        data[TokenLayout::STATE_OFFSET] = 1;
+        assert!(!is_account_frozen(&data));
+        data[TokenLayout::STATE_OFFSET] = TokenLayout::STATE_FROZEN;
+        assert!(is_account_frozen(&data));
+        assert!(!is_account_frozen(&data[..TokenLayout::STATE_OFFSET]));
+    }
+
+    #[test]
+    fn test_is_native_sol_account() {
+        let mut data = [0u8; TokenLayout::ACCOUNT_SIZE];
+        data[..32].copy_from_slice(NATIVE_MINT.as_ref());
+        data[TokenLayout::STATE_OFFSET] = 1;
+        // A USDC-style account: is_native = None
+        assert!(!is_native_sol_account(&data));
+
+        // WSOL account: Some(rent_exempt_reserve)
+        data[TokenLayout::IS_NATIVE_OFFSET..TokenLayout::IS_NATIVE_OFFSET + 4].copy_from_slice(&1u32.to_le_bytes());
+        data[TokenLayout::IS_NATIVE_OFFSET + 4..TokenLayout::IS_NATIVE_OFFSET + 12]
+            .copy_from_slice(&2_039_280u64.to_le_bytes());
+        assert!(is_native_sol_account(&data));
+        assert!(!is_native_sol_account(&data[..TokenLayout::IS_NATIVE_OFFSET + 3]));
+
+        // Invalid tag
+        data[TokenLayout::IS_NATIVE_OFFSET] = 2;
+        assert!(!is_native_sol_account(&data));
+    }
+
+    #[test]
+    fn test_pool_reserves_with_mints() {
+        let mut base_vault = [0u8; TokenLayout::ACCOUNT_SIZE];
+        base_vault[64..72].copy_from_slice(&5_000_000_000u64.to_le_bytes());
+        let mut quote_vault = [0u8; TokenLayout::ACCOUNT_SIZE];
+        quote_vault[64..72].copy_from_slice(&750_000_000u64.to_le_bytes());
+        let mut base_mint = [0u8; TokenLayout::MINT_SIZE];
+        base_mint[44] = 9;
+        base_mint[45] = 1;
+        let mut quote_mint = [0u8; TokenLayout::MINT_SIZE];
+        quote_mint[44] = 6;
+        quote_mint[45] = 1;
+
+        assert_eq!(
+            pool_reserves_with_mints(&base_vault, &quote_vault, &base_mint, &quote_mint),
+            Some((5_000_000_000, 9, 750_000_000, 6))
+        );
+        assert_eq!(
+            pool_reserves_with_mints(&base_vault, &quote_vault[..70], &base_mint, &quote_mint),
+            None
+        );
+        assert_eq!(
+            pool_reserves_with_mints(&base_vault, &quote_vault, &base_mint, &quote_mint[..44]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pda_cache_hits() {
+        let program_id = Address::new_from_array([9u8; 32]);
+        let pool = [4u8; 32];
+        let seeds: [&[u8]; 2] = [b"authority", &pool];
+        let mut cache = PdaCache::<2>::new();
+        let mut derivations = 0;
+
+        for _ in 0..3 {
+            let (address, bump) = cache.get_or_insert_with(&seeds, &program_id, || {
+                derivations += 1;
+                (Address::new_from_array([1u8; 32]), 253)
+            });
+            assert_eq!(address, Address::new_from_array([1u8; 32]));
+            assert_eq!(bump, 253);
+        }
+        assert_eq!(derivations, 1);
+
+        // Same bytes split differently are a different key
+        assert_eq!(cache.get(&[b"authority" as &[u8], &pool[..16], &pool[16..]], &program_id), None);
+        // Different program
+        assert_eq!(cache.get(&seeds, &Address::new_from_array([8u8; 32])), None);
+    }
+
+    #[test]
+    fn test_parse_or_derive_authority() {
+        let mut pool = [0u8; 72];
+        pool[8..40].copy_from_slice(&[6u8; 32]);
+        let seeds: [&[u8]; 1] = [b"vault_and_lp_mint_auth_seed"];
+        let derived = Address::new_from_array([3u8; 32]);
+        let mut derivations = 0;
+        let mut derive = |s: &[&[u8]]| {
+            derivations += 1;
+            assert_eq!(s, &seeds);
+            Some(derived.clone())
+        };
+
+        // Stored
+        assert_eq!(parse_authority(&pool, 8), Some([6u8; 32]));
+        assert_eq!(parse_or_derive_authority(&pool, Some(8), &seeds, &mut derive), Some([6u8; 32]));
+        // Stored but unset, and not stored at all
+        assert_eq!(parse_authority(&pool, 40), None);
+        assert_eq!(parse_or_derive_authority(&pool, Some(40), &seeds, &mut derive), Some([3u8; 32]));
+        assert_eq!(parse_or_derive_authority(&[], None, &seeds, &mut derive), Some([3u8; 32]));
+        // Truncated state is an error, not a reason to derive
+        assert_eq!(parse_or_derive_authority(&pool[..39], Some(8), &seeds, &mut derive), None);
+        assert_eq!(derivations, 2);
+        assert_eq!(parse_or_derive_authority(&[], None, &seeds, |_| None), None);
+    }
+
+    #[test]
+    fn test_pda_cache_evicts_oldest() {
+        let program_id = Address::new_from_array([9u8; 32]);
+        let seeds: [[&[u8]; 1]; 3] = [[b"a"], [b"b"], [b"c"]];
+        let mut cache = PdaCache::<2>::new();
+        for (i, seed) in seeds.iter().enumerate() {
+            cache.insert(seed, &program_id, &Address::new_from_array([i as u8; 32]), i as u8);
+        }
+        assert_eq!(cache.get(&[b"a"], &program_id), None);
+        assert_eq!(cache.get(&[b"b"], &program_id).map(|hit| hit.1), Some(1));
+        assert_eq!(cache.get(&[b"c"], &program_id).map(|hit| hit.1), Some(2));
+    }
+
+    #[test]
+    fn test_pda_cache_compares_seeds() {
+        let program_id = Address::new_from_array([9u8; 32]);
+        let (alice, mallory) = ([1u8; 32], [2u8; 32]);
+        let alice_seeds: [&[u8]; 2] = [b"position", &alice];
+        let mallory_seeds: [&[u8]; 2] = [b"position", &mallory];
+        let mut cache = PdaCache::<2>::new();
+        cache.insert(&alice_seeds, &program_id, &Address::new_from_array([7u8; 32]), 250);
+
+        // Seeds the caller picks never hit another user's entry
+        assert_eq!(cache.get(&mallory_seeds, &program_id), None);
+        assert_eq!(cache.get(&[b"position", &[1u8; 32]], &program_id).map(|hit| hit.1), Some(250));
+        assert!(core::mem::size_of::<PdaCache<8>>() <= 8 * 112);
+    }
+}
@@ -0,0 +1,54 @@
+//! Penalties that grow with the age of oracle or pool data
+//!
+//! Quotes built from data that is several slots old are less likely to
+//! fill at the quoted price. `exp_penalty_bps` charges for that age with an
+//! exponential curve, so any slot- or time-sensitive module can haircut a
+//! quote the same way.
+
+use super::math::{ONE_X64, mul_div, pow_frac};
+
+/// Penalty for data `age` units old: `max_bps * (1 - 2^(-age / half_life))`
+///
+/// Zero for fresh data, half of `max_bps` after one `half_life` and
+/// approaching (never exceeding) `max_bps` as the data ages; `age` and
+/// `half_life` share a unit (slots, seconds). Fixed point via
+/// `math::pow_frac`, rounded down. A zero `half_life` means any age > 0
+/// costs the full `max_bps`.
+pub fn exp_penalty_bps(age: u64, half_life: u64, max_bps: u64) -> u64 {
+    if age == 0 {
+        return 0;
+    }
+    if half_life == 0 {
+        return max_bps;
+    }
+    let remaining_x64 = pow_frac(ONE_X64 / 2, age, half_life);
+    mul_div(max_bps as u128, ONE_X64 - remaining_x64.min(ONE_X64), ONE_X64, false).map_or(max_bps, |bps| bps as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exp_penalty_age_zero() {
+        assert_eq!(exp_penalty_bps(0, 10, 500), 0);
+        assert_eq!(exp_penalty_bps(0, 0, 500), 0);
+    }
+
+    #[test]
+    fn test_exp_penalty_half_lives() {
+        assert_eq!(exp_penalty_bps(10, 10, 500), 250);
+        assert_eq!(exp_penalty_bps(20, 10, 500), 375);
+        // Half a half-life: 1 - 2^-0.5 ~= 0.2929
+        assert_eq!(exp_penalty_bps(5, 10, 10_000), 2_928);
+        assert!(exp_penalty_bps(4, 10, 500) < exp_penalty_bps(5, 10, 500));
+    }
+
+    #[test]
+    fn test_exp_penalty_caps_at_max() {
+        assert_eq!(exp_penalty_bps(1_000, 10, 500), 500);
+        assert_eq!(exp_penalty_bps(u64::MAX, 1, 10_000), 10_000);
+        assert_eq!(exp_penalty_bps(1, 0, 500), 500);
+        assert_eq!(exp_penalty_bps(30, 10, 0), 0);
+    }
+}
@@ -0,0 +1,67 @@
+//! Fee rates in the denominators pools store them in
+//!
+//! Pools keep fee rates over different bases: bps (`/ 10_000`), millionths
+//! (`/ 1_000_000`, Raydium CP, Byreal) or billionths (`/ 1_000_000_000`,
+//! Meteora DBC). `normalize_to_bps` brings
+//! them onto one scale so quote helpers can compare and apply fees alike.
+
+/// Denominator a raw fee rate is expressed over
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeBase {
+    /// Basis points, `raw / 10_000`
+    Bps,
+    /// Millionths, `raw / 1_000_000`
+    Millionths,
+    /// Billionths, `raw / 1_000_000_000`
+    Billionths,
+}
+
+impl FeeBase {
+    /// Value of a 100% fee in this base
+    #[inline(always)]
+    pub const fn denominator(self) -> u64 {
+        match self {
+            FeeBase::Bps => 10_000,
+            FeeBase::Millionths => 1_000_000,
+            FeeBase::Billionths => 1_000_000_000,
+        }
+    }
+}
+
+/// Convert a raw fee rate over `base` to basis points
+///
+/// Rounds up, so a fee finer than 1 bp is never quoted as free: 2_500
+/// millionths is 25 bps, 1 millionth is 1 bp.
+#[inline(always)]
+pub const fn normalize_to_bps(raw: u64, base: FeeBase) -> u64 {
+    (raw as u128 * 10_000).div_ceil(base.denominator() as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_bps() {
+        assert_eq!(normalize_to_bps(0, FeeBase::Bps), 0);
+        assert_eq!(normalize_to_bps(30, FeeBase::Bps), 30);
+        assert_eq!(normalize_to_bps(u64::MAX, FeeBase::Bps), u64::MAX);
+    }
+
+    #[test]
+    fn test_normalize_millionths() {
+        // Raydium CP 0.25% tier
+        assert_eq!(normalize_to_bps(2_500, FeeBase::Millionths), 25);
+        assert_eq!(normalize_to_bps(1_000_000, FeeBase::Millionths), 10_000);
+        assert_eq!(normalize_to_bps(1, FeeBase::Millionths), 1);
+        assert_eq!(normalize_to_bps(101, FeeBase::Millionths), 2);
+    }
+
+    #[test]
+    fn test_normalize_billionths() {
+        assert_eq!(normalize_to_bps(2_500_000, FeeBase::Billionths), 25);
+        assert_eq!(normalize_to_bps(1_000_000_000, FeeBase::Billionths), 10_000);
+        assert_eq!(normalize_to_bps(100_001, FeeBase::Billionths), 2);
+        assert_eq!(normalize_to_bps(0, FeeBase::Billionths), 0);
+    }
+}
@@ -0,0 +1,180 @@
+//! SHA-256 for discriminators and seeds
+//!
+//! A small `const fn` implementation (FIPS 180-4), so Anchor discriminators
+//! can be computed or checked at compile time without a hashing dependency.
+//! Not constant-time; only hash public data with it.
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const H0: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const fn compress(mut state: [u32; 8], block: &[u8; 64]) -> [u32; 8] {
+    let mut w = [0u32; 64];
+    let mut i = 0;
+    while i < 16 {
+        w[i] = u32::from_be_bytes([block[4 * i], block[4 * i + 1], block[4 * i + 2], block[4 * i + 3]]);
+        i += 1;
+    }
+    while i < 64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        i += 1;
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+    i = 0;
+    while i < 64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+        i += 1;
+    }
+
+    let out = [a, b, c, d, e, f, g, h];
+    i = 0;
+    while i < 8 {
+        state[i] = state[i].wrapping_add(out[i]);
+        i += 1;
+    }
+    state
+}
+
+/// SHA-256 of the concatenation of `parts`
+///
+/// Saves copying seeds into one buffer, e.g. `hashv(&[b"global:", name])`.
+pub const fn hashv(parts: &[&[u8]]) -> [u8; 32] {
+    let mut state = H0;
+    let mut block = [0u8; 64];
+    let mut used = 0;
+    let mut total: u64 = 0;
+
+    let mut p = 0;
+    while p < parts.len() {
+        let part = parts[p];
+        let mut i = 0;
+        while i < part.len() {
+            block[used] = part[i];
+            used += 1;
+            if used == 64 {
+                state = compress(state, &block);
+                used = 0;
+            }
+            i += 1;
+        }
+        total += part.len() as u64;
+        p += 1;
+    }
+
+    // Padding: 0x80, zeros, then the bit length in the last 8 bytes
+    block[used] = 0x80;
+    used += 1;
+    if used > 56 {
+        while used < 64 {
+            block[used] = 0;
+            used += 1;
+        }
+        state = compress(state, &block);
+        used = 0;
+    }
+    while used < 56 {
+        block[used] = 0;
+        used += 1;
+    }
+    let bits = (total * 8).to_be_bytes();
+    let mut i = 0;
+    while i < 8 {
+        block[56 + i] = bits[i];
+        i += 1;
+    }
+    state = compress(state, &block);
+
+    let mut out = [0u8; 32];
+    i = 0;
+    while i < 8 {
+        let word = state[i].to_be_bytes();
+        out[4 * i] = word[0];
+        out[4 * i + 1] = word[1];
+        out[4 * i + 2] = word[2];
+        out[4 * i + 3] = word[3];
+        i += 1;
+    }
+    out
+}
+
+/// SHA-256 of `data`
+#[inline(always)]
+pub const fn sha256(data: &[u8]) -> [u8; 32] {
+    hashv(&[data])
+}
+
+/// Anchor discriminator: first 8 bytes of `sha256("<namespace>:<name>")`
+///
+/// `namespace` is `"global"` for instructions (snake_case name) and
+/// `"account"` for accounts (PascalCase name).
+pub const fn anchor_discriminator(namespace: &str, name: &str) -> [u8; 8] {
+    let hash = hashv(&[namespace.as_bytes(), b":", name.as_bytes()]);
+    let mut out = [0u8; 8];
+    let mut i = 0;
+    while i < 8 {
+        out[i] = hash[i];
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_vectors() {
+        assert_eq!(sha256(b""), [
+            0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f, 0xb9, 0x24,
+            0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b, 0x78, 0x52, 0xb8, 0x55,
+        ]);
+        assert_eq!(sha256(b"abc"), [
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+            0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+        ]);
+        // 56 bytes: the length no longer fits the first block
+        assert_eq!(sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"), [
+            0x24, 0x8d, 0x6a, 0x61, 0xd2, 0x06, 0x38, 0xb8, 0xe5, 0xc0, 0x26, 0x93, 0x0c, 0x3e, 0x60, 0x39,
+            0xa3, 0x3c, 0xe4, 0x59, 0x64, 0xff, 0x21, 0x67, 0xf6, 0xec, 0xed, 0xd4, 0x19, 0xdb, 0x06, 0xc1,
+        ]);
+        assert_eq!(hashv(&[b"ab", b"", b"c"]), sha256(b"abc"));
+    }
+
+    #[test]
+    fn test_anchor_discriminator() {
+        const INITIALIZE: [u8; 8] = anchor_discriminator("global", "initialize");
+        assert_eq!(INITIALIZE, crate::raydium_launchlab::INITIALIZE);
+        assert_eq!(anchor_discriminator("account", "WooPool"), crate::woofi::WOO_POOL_DISCRIMINATOR);
+        assert_eq!(
+            anchor_discriminator("account", "TickArrayState"),
+            crate::pancakeswap::TICK_ARRAY_STATE_DISCRIMINATOR
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Debug logs for outgoing CPIs (`debug-logs` feature)
+//!
+//! Every CPI made through `common::invoke_signed` logs the target DEX, the
+//! account count and the instruction data as hex (discriminator followed by
+//! the encoded args), and the router logs each leg's amounts. pinocchio 0.10
+//! has no `msg!`, so lines are formatted into a fixed buffer and written
+//! with the `sol_log_` syscall; off-chain they are formatted and dropped.
+//!
+//! Without the feature `debug_log!` expands to nothing and its arguments are
+//! never evaluated, so release builds pay no compute units.
+
+use pinocchio::instruction::InstructionView;
+
+/// Longest logged line; longer lines are truncated
+pub const LOG_LINE_MAX_LEN: usize = 256;
+
+/// Log a formatted line under `debug-logs`, compiled out otherwise
+macro_rules! debug_log {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "debug-logs")]
+        $crate::common::log::log_fmt(format_args!($($arg)*));
+    };
+}
+pub(crate) use debug_log;
+
+/// Fixed-capacity line buffer that truncates instead of failing
+#[cfg(feature = "debug-logs")]
+struct LogLine {
+    buf: [u8; LOG_LINE_MAX_LEN],
+    len: usize,
+}
+
+#[cfg(feature = "debug-logs")]
+impl core::fmt::Write for LogLine {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let n = s.len().min(LOG_LINE_MAX_LEN - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        Ok(())
+    }
+}
+
+/// Format `args` and write them to the program log
+#[cfg(feature = "debug-logs")]
+pub fn log_fmt(args: core::fmt::Arguments) {
+    let mut line = LogLine { buf: [0u8; LOG_LINE_MAX_LEN], len: 0 };
+    let _ = core::fmt::write(&mut line, args);
+    #[cfg(any(target_os = "solana", target_arch = "bpf"))]
+    // SAFETY: `buf[..len]` is initialized and outlives the call
+    unsafe {
+        pinocchio::syscalls::sol_log_(line.buf.as_ptr(), line.len as u64)
+    };
+}
+
+/// Lowercase hex `Display` for instruction data
+#[cfg(feature = "debug-logs")]
+struct Hex<'a>(&'a [u8]);
+
+#[cfg(feature = "debug-logs")]
+impl core::fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+/// Log the DEX, account count and data of an instruction about to be invoked
+#[inline(always)]
+pub fn log_instruction(instruction: &InstructionView) {
+    debug_log!(
+        "CPI {:?} accounts={} data={}",
+        super::Dex::from_program_id(instruction.program_id),
+        instruction.accounts.len(),
+        Hex(instruction.data)
+    );
+    #[cfg(not(feature = "debug-logs"))]
+    let _ = instruction;
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(not(feature = "debug-logs"))]
+    fn test_debug_log_compiled_out() {
+        // Only referenced inside `debug_log!`, which drops its arguments
+        #[allow(dead_code)]
+        fn never() -> u64 {
+            panic!("debug_log! arguments must not be evaluated without debug-logs")
+        }
+        debug_log!("amount_in={}", never());
+    }
+
+    #[test]
+    #[cfg(feature = "debug-logs")]
+    fn test_log_line_truncates() {
+        use core::fmt::Write;
+        let mut line = super::LogLine { buf: [0u8; super::LOG_LINE_MAX_LEN], len: 0 };
+        for _ in 0..100 {
+            write!(line, "{}", super::Hex(&[0xab, 0xcd])).unwrap();
+        }
+        assert_eq!(line.len, super::LOG_LINE_MAX_LEN);
+        assert_eq!(&line.buf[..4], b"abcd");
+    }
+}
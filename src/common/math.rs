@@ -0,0 +1,284 @@
+//! Fixed-point math for pool pricing
+//!
+//! All values are unsigned Q64.64 (`x64`): the real value is `raw / 2^64`.
+//! `U256` holds the 256-bit intermediates of sqrt-price curve math.
+
+/// 1.0 in Q64.64
+pub const ONE_X64: u128 = 1 << 64;
+
+/// ln(2) in Q64.64
+const LN2_X64: u128 = 12_786_308_645_202_655_659;
+
+/// Fractional exponentiation: `base ^ (exp_num / exp_den)` in Q64.64
+///
+/// Needed by weighted (Balancer-style) pools, where the swap output is
+/// `balance_out * (1 - (balance_in / (balance_in + amount_in)) ^ (w_in / w_out))`.
+///
+/// Computed as `2 ^ (e * log2(base))`: `log2` by repeated squaring of the
+/// normalized mantissa, `2^f` by the Taylor series of `e^(f * ln 2)`.
+///
+/// # Precision
+/// Error is below `1e-15` relative plus a few ulps (2^-64) absolute; every
+/// step truncates, so results round towards zero (safe for output quotes).
+/// Results above `u128::MAX` saturate to `u128::MAX`, results below one ulp
+/// return `0`. `exp_den == 0` returns `0`.
+pub fn pow_frac(base_x64: u128, exp_num: u64, exp_den: u64) -> u128 {
+    if exp_den == 0 {
+        return 0;
+    }
+    if exp_num == 0 {
+        return ONE_X64;
+    }
+    if base_x64 == 0 {
+        return 0;
+    }
+    if base_x64 == ONE_X64 {
+        return ONE_X64;
+    }
+
+    // y = log2(base) * exp_num / exp_den, as sign + Q64.64 magnitude
+    let (negative, log2) = log2_x64(base_x64);
+    let den = exp_den as u128;
+    let num = exp_num as u128;
+    let whole = match (log2 / den).checked_mul(num) {
+        Some(whole) => whole,
+        None => return if negative { 0 } else { u128::MAX },
+    };
+    let y = match whole.checked_add((log2 % den) * num / den) {
+        Some(y) => y,
+        None => return if negative { 0 } else { u128::MAX },
+    };
+
+    exp2_x64(negative, y)
+}
+
+/// log2 of a Q64.64 value, returned as (is_negative, |log2| in Q64.64)
+fn log2_x64(x: u128) -> (bool, u128) {
+    let msb = 127 - x.leading_zeros();
+    let (negative, int_part) = if msb >= 64 {
+        (false, (msb - 64) as u128)
+    } else {
+        (true, (64 - msb) as u128)
+    };
+
+    // Normalize the mantissa into [1, 2) as Q1.63
+    let mut m = if msb >= 63 { x >> (msb - 63) } else { x << (63 - msb) };
+    let mut frac: u128 = 0;
+    for bit in (0..64).rev() {
+        m = (m * m) >> 63;
+        if m >= 1 << 64 {
+            m >>= 1;
+            frac |= 1 << bit;
+        }
+    }
+
+    if negative {
+        // log2(x) = -(int_part) + frac
+        ((int_part << 64) > frac, (int_part << 64).abs_diff(frac))
+    } else {
+        (false, (int_part << 64) | frac)
+    }
+}
+
+/// 2^(±y) for a Q64.64 magnitude `y`
+fn exp2_x64(negative: bool, y: u128) -> u128 {
+    let int_part = y >> 64;
+    let frac = y & (ONE_X64 - 1);
+
+    // For negative exponents: 2^-y = 2^-(n+1) * 2^(1-f)
+    let (shift, frac) = if negative && frac != 0 {
+        (int_part + 1, ONE_X64 - frac)
+    } else {
+        (int_part, frac)
+    };
+
+    // 2^f = e^(f ln2) = sum (f ln2)^k / k!, f ln2 < 0.7 so 30 terms reach 2^-64
+    let x = (frac * LN2_X64) >> 64;
+    let mut term = ONE_X64;
+    let mut sum = ONE_X64;
+    for k in 1..30u128 {
+        term = ((term * x) >> 64) / k;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+
+    if negative {
+        if shift >= 128 { 0 } else { sum >> shift }
+    } else if shift > 62 || sum.leading_zeros() < shift as u32 {
+        u128::MAX
+    } else {
+        sum << shift
+    }
+}
+
+/// Minimal 256-bit unsigned integer for `L * sqrt_price` style products
+///
+/// Only the operations the curve math needs. Field order makes the derived
+/// `Ord` numeric.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct U256 {
+    pub hi: u128,
+    pub lo: u128,
+}
+
+impl U256 {
+    pub const ZERO: Self = Self { hi: 0, lo: 0 };
+
+    #[inline(always)]
+    pub const fn from_u128(x: u128) -> Self {
+        Self { hi: 0, lo: x }
+    }
+
+    /// Full 128 x 128 -> 256 bit product
+    pub const fn full_mul(a: u128, b: u128) -> Self {
+        const MASK: u128 = u64::MAX as u128;
+        let (a_lo, a_hi) = (a & MASK, a >> 64);
+        let (b_lo, b_hi) = (b & MASK, b >> 64);
+
+        let ll = a_lo * b_lo;
+        let lh = a_lo * b_hi;
+        let hl = a_hi * b_lo;
+        let hh = a_hi * b_hi;
+
+        let mid = (ll >> 64) + (lh & MASK) + (hl & MASK);
+        let lo = (ll & MASK) | (mid << 64);
+        let hi = hh + (lh >> 64) + (hl >> 64) + (mid >> 64);
+        Self { hi, lo }
+    }
+
+    /// Narrow to u128, `None` if the value does not fit
+    #[inline(always)]
+    pub const fn to_u128(self) -> Option<u128> {
+        if self.hi == 0 { Some(self.lo) } else { None }
+    }
+
+    pub const fn checked_add(self, other: Self) -> Option<Self> {
+        let (lo, carry) = self.lo.overflowing_add(other.lo);
+        let Some(hi) = self.hi.checked_add(other.hi) else {
+            return None;
+        };
+        match hi.checked_add(carry as u128) {
+            Some(hi) => Some(Self { hi, lo }),
+            None => None,
+        }
+    }
+
+    pub const fn wrapping_sub(self, other: Self) -> Self {
+        let (lo, borrow) = self.lo.overflowing_sub(other.lo);
+        let hi = self.hi.wrapping_sub(other.hi).wrapping_sub(borrow as u128);
+        Self { hi, lo }
+    }
+
+    /// Left shift by `n < 256` bits, dropping overflow
+    pub const fn shl(self, n: u32) -> Self {
+        match n {
+            0 => self,
+            1..=127 => Self { hi: (self.hi << n) | (self.lo >> (128 - n)), lo: self.lo << n },
+            128..=255 => Self { hi: self.lo << (n - 128), lo: 0 },
+            _ => Self::ZERO,
+        }
+    }
+
+    #[inline(always)]
+    const fn bit(self, i: u32) -> bool {
+        if i >= 128 { (self.hi >> (i - 128)) & 1 == 1 } else { (self.lo >> i) & 1 == 1 }
+    }
+
+    #[inline(always)]
+    const fn bits(self) -> u32 {
+        if self.hi != 0 { 256 - self.hi.leading_zeros() } else { 128 - self.lo.leading_zeros() }
+    }
+
+    /// Quotient and remainder, `None` when dividing by zero
+    ///
+    /// Binary long division; only as many steps as the dividend has bits.
+    pub fn div_rem(self, divisor: Self) -> Option<(Self, Self)> {
+        if divisor == Self::ZERO {
+            return None;
+        }
+        let mut quotient = Self::ZERO;
+        let mut remainder = Self::ZERO;
+        for i in (0..self.bits()).rev() {
+            let carry = remainder.hi >> 127 == 1;
+            remainder = remainder.shl(1);
+            remainder.lo |= self.bit(i) as u128;
+            if carry || remainder >= divisor {
+                remainder = remainder.wrapping_sub(divisor);
+                quotient = quotient.checked_add(Self::from_u128(1).shl(i))?;
+            }
+        }
+        Some((quotient, remainder))
+    }
+
+    /// `self / divisor`, rounded up when `round_up`
+    pub fn div_round(self, divisor: Self, round_up: bool) -> Option<Self> {
+        let (quotient, remainder) = self.div_rem(divisor)?;
+        if round_up && remainder != Self::ZERO {
+            quotient.checked_add(Self::from_u128(1))
+        } else {
+            Some(quotient)
+        }
+    }
+}
+
+/// `a * b / c` with a 256-bit intermediate, `None` on overflow or `c == 0`
+#[inline(always)]
+pub fn mul_div(a: u128, b: u128, c: u128, round_up: bool) -> Option<u128> {
+    U256::full_mul(a, b).div_round(U256::from_u128(c), round_up)?.to_u128()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: u128, expected: u128) {
+        // 1e-15 relative tolerance
+        let tolerance = expected / 1_000_000_000_000_000 + 1;
+        assert!(
+            actual.abs_diff(expected) <= tolerance,
+            "actual {actual} expected {expected}"
+        );
+    }
+
+    #[test]
+    fn test_pow_frac_known_values() {
+        // 2^0.5
+        assert_close(pow_frac(2 * ONE_X64, 1, 2), 26_087_635_650_665_564_424);
+        // 10^0.8
+        assert_close(pow_frac(10 * ONE_X64, 4, 5), 116_391_086_550_535_208_933);
+        // 0.5^0.5
+        assert_close(pow_frac(ONE_X64 / 2, 1, 2), 13_043_817_825_332_782_212);
+        // 3^2
+        assert_close(pow_frac(3 * ONE_X64, 2, 1), 9 * ONE_X64);
+    }
+
+    #[test]
+    fn test_pow_frac_edges() {
+        assert_eq!(pow_frac(5 * ONE_X64, 0, 3), ONE_X64);
+        assert_eq!(pow_frac(ONE_X64, 7, 3), ONE_X64);
+        assert_eq!(pow_frac(0, 1, 2), 0);
+        assert_eq!(pow_frac(2 * ONE_X64, 1, 0), 0);
+        assert_eq!(pow_frac(2 * ONE_X64, 200, 1), u128::MAX);
+        assert_eq!(pow_frac(ONE_X64 / 2, 200, 1), 0);
+    }
+
+    #[test]
+    fn test_u256_mul_div() {
+        let max = u128::MAX;
+        assert_eq!(U256::full_mul(max, max), U256 { hi: max - 1, lo: 1 });
+        assert_eq!(U256::full_mul(1 << 64, 1 << 64), U256 { hi: 1, lo: 0 });
+
+        assert_eq!(mul_div(max, max, max, false), Some(max));
+        assert_eq!(mul_div(max, 3, 4, false), Some(max / 4 * 3 + 2));
+        assert_eq!(mul_div(10, 10, 3, false), Some(33));
+        assert_eq!(mul_div(10, 10, 3, true), Some(34));
+        assert_eq!(mul_div(max, 2, 1, false), None);
+        assert_eq!(mul_div(1, 1, 0, false), None);
+
+        let n = U256::full_mul(max, 1 << 100);
+        let d = U256::full_mul(1 << 90, 1 << 90);
+        assert_eq!(n.div_rem(d).unwrap().0, U256::from_u128(max >> 80));
+    }
+}
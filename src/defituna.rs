@@ -5,16 +5,19 @@
 //! Instructions: 31
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::invoke_signed;
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("fUSioN9YKKSa3CUC2YUc4tPkHJ5Y6XW1yz8y6F7qWz9"));
 
@@ -5,16 +5,20 @@
 //! Instructions: 28
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::math::U256;
+use crate::common::{invoke_signed, read_bytes, read_pubkey, read_u128_le, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("dbcij3LWUppWqq96dh6gJWwBifmcGfLSB5D4DuSMaqN"));
 
@@ -111,12 +115,8 @@ pub struct SwapArgs {
     pub params: [u8; 32],
 }
 
-/// Arguments for `swap2`
-#[derive(Clone, Copy)]
-#[repr(C, packed)]
-pub struct Swap2Args {
-    pub params: [u8; 32],
-}
+/// Arguments for `swap2` (`SwapParameters2`), shared with DAMM v2
+pub use crate::meteora_damm_v2::Swap2Args;
 
 /// Arguments for `withdraw_migration_fee`
 #[derive(Clone, Copy)]
@@ -2495,3 +2495,542 @@ pub fn withdraw_migration_fee<'a>(
     invoke_signed::<10>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `PoolConfig`
+pub const POOL_CONFIG_DISCRIMINATOR: [u8; 8] = [26, 108, 14, 123, 116, 230, 129, 43];
+
+/// Account discriminator for `VirtualPool`
+pub const VIRTUAL_POOL_DISCRIMINATOR: [u8; 8] = [213, 224, 5, 209, 98, 69, 119, 92];
+
+/// Denominator of fee numerators (1e9 = 100%)
+pub const FEE_DENOMINATOR: u64 = 1_000_000_000;
+
+/// Number of points in a config's liquidity curve
+pub const MAX_CURVE_POINTS: usize = 20;
+
+/// One curve segment: `liquidity` applies up to `sqrt_price` (Q64.64)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CurvePoint {
+    pub sqrt_price: u128,
+    pub liquidity: u128,
+}
+
+/// Decoded `PoolConfig` account (curve, fees and migration parameters)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolConfig {
+    pub quote_mint: [u8; 32],
+    pub fee_claimer: [u8; 32],
+    /// Starting base fee; the scheduler only lowers it from here
+    pub cliff_fee_numerator: u64,
+    /// Scheduler parameters, meaning depends on `base_fee_mode`
+    /// (periods / frequency / reduction for the linear and exponential modes)
+    pub first_factor: u16,
+    pub second_factor: u64,
+    pub third_factor: u64,
+    pub base_fee_mode: u8,
+    pub dynamic_fee_enabled: bool,
+    /// 0 = fees in quote token, 1 = fees in output token
+    pub collect_fee_mode: u8,
+    /// 0 = Meteora DAMM v1, 1 = DAMM v2
+    pub migration_option: u8,
+    pub token_decimal: u8,
+    pub migration_quote_threshold: u64,
+    pub migration_base_threshold: u64,
+    pub migration_sqrt_price: u128,
+    pub sqrt_start_price: u128,
+    pub curve: [CurvePoint; MAX_CURVE_POINTS],
+}
+
+impl PoolConfig {
+    /// Account size (bytemuck layout, including discriminator)
+    pub const SIZE: usize = 1048;
+
+    /// Offset of the first curve point
+    const CURVE_OFFSET: usize = 408;
+
+    /// Parse a `PoolConfig` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || data[0..8] != POOL_CONFIG_DISCRIMINATOR {
+            return None;
+        }
+        let mut curve = [CurvePoint::default(); MAX_CURVE_POINTS];
+        for (i, point) in curve.iter_mut().enumerate() {
+            let o = Self::CURVE_OFFSET + i * 32;
+            point.sqrt_price = read_u128_le(data, o)?;
+            point.liquidity = read_u128_le(data, o + 16)?;
+        }
+        Some(Self {
+            quote_mint: read_pubkey(data, 8)?,
+            fee_claimer: read_pubkey(data, 40)?,
+            // leftover_receiver at 72, pool_fees.base_fee at 104
+            cliff_fee_numerator: read_u64_le(data, 104)?,
+            second_factor: read_u64_le(data, 112)?,
+            third_factor: read_u64_le(data, 120)?,
+            first_factor: u16::from_le_bytes(read_bytes(data, 128)?),
+            base_fee_mode: data[130],
+            // pool_fees.dynamic_fee at 136, vesting infos and padding to 232
+            dynamic_fee_enabled: data[136] != 0,
+            collect_fee_mode: data[232],
+            migration_option: data[233],
+            token_decimal: data[235],
+            // swap_base_amount at 256
+            migration_quote_threshold: read_u64_le(data, 264)?,
+            migration_base_threshold: read_u64_le(data, 272)?,
+            migration_sqrt_price: read_u128_le(data, 280)?,
+            // locked vesting, supplies, migrated fee params, creation fee to 392
+            sqrt_start_price: read_u128_le(data, 392)?,
+            curve,
+        })
+    }
+}
+
+/// Decoded `VirtualPool` account (fields needed for quoting)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VirtualPool {
+    pub config: [u8; 32],
+    pub creator: [u8; 32],
+    pub base_mint: [u8; 32],
+    pub base_vault: [u8; 32],
+    pub quote_vault: [u8; 32],
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+    pub sqrt_price: u128,
+    pub activation_point: u64,
+    pub is_migrated: bool,
+    pub migration_progress: u8,
+}
+
+impl VirtualPool {
+    /// Account size (bytemuck layout, including discriminator)
+    pub const SIZE: usize = 424;
+
+    /// Parse a `VirtualPool` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || data[0..8] != VIRTUAL_POOL_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            // volatility_tracker at 8..72
+            config: read_pubkey(data, 72)?,
+            creator: read_pubkey(data, 104)?,
+            base_mint: read_pubkey(data, 136)?,
+            base_vault: read_pubkey(data, 168)?,
+            quote_vault: read_pubkey(data, 200)?,
+            base_reserve: read_u64_le(data, 232)?,
+            quote_reserve: read_u64_le(data, 240)?,
+            // protocol / partner fees at 248..280
+            sqrt_price: read_u128_le(data, 280)?,
+            activation_point: read_u64_le(data, 296)?,
+            is_migrated: data[305] != 0,
+            migration_progress: data[308],
+        })
+    }
+
+    /// Whether the curve has raised enough quote to migrate
+    #[inline(always)]
+    pub fn is_curve_complete(&self, config: &PoolConfig) -> bool {
+        self.quote_reserve >= config.migration_quote_threshold
+    }
+}
+
+// ============================================
+// Quote
+// ============================================
+
+/// Expected output for an exact-input swap against the bonding curve
+///
+/// Walks the config's liquidity curve from the pool's current sqrt price
+/// like the program's swap: buys (`quote_to_base`) move the price up
+/// through `config.curve`, sells move it down towards `sqrt_start_price`.
+/// The trading fee uses `cliff_fee_numerator`, the scheduler's starting
+/// (highest) base fee; the dynamic (volatility) fee is not modelled.
+///
+/// Returns 0 for unparsable accounts, migrated or completed curves, and
+/// swaps the curve cannot fill.
+pub fn quote_exact_in(pool_data: &[u8], config_data: &[u8], amount_in: u64, quote_to_base: bool) -> u64 {
+    let (Some(pool), Some(config)) = (VirtualPool::from_bytes(pool_data), PoolConfig::from_bytes(config_data)) else {
+        return 0;
+    };
+    if pool.is_migrated || pool.is_curve_complete(&config) || amount_in == 0 {
+        return 0;
+    }
+
+    let fee = |amount: u64| -> u64 {
+        (amount as u128 * config.cliff_fee_numerator as u128)
+            .div_ceil(FEE_DENOMINATOR as u128)
+            .min(amount as u128) as u64
+    };
+
+    if quote_to_base {
+        // Fees are collected in quote (mode 0) or in the output token (mode 1)
+        let fee_on_input = config.collect_fee_mode == 0;
+        let amount = if fee_on_input { amount_in - fee(amount_in) } else { amount_in };
+        let out = swap_quote_to_base(&config, pool.sqrt_price, amount).unwrap_or(0);
+        if fee_on_input { out } else { out - fee(out) }
+    } else {
+        let out = swap_base_to_quote(&config, pool.sqrt_price, amount_in).unwrap_or(0);
+        out - fee(out)
+    }
+}
+
+/// Base out for `amount_in` quote, walking the curve upwards
+fn swap_quote_to_base(config: &PoolConfig, sqrt_price: u128, amount_in: u64) -> Option<u64> {
+    let mut current = sqrt_price;
+    let mut left = amount_in as u128;
+    let mut out = 0u128;
+    for point in config.curve.iter() {
+        if point.sqrt_price == 0 || point.liquidity == 0 {
+            break;
+        }
+        if point.sqrt_price <= current {
+            continue;
+        }
+        let max_in = delta_quote(current, point.sqrt_price, point.liquidity, true)?;
+        if left < max_in {
+            let next = next_sqrt_price_from_quote_in(current, point.liquidity, left)?;
+            out += delta_base(current, next, point.liquidity, false)?;
+            left = 0;
+            break;
+        }
+        out += delta_base(current, point.sqrt_price, point.liquidity, false)?;
+        current = point.sqrt_price;
+        left -= max_in;
+    }
+    if left != 0 {
+        return None;
+    }
+    u64::try_from(out).ok()
+}
+
+/// Quote out for `amount_in` base, walking the curve downwards
+fn swap_base_to_quote(config: &PoolConfig, sqrt_price: u128, amount_in: u64) -> Option<u64> {
+    let curve = &config.curve;
+    let mut current = sqrt_price;
+    let mut left = amount_in as u128;
+    let mut out = 0u128;
+    for i in (0..MAX_CURVE_POINTS - 1).rev() {
+        let (lower, liquidity) = (curve[i].sqrt_price, curve[i + 1].liquidity);
+        if lower == 0 || curve[i].liquidity == 0 || liquidity == 0 || lower >= current {
+            continue;
+        }
+        let max_in = delta_base(lower, current, liquidity, true)?;
+        if left < max_in {
+            let next = next_sqrt_price_from_base_in(current, liquidity, left)?;
+            out += delta_quote(next, current, liquidity, false)?;
+            left = 0;
+            break;
+        }
+        out += delta_quote(lower, current, liquidity, false)?;
+        current = lower;
+        left -= max_in;
+    }
+    if left != 0 {
+        // First segment, down to the start price
+        let liquidity = curve[0].liquidity;
+        let next = next_sqrt_price_from_base_in(current, liquidity, left)?;
+        if next < config.sqrt_start_price {
+            return None;
+        }
+        out += delta_quote(next, current, liquidity, false)?;
+    }
+    u64::try_from(out).ok()
+}
+
+/// Input needed for `amount_out` of an exact-output swap
+///
+/// The inverse of `quote_exact_in`, with the same fee model: the fee is
+/// grossed up on the input when collected in quote on a buy, and on the
+/// output otherwise, rounding against the trader so the returned input
+/// always fills `amount_out`.
+///
+/// Returns `None` for unparsable accounts, migrated or completed curves,
+/// and outputs beyond what the curve can supply: more base than remains
+/// below the last curve point on a buy, or more quote than selling down to
+/// `sqrt_start_price` releases.
+pub fn quote_exact_out(pool_data: &[u8], config_data: &[u8], amount_out: u64, quote_to_base: bool) -> Option<u64> {
+    let pool = VirtualPool::from_bytes(pool_data)?;
+    let config = PoolConfig::from_bytes(config_data)?;
+    if pool.is_migrated || pool.is_curve_complete(&config) || amount_out == 0 {
+        return None;
+    }
+
+    // Smallest gross amount whose fee-deducted part is at least `net`
+    let gross = |net: u64| -> Option<u64> {
+        let kept = FEE_DENOMINATOR.checked_sub(config.cliff_fee_numerator).filter(|kept| *kept != 0)?;
+        u64::try_from((net as u128 * FEE_DENOMINATOR as u128).div_ceil(kept as u128)).ok()
+    };
+
+    if quote_to_base {
+        if config.collect_fee_mode == 0 {
+            gross(swap_quote_to_base_exact_out(&config, pool.sqrt_price, amount_out)?)
+        } else {
+            swap_quote_to_base_exact_out(&config, pool.sqrt_price, gross(amount_out)?)
+        }
+    } else {
+        swap_base_to_quote_exact_out(&config, pool.sqrt_price, gross(amount_out)?)
+    }
+}
+
+/// Quote in for `amount_out` base, walking the curve upwards
+fn swap_quote_to_base_exact_out(config: &PoolConfig, sqrt_price: u128, amount_out: u64) -> Option<u64> {
+    let mut current = sqrt_price;
+    let mut left = amount_out as u128;
+    let mut amount_in = 0u128;
+    for point in config.curve.iter() {
+        if point.sqrt_price == 0 || point.liquidity == 0 {
+            break;
+        }
+        if point.sqrt_price <= current {
+            continue;
+        }
+        let max_out = delta_base(current, point.sqrt_price, point.liquidity, false)?;
+        if left < max_out {
+            let next = next_sqrt_price_from_base_out(current, point.liquidity, left)?;
+            amount_in += delta_quote(current, next, point.liquidity, true)?;
+            left = 0;
+            break;
+        }
+        amount_in += delta_quote(current, point.sqrt_price, point.liquidity, true)?;
+        current = point.sqrt_price;
+        left -= max_out;
+    }
+    if left != 0 {
+        return None;
+    }
+    u64::try_from(amount_in).ok()
+}
+
+/// Base in for `amount_out` quote, walking the curve downwards
+fn swap_base_to_quote_exact_out(config: &PoolConfig, sqrt_price: u128, amount_out: u64) -> Option<u64> {
+    let curve = &config.curve;
+    let mut current = sqrt_price;
+    let mut left = amount_out as u128;
+    let mut amount_in = 0u128;
+    for i in (0..MAX_CURVE_POINTS - 1).rev() {
+        let (lower, liquidity) = (curve[i].sqrt_price, curve[i + 1].liquidity);
+        if lower == 0 || curve[i].liquidity == 0 || liquidity == 0 || lower >= current {
+            continue;
+        }
+        let max_out = delta_quote(lower, current, liquidity, false)?;
+        if left < max_out {
+            let next = next_sqrt_price_from_quote_out(current, liquidity, left)?;
+            amount_in += delta_base(next, current, liquidity, true)?;
+            left = 0;
+            break;
+        }
+        amount_in += delta_base(lower, current, liquidity, true)?;
+        current = lower;
+        left -= max_out;
+    }
+    if left != 0 {
+        // First segment, down to the start price
+        let liquidity = curve[0].liquidity;
+        let next = next_sqrt_price_from_quote_out(current, liquidity, left)?;
+        if next < config.sqrt_start_price {
+            return None;
+        }
+        amount_in += delta_base(next, current, liquidity, true)?;
+    }
+    u64::try_from(amount_in).ok()
+}
+
+/// Quote between two sqrt prices: `L * (upper - lower) / 2^128`
+fn delta_quote(lower: u128, upper: u128, liquidity: u128, round_up: bool) -> Option<u128> {
+    let product = U256::full_mul(liquidity, upper.checked_sub(lower)?);
+    if round_up && product.lo != 0 {
+        product.hi.checked_add(1)
+    } else {
+        Some(product.hi)
+    }
+}
+
+/// Base between two sqrt prices: `L * (upper - lower) / (lower * upper)`
+fn delta_base(lower: u128, upper: u128, liquidity: u128, round_up: bool) -> Option<u128> {
+    U256::full_mul(liquidity, upper.checked_sub(lower)?)
+        .div_round(U256::full_mul(lower, upper), round_up)?
+        .to_u128()
+}
+
+/// Sqrt price after adding `amount` quote: `p + amount * 2^128 / L`
+fn next_sqrt_price_from_quote_in(sqrt_price: u128, liquidity: u128, amount: u128) -> Option<u128> {
+    let quotient = U256::from_u128(amount).shl(128).div_round(U256::from_u128(liquidity), false)?;
+    sqrt_price.checked_add(quotient.to_u128()?)
+}
+
+/// Sqrt price after adding `amount` base: `L * p / (L + amount * p)`, rounded up
+fn next_sqrt_price_from_base_in(sqrt_price: u128, liquidity: u128, amount: u128) -> Option<u128> {
+    let denominator = U256::from_u128(liquidity).checked_add(U256::full_mul(amount, sqrt_price))?;
+    U256::full_mul(liquidity, sqrt_price).div_round(denominator, true)?.to_u128()
+}
+
+/// Sqrt price after removing `amount` base: `L * p / (L - amount * p)`, rounded up
+fn next_sqrt_price_from_base_out(sqrt_price: u128, liquidity: u128, amount: u128) -> Option<u128> {
+    let removed = U256::full_mul(amount, sqrt_price);
+    let liquidity_wide = U256::from_u128(liquidity);
+    if removed >= liquidity_wide {
+        return None;
+    }
+    U256::full_mul(liquidity, sqrt_price).div_round(liquidity_wide.wrapping_sub(removed), true)?.to_u128()
+}
+
+/// Sqrt price after removing `amount` quote: `p - amount * 2^128 / L`, rounded down
+fn next_sqrt_price_from_quote_out(sqrt_price: u128, liquidity: u128, amount: u128) -> Option<u128> {
+    let quotient = U256::from_u128(amount).shl(128).div_round(U256::from_u128(liquidity), true)?;
+    sqrt_price.checked_sub(quotient.to_u128()?)
+}
+
+// ============================================
+// Exact Out
+// ============================================
+
+/// `swap2` mode, shared with DAMM v2; build args with `Swap2Args::exact_in` / `exact_out`
+pub use crate::meteora_damm_v2::SwapMode;
+
+/// CPI: swap2 in exact-out mode
+///
+/// Same accounts as an exact-in `swap2`. Size `max_amount_in` from
+/// `quote_exact_out`; an output the curve cannot supply fails on-chain.
+#[inline(always)]
+pub fn swap_exact_out<'a>(
+    accounts: &Swap2Accounts<'a>,
+    amount_out: u64,
+    max_amount_in: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    swap2(accounts, &Swap2Args::exact_out(amount_out, max_amount_in), signers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    const START: u128 = (1 << 64) >> 10;
+    const POINT_0: u128 = (1 << 64) >> 8;
+    const POINT_1: u128 = (1 << 64) >> 6;
+
+    fn config_fixture(collect_fee_mode: u8) -> [u8; PoolConfig::SIZE] {
+        let mut data = [0u8; PoolConfig::SIZE];
+        data[0..8].copy_from_slice(&POOL_CONFIG_DISCRIMINATOR);
+        data[104..112].copy_from_slice(&10_000_000u64.to_le_bytes());
+        data[232] = collect_fee_mode;
+        data[233] = 1;
+        data[235] = 6;
+        data[264..272].copy_from_slice(&85_000_000_000u64.to_le_bytes());
+        data[280..296].copy_from_slice(&POINT_1.to_le_bytes());
+        data[392..408].copy_from_slice(&START.to_le_bytes());
+        data[408..424].copy_from_slice(&POINT_0.to_le_bytes());
+        data[424..440].copy_from_slice(&(1u128 << 100).to_le_bytes());
+        data[440..456].copy_from_slice(&POINT_1.to_le_bytes());
+        data[456..472].copy_from_slice(&(1u128 << 99).to_le_bytes());
+        data
+    }
+
+    fn pool_fixture(sqrt_price: u128, quote_reserve: u64) -> [u8; VirtualPool::SIZE] {
+        let mut data = [0u8; VirtualPool::SIZE];
+        data[0..8].copy_from_slice(&VIRTUAL_POOL_DISCRIMINATOR);
+        data[72..104].copy_from_slice(&[1u8; 32]);
+        data[240..248].copy_from_slice(&quote_reserve.to_le_bytes());
+        data[280..296].copy_from_slice(&sqrt_price.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_pool_config_parse() {
+        let config = PoolConfig::from_bytes(&config_fixture(0)).unwrap();
+        assert_eq!(config.cliff_fee_numerator, 10_000_000);
+        assert_eq!(config.migration_option, 1);
+        assert_eq!(config.token_decimal, 6);
+        assert_eq!(config.migration_quote_threshold, 85_000_000_000);
+        assert_eq!(config.migration_sqrt_price, POINT_1);
+        assert_eq!(config.sqrt_start_price, START);
+        assert_eq!(config.curve[0], CurvePoint { sqrt_price: POINT_0, liquidity: 1 << 100 });
+        assert_eq!(config.curve[1], CurvePoint { sqrt_price: POINT_1, liquidity: 1 << 99 });
+        assert_eq!(config.curve[2], CurvePoint::default());
+
+        let pool = VirtualPool::from_bytes(&pool_fixture(START, 0)).unwrap();
+        assert_eq!(pool.config, [1u8; 32]);
+        assert!(!pool.is_curve_complete(&config));
+        let pool = VirtualPool::from_bytes(&pool_fixture(POINT_1, 85_000_000_000)).unwrap();
+        assert!(pool.is_curve_complete(&config));
+
+        assert!(PoolConfig::from_bytes(&pool_fixture(START, 0)).is_none());
+    }
+
+    #[test]
+    fn test_quote_buy() {
+        let (config, pool) = (config_fixture(0), pool_fixture(START, 0));
+        // Within the first segment, then crossing into the second
+        assert_eq!(quote_exact_in(&pool, &config, 100_000_000, true), 41_939_397_488_075);
+        assert_eq!(quote_exact_in(&pool, &config, 300_000_000, true), 56_437_213_394_526);
+        // Fee taken from the base output instead
+        assert_eq!(quote_exact_in(&pool, &config_fixture(1), 100_000_000, true), 41_688_426_974_099);
+        // More quote than the whole curve can absorb
+        assert_eq!(quote_exact_in(&pool, &config, 1_000_000_000, true), 0);
+    }
+
+    #[test]
+    fn test_quote_sell() {
+        let config = config_fixture(0);
+        let pool = pool_fixture(POINT_0 + (1 << 50), 1_000_000);
+        assert_eq!(quote_exact_in(&pool, &config, 100_000_000_000, false), 1_540_409);
+        // Crosses back into the first segment
+        assert_eq!(quote_exact_in(&pool, &config, 500_000_000_000, false), 7_473_165);
+        // Would push the price below the start price
+        assert_eq!(quote_exact_in(&pool, &config, u64::MAX, false), 0);
+
+        let complete = pool_fixture(POINT_1, 85_000_000_000);
+        assert_eq!(quote_exact_in(&complete, &config, 1_000, false), 0);
+    }
+
+    #[test]
+    fn test_quote_exact_out_round_trip() {
+        let pool = pool_fixture(START, 0);
+        for mode in [0, 1] {
+            let config = config_fixture(mode);
+            for amount_out in [41_939_397_488_075, 56_437_213_394_526] {
+                let amount_in = quote_exact_out(&pool, &config, amount_out, true).unwrap();
+                assert!(quote_exact_in(&pool, &config, amount_in, true) >= amount_out);
+                assert!(quote_exact_in(&pool, &config, amount_in - 1, true) < amount_out);
+            }
+        }
+
+        let config = config_fixture(0);
+        let pool = pool_fixture(POINT_0 + (1 << 50), 1_000_000);
+        for amount_out in [1_540_409, 7_473_165] {
+            let amount_in = quote_exact_out(&pool, &config, amount_out, false).unwrap();
+            assert!(quote_exact_in(&pool, &config, amount_in, false) >= amount_out);
+        }
+    }
+
+    #[test]
+    fn test_quote_exact_out_supply_cap() {
+        let pool = pool_fixture(START, 0);
+        let config = config_fixture(0);
+        let parsed = PoolConfig::from_bytes(&config).unwrap();
+        // All base below the last curve point
+        let supply = (delta_base(START, POINT_0, 1 << 100, false).unwrap()
+            + delta_base(POINT_0, POINT_1, 1 << 99, false).unwrap()) as u64;
+        assert!(quote_exact_out(&pool, &config, supply - 1_000, true).is_some());
+        assert_eq!(quote_exact_out(&pool, &config, supply + 1, true), None);
+        // Fee on the base output leaves less for the trader
+        let fee = (supply as u128 * parsed.cliff_fee_numerator as u128).div_ceil(FEE_DENOMINATOR as u128);
+        let net_supply = supply - fee as u64;
+        assert!(quote_exact_out(&pool, &config_fixture(1), net_supply - 1_000, true).is_some());
+        assert_eq!(quote_exact_out(&pool, &config_fixture(1), net_supply + 1_000, true), None);
+
+        // Selling cannot release more quote than lies above the start price
+        let pool = pool_fixture(POINT_0 + (1 << 50), 1_000_000);
+        let quote_supply = (delta_quote(START, POINT_0, 1 << 100, false).unwrap()
+            + delta_quote(POINT_0, POINT_0 + (1 << 50), 1 << 99, false).unwrap()) as u64;
+        assert!(quote_exact_out(&pool, &config, quote_supply * 98 / 100, false).is_some());
+        assert_eq!(quote_exact_out(&pool, &config, quote_supply, false), None);
+
+        let complete = pool_fixture(POINT_1, 85_000_000_000);
+        assert_eq!(quote_exact_out(&complete, &config, 1_000, true), None);
+        assert_eq!(quote_exact_out(&pool, &config, 0, true), None);
+    }
+}
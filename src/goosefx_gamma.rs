@@ -5,16 +5,19 @@
 //! Instructions: 27
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::invoke_signed;
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("GAMMA7meSFWaBXF25oSUgmGRwaW6sCMFLmBNiMSdbHVT"));
 
@@ -2333,3 +2336,106 @@ pub fn withdraw<'a>(
     invoke_signed::<16>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Exact Out
+// ============================================
+
+impl SwapBaseOutputArgs {
+    /// Buy exactly `amount_out`, paying at most `max_amount_in`
+    ///
+    /// The program takes the two amounts in the reverse order of
+    /// `SwapBaseInputArgs` (`max_amount_in` first); this keeps call sites
+    /// from swapping them.
+    #[inline(always)]
+    pub const fn new(amount_out: u64, max_amount_in: u64) -> Self {
+        Self { max_amount_in, amount_out }
+    }
+}
+
+impl<'a> SwapBaseInputAccounts<'a> {
+    /// The same accounts for `swap_base_output`
+    ///
+    /// Exact-in and exact-out take an identical 13-account set in the same
+    /// order and roles; only the discriminator and arguments differ, so an
+    /// exact-in leg can be flipped to exact-out without re-resolving accounts.
+    #[inline(always)]
+    pub fn to_exact_out(&self) -> SwapBaseOutputAccounts<'a> {
+        SwapBaseOutputAccounts {
+            payer: self.payer,
+            authority: self.authority,
+            amm_config: self.amm_config,
+            pool_state: self.pool_state,
+            input_token_account: self.input_token_account,
+            output_token_account: self.output_token_account,
+            input_vault: self.input_vault,
+            output_vault: self.output_vault,
+            input_token_program: self.input_token_program,
+            output_token_program: self.output_token_program,
+            input_token_mint: self.input_token_mint,
+            output_token_mint: self.output_token_mint,
+            observation_state: self.observation_state,
+        }
+    }
+}
+
+/// CPI: swap_base_output for exactly `amount_out`
+///
+/// Gamma selects exact-out by instruction (`SWAP_BASE_OUTPUT`) rather than
+/// a mode byte. `input_*` / `output_*` still name the sold and bought sides.
+#[inline(always)]
+pub fn swap_exact_out<'a>(
+    accounts: &SwapBaseOutputAccounts<'a>,
+    amount_out: u64,
+    max_amount_in: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    swap_base_output(accounts, &SwapBaseOutputArgs::new(amount_out, max_amount_in), signers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{args_bytes, last_cpi, mock_account_views};
+    use pinocchio::account::RuntimeAccount;
+
+    #[test]
+    fn test_swap_base_output_serialization() {
+        assert_eq!(SWAP_BASE_OUTPUT, crate::common::hash::anchor_discriminator("global", "swap_base_output"));
+        let args = SwapBaseOutputArgs::new(1_000, 1_100);
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(&bytes[0..8], &1_100u64.to_le_bytes());
+        assert_eq!(&bytes[8..16], &1_000u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_exact_out_accounts_match_exact_in() {
+        let mut raw: [RuntimeAccount; 13] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let accounts = SwapBaseInputAccounts {
+            payer: &v[0],
+            authority: &v[1],
+            amm_config: &v[2],
+            pool_state: &v[3],
+            input_token_account: &v[4],
+            output_token_account: &v[5],
+            input_vault: &v[6],
+            output_vault: &v[7],
+            input_token_program: &v[8],
+            output_token_program: &v[9],
+            input_token_mint: &v[10],
+            output_token_mint: &v[11],
+            observation_state: &v[12],
+        };
+        let exact_out = accounts.to_exact_out();
+        for (a, b) in accounts.to_instruction_accounts().iter().zip(exact_out.to_instruction_accounts().iter()) {
+            assert_eq!((a.address, a.is_writable, a.is_signer), (b.address, b.is_writable, b.is_signer));
+        }
+        swap_exact_out(&exact_out, 1_000, 1_100, &[]).unwrap();
+        let (program, data) = last_cpi();
+        assert_eq!(program, ID);
+        assert_eq!(&data[..8], &SWAP_BASE_OUTPUT);
+        assert_eq!(&data[8..], args_bytes(&SwapBaseOutputArgs::new(1_000, 1_100)));
+    }
+}
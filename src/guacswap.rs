@@ -5,16 +5,19 @@
 //! Instructions: 20
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::invoke_signed;
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("Gswppe6ERWKpUTXvRPfXdzHhiCyJvLadVvXGfdpBqcE1"));
 
@@ -49,12 +52,12 @@ pub const FORCE_WITHDRAW_REWARDS: [u8; 8] = [214, 35, 143, 68, 97, 248, 101, 241
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct CreatepoolArgs {
-    pub lp_fee: [u8; 32],
-    pub buyback_fee: [u8; 32],
-    pub project_fee: [u8; 32],
-    pub mercanti_fee: [u8; 32],
-    pub initial_token_x: [u8; 32],
-    pub initial_token_y: [u8; 32],
+    pub lp_fee: u128,
+    pub buyback_fee: u128,
+    pub project_fee: u128,
+    pub mercanti_fee: u128,
+    pub initial_token_x: u64,
+    pub initial_token_y: u64,
     pub bump: u8,
 }
 
@@ -62,8 +65,8 @@ pub struct CreatepoolArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct CreateproviderArgs {
-    pub token_x_amount: [u8; 32],
-    pub token_y_amount: [u8; 32],
+    pub token_x_amount: u64,
+    pub token_y_amount: u64,
     pub bump: u8,
 }
 
@@ -78,16 +81,16 @@ pub struct CreatestateArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct AddtokensArgs {
-    pub delta_x: [u8; 32],
-    pub delta_y: [u8; 32],
+    pub delta_x: u64,
+    pub delta_y: u64,
 }
 
 /// Arguments for `swap`
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct SwapArgs {
-    pub delta_in: [u8; 32],
-    pub price_limit: [u8; 32],
+    pub delta_in: u64,
+    pub price_limit: u128,
     pub x_to_y: bool,
 }
 
@@ -95,14 +98,14 @@ pub struct SwapArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct WithdrawsharesArgs {
-    pub shares: [u8; 32],
+    pub shares: u64,
 }
 
 /// Arguments for `createFarm`
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct CreatefarmArgs {
-    pub supply: [u8; 32],
+    pub supply: u64,
     pub duration: u64,
     pub bump: u8,
 }
@@ -111,8 +114,8 @@ pub struct CreatefarmArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct CreatedualfarmArgs {
-    pub supply_marco: [u8; 32],
-    pub supply_project_first: [u8; 32],
+    pub supply_marco: u64,
+    pub supply_project_first: u64,
     pub duration: u64,
     pub bump: u8,
 }
@@ -121,9 +124,9 @@ pub struct CreatedualfarmArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct CreatetriplefarmArgs {
-    pub supply_marco: [u8; 32],
-    pub supply_project_first: [u8; 32],
-    pub supply_project_second: [u8; 32],
+    pub supply_marco: u64,
+    pub supply_project_first: u64,
+    pub supply_project_second: u64,
     pub duration: u64,
     pub bump: u8,
 }
@@ -132,9 +135,9 @@ pub struct CreatetriplefarmArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct AddsupplyArgs {
-    pub supply_marco: [u8; 32],
-    pub supply_project_first: [u8; 32],
-    pub supply_project_second: [u8; 32],
+    pub supply_marco: u64,
+    pub supply_project_first: u64,
+    pub supply_project_second: u64,
     pub duration: u64,
 }
 
@@ -142,10 +145,10 @@ pub struct AddsupplyArgs {
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct UpdatefeesArgs {
-    pub new_buyback_fee: [u8; 32],
-    pub new_project_fee: [u8; 32],
-    pub new_provider_fee: [u8; 32],
-    pub new_mercanti_fee: [u8; 32],
+    pub new_buyback_fee: u128,
+    pub new_project_fee: u128,
+    pub new_provider_fee: u128,
+    pub new_mercanti_fee: u128,
 }
 
 
@@ -1886,3 +1889,79 @@ pub fn force_withdraw_rewards<'a>(
     invoke_signed::<13>(&instruction, &account_views, signers)
 }
 
+
+
+// ============================================
+// Liquidity
+// ============================================
+//
+// Guacswap has no LP mint: a provider's share count lives in its
+// `Provider` account (one per owner and pool, created by `create_provider`
+// with the first deposit). Liquidity sits in the pool vaults
+// `pool_x_account` / `pool_y_account`; the `owner_*` / `token_*` marco and
+// project accounts carry farm rewards, settled on every deposit and
+// withdrawal. The program takes no min-out on either path, so check the
+// pool's reserves right before the CPI.
+
+/// Accounts for `deposit` (`addTokens`)
+pub type DepositAccounts<'a> = AddtokensAccounts<'a>;
+
+/// Accounts for `withdraw` (`withdrawShares`)
+pub type WithdrawAccounts<'a> = WithdrawsharesAccounts<'a>;
+
+/// CPI: add `amount_x` / `amount_y` to an existing provider position
+#[inline(always)]
+pub fn deposit<'a>(
+    accounts: &DepositAccounts<'a>,
+    amount_x: u64,
+    amount_y: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    add_tokens(accounts, &AddtokensArgs { delta_x: amount_x, delta_y: amount_y }, signers)
+}
+
+/// CPI: burn `shares` from the provider position for both pool tokens
+#[inline(always)]
+pub fn withdraw<'a>(
+    accounts: &WithdrawAccounts<'a>,
+    shares: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    withdraw_shares(accounts, &WithdrawsharesArgs { shares }, signers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    #[test]
+    fn test_liquidity_discriminators() {
+        assert_eq!(ADD_TOKENS, [28, 218, 30, 209, 175, 155, 153, 240]);
+        assert_eq!(WITHDRAW_SHARES, [176, 104, 154, 105, 250, 80, 68, 244]);
+    }
+
+    #[test]
+    fn test_liquidity_args_serialization() {
+        // `Token` is a Borsh struct around a single u64
+        let deposit = AddtokensArgs { delta_x: 1_000_000, delta_y: u64::MAX };
+        let bytes = args_bytes(&deposit);
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(&bytes[0..8], &1_000_000u64.to_le_bytes());
+        assert_eq!(&bytes[8..16], &u64::MAX.to_le_bytes());
+
+        let withdraw = WithdrawsharesArgs { shares: 42 };
+        assert_eq!(args_bytes(&withdraw), &42u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_swap_args_serialization() {
+        // `FixedPoint` wraps a u128
+        let args = SwapArgs { delta_in: 500, price_limit: 1u128 << 80, x_to_y: true };
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 25);
+        assert_eq!(&bytes[0..8], &500u64.to_le_bytes());
+        assert_eq!(&bytes[8..24], &(1u128 << 80).to_le_bytes());
+        assert_eq!(bytes[24], 1);
+    }
+}
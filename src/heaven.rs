@@ -3,6 +3,19 @@
 //! Program: heaven_dex
 //! Program ID: HEAVENoP2qxoeuF8Dj2oT1GHEnu49U5mJYkdeC8BAX2o
 //! Instructions: 2
+//!
+//! Only `buy` and `sell` are published in the program IDL. The token
+//! launch instruction is not included: its discriminator, account list and
+//! argument layout are not public, and encoding a guessed layout would fail
+//! on-chain. Add it here once an IDL covering it is available.
+//!
+//! The same applies to liquidity: the IDL has no deposit or withdraw
+//! instruction, so there are no LP `deposit` / `withdraw` CPIs here, and
+//! the vault and LP accounts they would take cannot be documented yet.
+//!
+//! Fees: swaps read the fee schedule from `protocol_config` and accrue the
+//! protocol share into it, which is why it is writable in both `buy` and
+//! `sell`. There is no separate fee recipient account.
 
 #![allow(clippy::too_many_arguments)]
 #![allow(unused)]
@@ -11,10 +24,12 @@ use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::invoke_signed;
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("HEAVENoP2qxoeuF8Dj2oT1GHEnu49U5mJYkdeC8BAX2o"));
 
@@ -73,7 +88,7 @@ pub struct BuyAccounts<'a> {
     pub token_a_vault: &'a AccountView,
     /// Pool's token B vault
     pub token_b_vault: &'a AccountView,
-    /// Protocol configuration
+    /// Protocol configuration (fee schedule, accrues protocol fees)
     pub protocol_config: &'a AccountView,
     /// Sysvar instructions
     pub instruction_sysvar_account_info: &'a AccountView,
@@ -136,7 +151,7 @@ pub struct SellAccounts<'a> {
     pub token_a_vault: &'a AccountView,
     /// Pool's token B vault
     pub token_b_vault: &'a AccountView,
-    /// Protocol configuration
+    /// Protocol configuration (fee schedule, accrues protocol fees)
     pub protocol_config: &'a AccountView,
     /// Sysvar instructions
     pub instruction_sysvar_account_info: &'a AccountView,
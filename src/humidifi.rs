@@ -28,10 +28,12 @@
 
 use pinocchio::{
     AccountView, Address, ProgramResult,
-    cpi::{invoke_signed, Signer},
+    cpi::{Signer},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{AccountRole, invoke_signed, read_pubkey, read_u64_le};
+
 // ============================================
 // Constants
 // ============================================
@@ -150,12 +152,7 @@ impl PoolDataLayout {
 /// Parse quote mint from pool data
 #[inline(always)]
 pub fn parse_quote_mint(pool_data: &[u8]) -> Option<[u8; 32]> {
-    if pool_data.len() < PoolDataLayout::QUOTE_MINT_OFFSET + 32 {
-        return None;
-    }
-    
-    let encrypted: [u8; 32] = pool_data[PoolDataLayout::QUOTE_MINT_OFFSET..PoolDataLayout::QUOTE_MINT_OFFSET + 32]
-        .try_into().ok()?;
+    let encrypted = read_pubkey(pool_data, PoolDataLayout::QUOTE_MINT_OFFSET)?;
     
     Some(xor_decode_pubkey(&encrypted))
 }
@@ -163,12 +160,7 @@ pub fn parse_quote_mint(pool_data: &[u8]) -> Option<[u8; 32]> {
 /// Parse base mint from pool data
 #[inline(always)]
 pub fn parse_base_mint(pool_data: &[u8]) -> Option<[u8; 32]> {
-    if pool_data.len() < PoolDataLayout::BASE_MINT_OFFSET + 32 {
-        return None;
-    }
-    
-    let encrypted: [u8; 32] = pool_data[PoolDataLayout::BASE_MINT_OFFSET..PoolDataLayout::BASE_MINT_OFFSET + 32]
-        .try_into().ok()?;
+    let encrypted = read_pubkey(pool_data, PoolDataLayout::BASE_MINT_OFFSET)?;
     
     Some(xor_decode_pubkey(&encrypted))
 }
@@ -176,12 +168,7 @@ pub fn parse_base_mint(pool_data: &[u8]) -> Option<[u8; 32]> {
 /// Parse quote vault from pool data
 #[inline(always)]
 pub fn parse_quote_vault(pool_data: &[u8]) -> Option<[u8; 32]> {
-    if pool_data.len() < PoolDataLayout::QUOTE_VAULT_OFFSET + 32 {
-        return None;
-    }
-    
-    let encrypted: [u8; 32] = pool_data[PoolDataLayout::QUOTE_VAULT_OFFSET..PoolDataLayout::QUOTE_VAULT_OFFSET + 32]
-        .try_into().ok()?;
+    let encrypted = read_pubkey(pool_data, PoolDataLayout::QUOTE_VAULT_OFFSET)?;
     
     Some(xor_decode_pubkey(&encrypted))
 }
@@ -189,12 +176,7 @@ pub fn parse_quote_vault(pool_data: &[u8]) -> Option<[u8; 32]> {
 /// Parse base vault from pool data
 #[inline(always)]
 pub fn parse_base_vault(pool_data: &[u8]) -> Option<[u8; 32]> {
-    if pool_data.len() < PoolDataLayout::BASE_VAULT_OFFSET + 32 {
-        return None;
-    }
-    
-    let encrypted: [u8; 32] = pool_data[PoolDataLayout::BASE_VAULT_OFFSET..PoolDataLayout::BASE_VAULT_OFFSET + 32]
-        .try_into().ok()?;
+    let encrypted = read_pubkey(pool_data, PoolDataLayout::BASE_VAULT_OFFSET)?;
     
     Some(xor_decode_pubkey(&encrypted))
 }
@@ -273,6 +255,23 @@ impl SwapDirection {
     }
 }
 
+/// Direction for spending `input_mint` in the pool
+///
+/// Decodes the pool's mints and picks the direction from which side
+/// `input_mint` is on; feed the result to `to_swap_v1_bool` /
+/// `to_swap_v2_bool` rather than passing a raw bool. `None` if the pool
+/// data is short or `input_mint` is neither of its mints.
+#[inline(always)]
+pub fn direction_for(input_mint: &[u8; 32], pool_data: &[u8]) -> Option<SwapDirection> {
+    if *input_mint == parse_base_mint(pool_data)? {
+        Some(SwapDirection::BaseToQuote)
+    } else if *input_mint == parse_quote_mint(pool_data)? {
+        Some(SwapDirection::QuoteToBase)
+    } else {
+        None
+    }
+}
+
 // ============================================
 // Swap V1 (9 Accounts)
 // ============================================
@@ -307,6 +306,19 @@ pub struct SwapV1Accounts<'a> {
     pub instructions_sysvar: &'a AccountView,
 }
 
+/// Account spec of swap V1, matching `SwapV1Accounts::to_instruction_accounts`
+pub const SWAP_V1_ACCOUNT_SPEC: [AccountRole; SWAP_V1_ACCOUNTS_COUNT] = [
+    AccountRole::readonly_signer("user_wallet"),
+    AccountRole::writable("pool"),
+    AccountRole::writable("pool_account_1"),
+    AccountRole::writable("pool_account_2"),
+    AccountRole::writable("pool_account_3"),
+    AccountRole::writable("pool_account_4"),
+    AccountRole::readonly("clock"),
+    AccountRole::readonly("token_program"),
+    AccountRole::readonly("instructions_sysvar"),
+];
+
 impl<'a> SwapV1Accounts<'a> {
     #[inline(always)]
     pub fn to_instruction_accounts(&self) -> [InstructionAccount<'a>; SWAP_V1_ACCOUNTS_COUNT] {
@@ -385,6 +397,23 @@ pub struct SwapV2Accounts<'a> {
     pub additional_account: &'a AccountView,
 }
 
+/// Account spec of swap V2, matching `SwapV2Accounts::to_instruction_accounts`
+pub const SWAP_V2_ACCOUNT_SPEC: [AccountRole; SWAP_V2_ACCOUNTS_COUNT] = [
+    AccountRole::writable("pool_account_0"),
+    AccountRole::writable("pool_account_1"),
+    AccountRole::writable("pool_account_2"),
+    AccountRole::writable("pool_account_3"),
+    AccountRole::writable("pool_account_4"),
+    AccountRole::writable("pool_account_5"),
+    AccountRole::readonly("clock"),
+    AccountRole::readonly("token_program_1"),
+    AccountRole::readonly("token_program_2"),
+    AccountRole::readonly("instructions_sysvar"),
+    AccountRole::readonly("quote_mint"),
+    AccountRole::readonly("base_mint"),
+    AccountRole::readonly("additional_account"),
+];
+
 impl<'a> SwapV2Accounts<'a> {
     #[inline(always)]
     pub fn to_instruction_accounts(&self) -> [InstructionAccount<'a>; SWAP_V2_ACCOUNTS_COUNT] {
@@ -433,6 +462,11 @@ impl<'a> SwapV2Accounts<'a> {
 /// 
 /// NOTE: The actual instruction data is XOR-obfuscated.
 /// This struct represents the decoded values.
+///
+/// Chunks 2 and 4 still encode zero: which of them carries the input
+/// amount and the minimum output has not been confirmed against a captured
+/// mainnet swap, so no amount fields are exposed. Use `swap_v1_raw` /
+/// `swap_v2_raw` with data copied from a real transaction until then.
 #[derive(Clone, Copy, Debug)]
 pub struct SwapArgs {
     /// Swap ID (identifies the specific pool/pair)
@@ -629,15 +663,70 @@ pub fn is_humidifi_program(program_id: &Address) -> bool {
 /// Parse token account balance
 #[inline(always)]
 pub fn parse_token_account_balance(data: &[u8]) -> Option<u64> {
-    if data.len() < 72 {
-        return None;
-    }
-    Some(u64::from_le_bytes(data[64..72].try_into().ok()?))
+    read_u64_le(data, 64)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::{Dex, Op, account_spec, matches_account_spec, mock_account_views};
+    use pinocchio::account::RuntimeAccount;
+    
+    #[test]
+    fn test_swap_account_specs() {
+        let mut raw: [RuntimeAccount; SWAP_V2_ACCOUNTS_COUNT] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let v1 = SwapV1Accounts {
+            user_wallet: &v[0],
+            pool: &v[1],
+            pool_account_1: &v[2],
+            pool_account_2: &v[3],
+            pool_account_3: &v[4],
+            pool_account_4: &v[5],
+            clock: &v[6],
+            token_program: &v[7],
+            instructions_sysvar: &v[8],
+        };
+        let v2 = SwapV2Accounts {
+            pool_account_0: &v[0],
+            pool_account_1: &v[1],
+            pool_account_2: &v[2],
+            pool_account_3: &v[3],
+            pool_account_4: &v[4],
+            pool_account_5: &v[5],
+            clock: &v[6],
+            token_program_1: &v[7],
+            token_program_2: &v[8],
+            instructions_sysvar: &v[9],
+            quote_mint: &v[10],
+            base_mint: &v[11],
+            additional_account: &v[12],
+        };
+        assert!(matches_account_spec(&v1.to_instruction_accounts(), account_spec(Dex::HumidiFi, Op::Swap)));
+        assert!(matches_account_spec(&v2.to_instruction_accounts(), account_spec(Dex::HumidiFi, Op::SwapV2)));
+        assert!(!matches_account_spec(&v2.to_instruction_accounts(), &SWAP_V1_ACCOUNT_SPEC));
+        assert!(account_spec(Dex::Whirlpool, Op::SwapV2).is_empty());
+    }
+    
+    #[test]
+    fn test_direction_for() {
+        let base = [7u8; 32];
+        let quote = [9u8; 32];
+        let mut pool = [0u8; PoolDataLayout::MIN_SIZE];
+        pool[PoolDataLayout::BASE_MINT_OFFSET..][..32].copy_from_slice(&xor_encode_pubkey(&base));
+        pool[PoolDataLayout::QUOTE_MINT_OFFSET..][..32].copy_from_slice(&xor_encode_pubkey(&quote));
+
+        let sell_base = direction_for(&base, &pool).unwrap();
+        assert_eq!(sell_base, SwapDirection::BaseToQuote);
+        assert!(!sell_base.to_swap_v1_bool() && sell_base.to_swap_v2_bool());
+
+        let sell_quote = direction_for(&quote, &pool).unwrap();
+        assert_eq!(sell_quote, SwapDirection::QuoteToBase);
+        assert!(sell_quote.to_swap_v1_bool() && !sell_quote.to_swap_v2_bool());
+
+        assert_eq!(direction_for(&[1u8; 32], &pool), None);
+        assert_eq!(direction_for(&base, &pool[..400]), None);
+    }
     
     #[test]
     fn test_xor_symmetric() {
@@ -658,12 +747,12 @@ mod tests {
     #[test]
     fn test_swap_direction() {
         // V1: direction is inverted
-        assert_eq!(SwapDirection::QuoteToBase.to_swap_v1_bool(), true);
-        assert_eq!(SwapDirection::BaseToQuote.to_swap_v1_bool(), false);
+        assert!(SwapDirection::QuoteToBase.to_swap_v1_bool());
+        assert!(!SwapDirection::BaseToQuote.to_swap_v1_bool());
         
         // V2: direction matches Jupiter
-        assert_eq!(SwapDirection::QuoteToBase.to_swap_v2_bool(), false);
-        assert_eq!(SwapDirection::BaseToQuote.to_swap_v2_bool(), true);
+        assert!(!SwapDirection::QuoteToBase.to_swap_v2_bool());
+        assert!(SwapDirection::BaseToQuote.to_swap_v2_bool());
     }
     
     #[test]
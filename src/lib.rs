@@ -6,10 +6,18 @@
 #![no_std]
 #![allow(clippy::too_many_arguments)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(test)]
+extern crate std;
+
 pub mod bonkswap;
 pub mod boop_fun;
 pub mod byreal;
 pub mod carrot;
+pub mod clmm_common;
+pub mod common;
 pub mod defituna;
 pub mod dynamic_bonding_curve;
 pub mod goosefx_gamma;
@@ -32,6 +40,7 @@ pub mod raydium_amm;
 pub mod raydium_clmm;
 pub mod raydium_cp;
 pub mod raydium_launchlab;
+pub mod router;
 pub mod saber_decimals;
 pub mod solfi_v2;
 pub mod stabble_clmm;
@@ -5,16 +5,19 @@
 //! Instructions: 17
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_bytes, read_pubkey, read_u128_le, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("FUTARELBfJfQ8RDGhg1wdhddq1odMAJUePHFuBYfUxKq"));
 
@@ -70,13 +73,6 @@ pub struct UpdatedaoArgs {
     pub dao_params: [u8; 32],
 }
 
-/// Arguments for `spotSwap`
-#[derive(Clone, Copy)]
-#[repr(C, packed)]
-pub struct SpotswapArgs {
-    pub params: [u8; 32],
-}
-
 /// Arguments for `conditionalSwap`
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
@@ -1292,22 +1288,27 @@ pub fn update_dao<'a>(
     invoke_signed::<4>(&instruction, &account_views, signers)
 }
 
-/// CPI: spotSwap
+/// CPI: spotSwap against the DAO's underlying base/quote pool
+///
+/// Trades the DAO token itself through `dao`'s spot AMM. Unlike
+/// `conditional_swap` there is no proposal, conditional vault or pass/fail
+/// mint: the user's base and quote accounts are both passed and `swap_type`
+/// picks which one is debited. Works whether or not a proposal is live.
 #[inline(always)]
 pub fn spot_swap<'a>(
-    accounts: &SpotswapAccounts<'a>, args: &SpotswapArgs,
+    accounts: &SpotswapAccounts<'a>, args: &SpotSwapParams,
     signers: &[Signer<'_, '_>],
 ) -> ProgramResult {
     // Build instruction data
-    let mut data = [0u8; 8 + core::mem::size_of::<SpotswapArgs>()];
+    let mut data = [0u8; 8 + core::mem::size_of::<SpotSwapParams>()];
     data[0..8].copy_from_slice(&SPOT_SWAP);
     
         // Copy arguments (unsafe due to packed struct)
         unsafe {
             core::ptr::copy_nonoverlapping(
-                args as *const SpotswapArgs as *const u8,
+                args as *const SpotSwapParams as *const u8,
                 data.as_mut_ptr().add(8),
-                core::mem::size_of::<SpotswapArgs>(),
+                core::mem::size_of::<SpotSwapParams>(),
             );
         }
     
@@ -1584,3 +1585,302 @@ pub fn admin_remove_proposal<'a>(
     invoke_signed::<5>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `Proposal`
+pub const PROPOSAL_DISCRIMINATOR: [u8; 8] = [26, 94, 189, 187, 116, 136, 53, 33];
+
+/// Account discriminator for `Dao`
+pub const DAO_DISCRIMINATOR: [u8; 8] = [163, 9, 47, 31, 52, 85, 197, 49];
+
+/// Lifecycle of a proposal
+///
+/// Conditional markets only trade while `Pending`; `Passed` / `Failed`
+/// decide which side's conditional tokens redeem.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProposalState {
+    /// Collecting stake before launch
+    Draft { amount_staked: u64 },
+    Pending,
+    Passed,
+    Failed,
+    Removed,
+}
+
+/// Decoded `Proposal` account
+///
+/// This program version records `timestamp_enqueued` instead of a slot, and
+/// keeps the pass/fail TWAPs on the DAO's embedded AMM (see
+/// `futarchy_oracles`) rather than on the proposal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Proposal {
+    pub number: u32,
+    pub proposer: [u8; 32],
+    pub timestamp_enqueued: i64,
+    pub state: ProposalState,
+    pub base_vault: [u8; 32],
+    pub quote_vault: [u8; 32],
+    pub dao: [u8; 32],
+    pub question: [u8; 32],
+    pub duration_in_seconds: u32,
+    pub pass_base_mint: [u8; 32],
+    pub pass_quote_mint: [u8; 32],
+    pub fail_base_mint: [u8; 32],
+    pub fail_quote_mint: [u8; 32],
+    pub is_team_sponsored: bool,
+}
+
+impl Proposal {
+    /// Parse a `Proposal` account, validating the discriminator
+    ///
+    /// Borsh-encoded: fields after `state` shift by 8 bytes for `Draft`.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < 53 || data[0..8] != PROPOSAL_DISCRIMINATOR {
+            return None;
+        }
+        // number (8), proposer (12), timestamp_enqueued (44), state (52)
+        let (state, o) = match data[52] {
+            0 => (ProposalState::Draft { amount_staked: read_u64_le(data, 53)? }, 61),
+            1 => (ProposalState::Pending, 53),
+            2 => (ProposalState::Passed, 53),
+            3 => (ProposalState::Failed, 53),
+            4 => (ProposalState::Removed, 53),
+            _ => return None,
+        };
+        // base_vault, quote_vault, dao, pda_bump, question, duration_in_seconds,
+        // squads_proposal, pass/fail base/quote mints, is_team_sponsored
+        Some(Self {
+            number: u32::from_le_bytes(read_bytes(data, 8)?),
+            proposer: read_pubkey(data, 12)?,
+            timestamp_enqueued: i64::from_le_bytes(read_bytes(data, 44)?),
+            state,
+            base_vault: read_pubkey(data, o)?,
+            quote_vault: read_pubkey(data, o + 32)?,
+            dao: read_pubkey(data, o + 64)?,
+            question: read_pubkey(data, o + 97)?,
+            duration_in_seconds: u32::from_le_bytes(read_bytes(data, o + 129)?),
+            pass_base_mint: read_pubkey(data, o + 165)?,
+            pass_quote_mint: read_pubkey(data, o + 197)?,
+            fail_base_mint: read_pubkey(data, o + 229)?,
+            fail_quote_mint: read_pubkey(data, o + 261)?,
+            is_team_sponsored: *data.get(o + 293)? != 0,
+        })
+    }
+
+    /// Whether the outcome is decided
+    #[inline(always)]
+    pub const fn is_finalized(&self) -> bool {
+        matches!(self.state, ProposalState::Passed | ProposalState::Failed)
+    }
+}
+
+/// TWAP oracle of one futarchy AMM market
+///
+/// Prices are quote units per base unit scaled by 1e12.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TwapOracle {
+    pub aggregator: u128,
+    pub last_updated_timestamp: i64,
+    pub created_at_timestamp: i64,
+    pub last_price: u128,
+    pub last_observation: u128,
+    pub start_delay_seconds: u32,
+}
+
+impl TwapOracle {
+    /// Serialized size, followed by the market's reserves in `Pool`
+    const SIZE: usize = 100;
+
+    fn from_bytes(data: &[u8], offset: usize) -> Option<Self> {
+        // max_observation_change_per_update (64), initial_observation (80)
+        Some(Self {
+            aggregator: read_u128_le(data, offset)?,
+            last_updated_timestamp: i64::from_le_bytes(read_bytes(data, offset + 16)?),
+            created_at_timestamp: i64::from_le_bytes(read_bytes(data, offset + 24)?),
+            last_price: read_u128_le(data, offset + 32)?,
+            last_observation: read_u128_le(data, offset + 48)?,
+            start_delay_seconds: u32::from_le_bytes(read_bytes(data, offset + 96)?),
+        })
+    }
+
+    /// Time-weighted average observation since recording started
+    ///
+    /// `None` until the start delay has elapsed and an update landed.
+    #[inline(always)]
+    pub fn twap(&self) -> Option<u128> {
+        let start = self.created_at_timestamp.checked_add(self.start_delay_seconds as i64)?;
+        let elapsed = self.last_updated_timestamp.checked_sub(start)?;
+        if elapsed <= 0 {
+            return None;
+        }
+        Some(self.aggregator / elapsed as u128)
+    }
+}
+
+/// Pass and fail market oracles of a DAO with a live proposal
+///
+/// The DAO's AMM switches from `Spot` to `Futarchy { spot, pass, fail }`
+/// when a proposal launches; `None` while it is spot-only.
+pub fn futarchy_oracles(dao_data: &[u8]) -> Option<(TwapOracle, TwapOracle)> {
+    // Pool: oracle, quote_reserves, base_reserves, two protocol fee balances
+    const POOL_SIZE: usize = TwapOracle::SIZE + 32;
+    if dao_data.len() < 9 || dao_data[0..8] != DAO_DISCRIMINATOR || dao_data[8] != 1 {
+        return None;
+    }
+    let pass = 9 + POOL_SIZE;
+    Some((TwapOracle::from_bytes(dao_data, pass)?, TwapOracle::from_bytes(dao_data, pass + POOL_SIZE)?))
+}
+
+// ============================================
+// Spot Swap
+// ============================================
+
+/// Side of a spot swap (`SwapType`), from the trader's view of the base token
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapType {
+    /// Spend quote, receive the DAO (base) token
+    Buy = 0,
+    /// Spend the DAO (base) token, receive quote
+    Sell = 1,
+}
+
+/// Arguments for `spotSwap` (`SpotSwapParams`)
+///
+/// `input_amount`, `swap_type`, `min_output_amount`: the 17-byte Borsh
+/// layout the program decodes.
+#[derive(Clone, Copy)]
+#[repr(C, packed)]
+pub struct SpotSwapParams {
+    pub input_amount: u64,
+    pub swap_type: u8,
+    pub min_output_amount: u64,
+}
+
+impl SpotSwapParams {
+    /// Spend `quote_in` for at least `min_base_out` of the DAO token
+    #[inline(always)]
+    pub const fn buy(quote_in: u64, min_base_out: u64) -> Self {
+        Self { input_amount: quote_in, swap_type: SwapType::Buy as u8, min_output_amount: min_base_out }
+    }
+
+    /// Spend `base_in` of the DAO token for at least `min_quote_out`
+    #[inline(always)]
+    pub const fn sell(base_in: u64, min_quote_out: u64) -> Self {
+        Self { input_amount: base_in, swap_type: SwapType::Sell as u8, min_output_amount: min_quote_out }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{args_bytes, dump_account_roles, hash::anchor_discriminator, last_cpi, mock_account_views};
+
+    fn proposal_fixture(state: &[u8]) -> [u8; 400] {
+        let mut data = [0u8; 400];
+        data[0..8].copy_from_slice(&PROPOSAL_DISCRIMINATOR);
+        data[8..12].copy_from_slice(&7u32.to_le_bytes());
+        data[12..44].copy_from_slice(&[1; 32]);
+        data[44..52].copy_from_slice(&1_700_000_000i64.to_le_bytes());
+        data[52..52 + state.len()].copy_from_slice(state);
+        let o = 52 + state.len();
+        data[o..o + 32].copy_from_slice(&[2; 32]);
+        data[o + 64..o + 96].copy_from_slice(&[3; 32]);
+        data[o + 129..o + 133].copy_from_slice(&259_200u32.to_le_bytes());
+        data[o + 165..o + 197].copy_from_slice(&[4; 32]);
+        data[o + 261..o + 293].copy_from_slice(&[5; 32]);
+        data[o + 293] = 1;
+        data
+    }
+
+    #[test]
+    fn test_pending_proposal() {
+        let proposal = Proposal::from_bytes(&proposal_fixture(&[1])).unwrap();
+        assert_eq!(proposal.number, 7);
+        assert_eq!(proposal.timestamp_enqueued, 1_700_000_000);
+        assert_eq!(proposal.state, ProposalState::Pending);
+        assert!(!proposal.is_finalized());
+        assert_eq!(proposal.base_vault, [2; 32]);
+        assert_eq!(proposal.dao, [3; 32]);
+        assert_eq!(proposal.duration_in_seconds, 259_200);
+        assert_eq!(proposal.pass_base_mint, [4; 32]);
+        assert_eq!(proposal.fail_quote_mint, [5; 32]);
+        assert!(proposal.is_team_sponsored);
+    }
+
+    #[test]
+    fn test_finalized_and_draft_proposals() {
+        let passed = Proposal::from_bytes(&proposal_fixture(&[2])).unwrap();
+        assert_eq!(passed.state, ProposalState::Passed);
+        assert!(passed.is_finalized());
+        assert!(Proposal::from_bytes(&proposal_fixture(&[3])).unwrap().is_finalized());
+
+        let mut draft = [0u8; 9];
+        draft[1..9].copy_from_slice(&5_000u64.to_le_bytes());
+        let draft = Proposal::from_bytes(&proposal_fixture(&draft)).unwrap();
+        assert_eq!(draft.state, ProposalState::Draft { amount_staked: 5_000 });
+        assert_eq!(draft.pass_base_mint, [4; 32]);
+
+        assert!(Proposal::from_bytes(&proposal_fixture(&[5])).is_none());
+        let mut bad = proposal_fixture(&[1]);
+        bad[0] ^= 1;
+        assert!(Proposal::from_bytes(&bad).is_none());
+    }
+
+    #[test]
+    fn test_futarchy_oracles() {
+        let mut data = [0u8; 600];
+        data[0..8].copy_from_slice(&DAO_DISCRIMINATOR);
+        data[8] = 1;
+        for (pool, aggregator) in [(1usize, 3_000u128), (2, 1_200)] {
+            let o = 9 + pool * 132;
+            data[o..o + 16].copy_from_slice(&aggregator.to_le_bytes());
+            data[o + 16..o + 24].copy_from_slice(&1_160i64.to_le_bytes());
+            data[o + 24..o + 32].copy_from_slice(&1_000i64.to_le_bytes());
+            data[o + 96..o + 100].copy_from_slice(&60u32.to_le_bytes());
+        }
+        let (pass, fail) = futarchy_oracles(&data).unwrap();
+        // 100 seconds recorded after the 60s start delay
+        assert_eq!(pass.twap(), Some(30));
+        assert_eq!(fail.twap(), Some(12));
+
+        let early = TwapOracle { last_updated_timestamp: 1_060, ..pass };
+        assert_eq!(early.twap(), None);
+
+        data[8] = 0;
+        assert!(futarchy_oracles(&data).is_none());
+    }
+
+    #[test]
+    fn test_spot_swap_serialization() {
+        assert_eq!(SPOT_SWAP, anchor_discriminator("global", "spot_swap"));
+        assert_eq!(CONDITIONAL_SWAP, anchor_discriminator("global", "conditional_swap"));
+
+        let buy = SpotSwapParams::buy(2_000_000, 150_000_000);
+        let bytes = args_bytes(&buy);
+        assert_eq!(bytes.len(), 17);
+        assert_eq!(&bytes[0..8], &2_000_000u64.to_le_bytes());
+        assert_eq!(bytes[8], 0);
+        assert_eq!(&bytes[9..17], &150_000_000u64.to_le_bytes());
+
+        let sell = SpotSwapParams::sell(150_000_000, 1_900_000);
+        assert_eq!(args_bytes(&sell)[8], SwapType::Sell as u8);
+
+        let mut raw: [pinocchio::account::RuntimeAccount; 9] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let accounts = SpotswapAccounts {
+            dao: &v[0], user_base_account: &v[1], user_quote_account: &v[2],
+            amm_base_vault: &v[3], amm_quote_vault: &v[4], user: &v[5],
+            token_program: &v[6], event_authority: &v[7], program: &v[8],
+        };
+        assert_eq!(dump_account_roles(&accounts.to_instruction_accounts()), "w w w w w rs r r r");
+        spot_swap(&accounts, &buy, &[]).unwrap();
+        let (program, data) = last_cpi();
+        assert_eq!(program, ID);
+        assert_eq!(data.len(), 25);
+        assert_eq!(&data[..8], &SPOT_SWAP);
+        assert_eq!(&data[8..], args_bytes(&buy));
+    }
+}
@@ -5,16 +5,19 @@
 //! Instructions: 26
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_pubkey, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB"));
 
@@ -2607,3 +2610,205 @@ pub fn partner_claim_fee<'a>(
     invoke_signed::<8>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Dynamic Vault State
+// ============================================
+//
+// Pool liquidity sits in Meteora dynamic vaults (`a_vault` / `b_vault`),
+// which lend part of it out through strategies. A vault LP token is worth
+// the vault's unlocked amount divided by the LP mint supply; profit from a
+// strategy report unlocks linearly, so the locked part is excluded until
+// it has vested.
+
+/// Dynamic vault program ID
+pub const VAULT_PROGRAM_ID: Address = Address::new_from_array(five8_const::decode_32_const("24Uqj9JCLxUeoC3hGfh5W3s9FM9uCHDS2SG3LYwBpyTi"));
+
+/// Account discriminator for `Vault`
+pub const VAULT_DISCRIMINATOR: [u8; 8] = [211, 8, 232, 43, 2, 152, 117, 119];
+
+/// Account discriminator for `Strategy`
+pub const STRATEGY_DISCRIMINATOR: [u8; 8] = [174, 110, 39, 119, 82, 106, 169, 102];
+
+/// Strategy slots per vault
+pub const MAX_STRATEGY: usize = 30;
+
+/// Denominator of `locked_profit_degradation` (1e12 = fully unlocked per second)
+pub const LOCKED_PROFIT_DEGRADATION_DENOMINATOR: u128 = 1_000_000_000_000;
+
+/// Decoded `Vault` account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Vault {
+    pub enabled: bool,
+    /// Tokens held by the vault, idle plus lent out through strategies
+    pub total_amount: u64,
+    pub token_vault: [u8; 32],
+    pub token_mint: [u8; 32],
+    pub lp_mint: [u8; 32],
+    /// Strategy accounts; unused slots are all zeros
+    pub strategies: [[u8; 32]; MAX_STRATEGY],
+    pub last_updated_locked_profit: u64,
+    /// Unix timestamp of the last strategy report
+    pub last_report: u64,
+    pub locked_profit_degradation: u64,
+}
+
+impl Vault {
+    /// Account size up to the end of `locked_profit_tracker`
+    pub const MIN_SIZE: usize = 1227;
+
+    /// Parse a `Vault` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        // discriminator, enabled, bumps (2), total_amount, token_vault, fee_vault, token_mint, lp_mint,
+        // strategies, base, admin, operator, locked_profit_tracker
+        if data.len() < Self::MIN_SIZE || data[0..8] != VAULT_DISCRIMINATOR {
+            return None;
+        }
+        let mut strategies = [[0u8; 32]; MAX_STRATEGY];
+        for (i, strategy) in strategies.iter_mut().enumerate() {
+            *strategy = read_pubkey(data, 147 + i * 32)?;
+        }
+        Some(Self {
+            enabled: data[8] != 0,
+            total_amount: read_u64_le(data, 11)?,
+            token_vault: read_pubkey(data, 19)?,
+            token_mint: read_pubkey(data, 83)?,
+            lp_mint: read_pubkey(data, 115)?,
+            strategies,
+            last_updated_locked_profit: read_u64_le(data, 1203)?,
+            last_report: read_u64_le(data, 1211)?,
+            locked_profit_degradation: read_u64_le(data, 1219)?,
+        })
+    }
+
+    /// Strategy accounts in use
+    pub fn active_strategies(&self) -> impl Iterator<Item = &[u8; 32]> {
+        self.strategies.iter().filter(|s| **s != [0; 32])
+    }
+
+    /// Profit from the last report that has not unlocked by `now`
+    #[inline(always)]
+    pub fn locked_profit(&self, now: u64) -> u64 {
+        let elapsed = now.saturating_sub(self.last_report) as u128;
+        let ratio = elapsed * self.locked_profit_degradation as u128;
+        if ratio >= LOCKED_PROFIT_DEGRADATION_DENOMINATOR {
+            return 0;
+        }
+        let remaining = LOCKED_PROFIT_DEGRADATION_DENOMINATOR - ratio;
+        (self.last_updated_locked_profit as u128 * remaining / LOCKED_PROFIT_DEGRADATION_DENOMINATOR) as u64
+    }
+
+    /// Tokens backing the LP supply at `now`
+    #[inline(always)]
+    pub fn unlocked_amount(&self, now: u64) -> u64 {
+        self.total_amount.saturating_sub(self.locked_profit(now))
+    }
+
+    /// Tokens per vault LP token in Q64.64, given the LP mint `supply`
+    ///
+    /// `None` when `lp_supply` is zero (empty vault).
+    #[inline(always)]
+    pub fn virtual_price_x64(&self, now: u64, lp_supply: u64) -> Option<u128> {
+        if lp_supply == 0 {
+            return None;
+        }
+        crate::common::math::mul_div(self.unlocked_amount(now) as u128, 1 << 64, lp_supply as u128, false)
+    }
+}
+
+/// Decoded `Strategy` account: one lending allocation of a vault
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Strategy {
+    pub reserve: [u8; 32],
+    pub collateral_vault: [u8; 32],
+    pub strategy_type: u8,
+    /// Vault tokens currently allocated to this strategy
+    pub current_liquidity: u64,
+    pub vault: [u8; 32],
+    pub is_disable: bool,
+}
+
+impl Strategy {
+    /// Account size up to and including `is_disable`
+    pub const MIN_SIZE: usize = 124;
+
+    /// Parse a `Strategy` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        // discriminator, reserve, collateral_vault, strategy_type, current_liquidity, bumps (10), vault, is_disable
+        if data.len() < Self::MIN_SIZE || data[0..8] != STRATEGY_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            reserve: read_pubkey(data, 8)?,
+            collateral_vault: read_pubkey(data, 40)?,
+            strategy_type: data[72],
+            current_liquidity: read_u64_le(data, 73)?,
+            vault: read_pubkey(data, 91)?,
+            is_disable: data[123] != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::hash::anchor_discriminator;
+
+    fn vault_fixture() -> [u8; Vault::MIN_SIZE] {
+        let mut data = [0u8; Vault::MIN_SIZE];
+        data[0..8].copy_from_slice(&VAULT_DISCRIMINATOR);
+        data[8] = 1;
+        data[11..19].copy_from_slice(&1_100_000_000u64.to_le_bytes());
+        data[115..147].copy_from_slice(&[5u8; 32]);
+        // strategies in slots 0 and 2
+        data[147..179].copy_from_slice(&[6u8; 32]);
+        data[211..243].copy_from_slice(&[7u8; 32]);
+        // 100 tokens of profit reported at t = 1_000, unlocking over 1_000s
+        data[1203..1211].copy_from_slice(&100_000_000u64.to_le_bytes());
+        data[1211..1219].copy_from_slice(&1_000u64.to_le_bytes());
+        data[1219..1227].copy_from_slice(&1_000_000_000u64.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_vault_discriminators() {
+        assert_eq!(VAULT_DISCRIMINATOR, anchor_discriminator("account", "Vault"));
+        assert_eq!(STRATEGY_DISCRIMINATOR, anchor_discriminator("account", "Strategy"));
+    }
+
+    #[test]
+    fn test_parse_vault_and_virtual_price() {
+        let vault = Vault::from_bytes(&vault_fixture()).unwrap();
+        assert!(vault.enabled);
+        assert_eq!(vault.lp_mint, [5; 32]);
+        let mut strategies = vault.active_strategies();
+        assert_eq!((strategies.next(), strategies.next(), strategies.next()), (Some(&[6; 32]), Some(&[7; 32]), None));
+
+        // Right after the report all profit is locked: 1_000 tokens over 1_000 LP
+        assert_eq!(vault.unlocked_amount(1_000), 1_000_000_000);
+        assert_eq!(vault.virtual_price_x64(1_000, 1_000_000_000), Some(1 << 64));
+        // Halfway through: 50 tokens unlocked
+        assert_eq!(vault.unlocked_amount(1_500), 1_050_000_000);
+        assert_eq!(vault.virtual_price_x64(1_500, 1_000_000_000), Some((1 << 64) + (1 << 64) / 20));
+        // Fully unlocked
+        assert_eq!(vault.unlocked_amount(5_000), 1_100_000_000);
+        assert_eq!(vault.virtual_price_x64(5_000, 0), None);
+
+        let mut data = vault_fixture();
+        data[0] ^= 1;
+        assert!(Vault::from_bytes(&data).is_none());
+    }
+
+    #[test]
+    fn test_parse_strategy() {
+        let mut data = [0u8; Strategy::MIN_SIZE];
+        data[0..8].copy_from_slice(&STRATEGY_DISCRIMINATOR);
+        data[72] = 3;
+        data[73..81].copy_from_slice(&400_000_000u64.to_le_bytes());
+        data[91..123].copy_from_slice(&[8u8; 32]);
+        let strategy = Strategy::from_bytes(&data).unwrap();
+        assert_eq!((strategy.strategy_type, strategy.current_liquidity), (3, 400_000_000));
+        assert_eq!(strategy.vault, [8; 32]);
+        assert!(!strategy.is_disable);
+        assert!(Strategy::from_bytes(&data[..100]).is_none());
+    }
+}
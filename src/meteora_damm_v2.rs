@@ -5,16 +5,19 @@
 //! Instructions: 35
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::invoke_signed;
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG"));
 
@@ -217,11 +220,16 @@ pub struct SwapArgs {
     pub params: [u8; 32],
 }
 
-/// Arguments for `swap2`
+/// Arguments for `swap2` (`SwapParameters2`)
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct Swap2Args {
-    pub params: [u8; 32],
+    /// `amount_in` for exact-in and partial fill, `amount_out` for exact-out
+    pub amount_0: u64,
+    /// `minimum_amount_out` for exact-in and partial fill, `maximum_amount_in` for exact-out
+    pub amount_1: u64,
+    /// `SwapMode` as u8
+    pub swap_mode: u8,
 }
 
 /// Arguments for `update_pool_fees`
@@ -3168,3 +3176,69 @@ pub fn zap_protocol_fee<'a>(
     invoke_signed::<9>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Exact Out
+// ============================================
+
+/// `swap2` mode (`SwapMode`), also used by Dynamic Bonding Curve
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapMode {
+    ExactIn = 0,
+    /// Exact-in that fills what the pool allows: the current price range
+    /// here, the curve before migration in Dynamic Bonding Curve
+    PartialFill = 1,
+    ExactOut = 2,
+}
+
+impl Swap2Args {
+    /// Sell exactly `amount_in`, receiving at least `minimum_amount_out`
+    #[inline(always)]
+    pub const fn exact_in(amount_in: u64, minimum_amount_out: u64) -> Self {
+        Self { amount_0: amount_in, amount_1: minimum_amount_out, swap_mode: SwapMode::ExactIn as u8 }
+    }
+
+    /// Buy exactly `amount_out`, paying at most `maximum_amount_in`
+    #[inline(always)]
+    pub const fn exact_out(amount_out: u64, maximum_amount_in: u64) -> Self {
+        Self { amount_0: amount_out, amount_1: maximum_amount_in, swap_mode: SwapMode::ExactOut as u8 }
+    }
+}
+
+/// CPI: swap2 in exact-out mode
+///
+/// Takes the same 14 accounts as an exact-in `swap2` (and `swap`); only the
+/// mode byte and the meaning of the two amounts change.
+#[inline(always)]
+pub fn swap_exact_out<'a>(
+    accounts: &Swap2Accounts<'a>,
+    amount_out: u64,
+    max_amount_in: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    swap2(accounts, &Swap2Args::exact_out(amount_out, max_amount_in), signers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    #[test]
+    fn test_swap2_args_mode_encoding() {
+        let args = Swap2Args::exact_out(1_000, 2_000);
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 17);
+        assert_eq!(&bytes[0..8], &1_000u64.to_le_bytes());
+        assert_eq!(&bytes[8..16], &2_000u64.to_le_bytes());
+        assert_eq!(bytes[16], 2);
+
+        let args = Swap2Args::exact_in(1_000, 900);
+        let bytes = args_bytes(&args);
+        assert_eq!(&bytes[8..16], &900u64.to_le_bytes());
+        assert_eq!(bytes[16], 0);
+        assert_eq!(SwapMode::PartialFill as u8, 1);
+        assert_eq!(SWAP2, crate::common::hash::anchor_discriminator("global", "swap2"));
+    }
+}
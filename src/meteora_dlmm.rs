@@ -5,16 +5,20 @@
 //! Instructions: 74
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{QuoteResult, invoke_signed, read_bytes, read_pubkey};
+use crate::common::math::{ONE_X64, mul_div};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo"));
 
@@ -6524,3 +6528,316 @@ pub fn zap_protocol_fee<'a>(
     invoke_signed::<8>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `LbPair`
+pub const LB_PAIR_DISCRIMINATOR: [u8; 8] = [33, 11, 49, 98, 181, 101, 177, 13];
+
+/// Fee parameters set by the protocol (`StaticParameters`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticParameters {
+    pub base_factor: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub variable_fee_control: u32,
+    pub max_volatility_accumulator: u32,
+    pub min_bin_id: i32,
+    pub max_bin_id: i32,
+    pub protocol_share: u16,
+    pub base_fee_power_factor: u8,
+}
+
+/// Fee parameters that track market volatility (`VariableParameters`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VariableParameters {
+    pub volatility_accumulator: u32,
+    pub volatility_reference: u32,
+    pub index_reference: i32,
+    pub last_update_timestamp: i64,
+}
+
+/// Decoded `LbPair` account (fields needed for quoting)
+///
+/// `reserve_x` / `reserve_y` are the vault token accounts; read their
+/// balances with `common::parse_token_account_balance`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LbPair {
+    pub parameters: StaticParameters,
+    pub v_parameters: VariableParameters,
+    pub active_id: i32,
+    pub bin_step: u16,
+    pub status: u8,
+    pub token_x_mint: [u8; 32],
+    pub token_y_mint: [u8; 32],
+    pub reserve_x: [u8; 32],
+    pub reserve_y: [u8; 32],
+    pub oracle: [u8; 32],
+}
+
+impl LbPair {
+    /// Account size (bytemuck layout, including discriminator)
+    pub const SIZE: usize = 904;
+
+    /// Parse an `LbPair` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || data[0..8] != LB_PAIR_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            parameters: StaticParameters {
+                base_factor: u16::from_le_bytes(read_bytes(data, 8)?),
+                filter_period: u16::from_le_bytes(read_bytes(data, 10)?),
+                decay_period: u16::from_le_bytes(read_bytes(data, 12)?),
+                reduction_factor: u16::from_le_bytes(read_bytes(data, 14)?),
+                variable_fee_control: u32::from_le_bytes(read_bytes(data, 16)?),
+                max_volatility_accumulator: u32::from_le_bytes(read_bytes(data, 20)?),
+                min_bin_id: i32::from_le_bytes(read_bytes(data, 24)?),
+                max_bin_id: i32::from_le_bytes(read_bytes(data, 28)?),
+                protocol_share: u16::from_le_bytes(read_bytes(data, 32)?),
+                base_fee_power_factor: data[34],
+            },
+            v_parameters: VariableParameters {
+                volatility_accumulator: u32::from_le_bytes(read_bytes(data, 40)?),
+                volatility_reference: u32::from_le_bytes(read_bytes(data, 44)?),
+                index_reference: i32::from_le_bytes(read_bytes(data, 48)?),
+                last_update_timestamp: i64::from_le_bytes(read_bytes(data, 56)?),
+            },
+            // bump_seed, bin_step_seed, pair_type at 72..76
+            active_id: i32::from_le_bytes(read_bytes(data, 76)?),
+            bin_step: u16::from_le_bytes(read_bytes(data, 80)?),
+            status: data[82],
+            token_x_mint: read_pubkey(data, 88)?,
+            token_y_mint: read_pubkey(data, 120)?,
+            reserve_x: read_pubkey(data, 152)?,
+            reserve_y: read_pubkey(data, 184)?,
+            // protocol_fee, padding and two RewardInfo at 216..552
+            oracle: read_pubkey(data, 552)?,
+        })
+    }
+}
+
+/// `swap_for_y` for a swap selling `input_mint` in this pair
+///
+/// `true` when the input is `token_x_mint` (X in, Y out), `false` when it
+/// is `token_y_mint`, `None` if the pair does not hold the mint. Also picks
+/// which of `reserve_x` / `reserve_y` is the swap's input reserve.
+#[inline(always)]
+pub fn swap_for_y_for(input_mint: &[u8; 32], lb_pair: &LbPair) -> Option<bool> {
+    if input_mint == &lb_pair.token_x_mint {
+        Some(true)
+    } else if input_mint == &lb_pair.token_y_mint {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+// ============================================
+// Quote
+// ============================================
+
+/// Denominator of DLMM fee rates
+pub const FEE_PRECISION: u64 = 1_000_000_000;
+
+/// Cap on the total fee rate (10%)
+pub const MAX_FEE_RATE: u64 = 100_000_000;
+
+/// Price of bin `bin_id` in Q64.64, Y per X: `(1 + bin_step / 10_000) ^ bin_id`
+///
+/// Square-and-multiply with floor rounding at each step, so the last few
+/// units can differ from the program's own `pow`. `None` on overflow.
+pub fn price_from_id(bin_id: i32, bin_step: u16) -> Option<u128> {
+    let base = ONE_X64 + mul_div(bin_step as u128, ONE_X64, 10_000, false)?;
+    let mut exp = bin_id.unsigned_abs();
+    let (mut result, mut square) = (ONE_X64, base);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_div(result, square, ONE_X64, false)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            square = mul_div(square, square, ONE_X64, false)?;
+        }
+    }
+    if bin_id < 0 {
+        mul_div(ONE_X64, ONE_X64, result, false)
+    } else {
+        Some(result)
+    }
+}
+
+impl LbPair {
+    /// Base plus variable fee rate over `FEE_PRECISION`, capped at `MAX_FEE_RATE`
+    ///
+    /// Uses the stored `volatility_accumulator`; the program recomputes it
+    /// from `v_parameters` at the start of a swap, so the charged fee can
+    /// differ from this one after the pair has been idle.
+    pub fn total_fee_rate(&self) -> u64 {
+        let params = &self.parameters;
+        let base = params.base_factor as u128
+            * self.bin_step as u128
+            * 10
+            * 10u128.pow(params.base_fee_power_factor as u32);
+        let volatility = self.v_parameters.volatility_accumulator as u128 * self.bin_step as u128;
+        let variable = (volatility * volatility * params.variable_fee_control as u128).div_ceil(100_000_000_000);
+        (base + variable).min(MAX_FEE_RATE as u128) as u64
+    }
+}
+
+/// Quote selling `amount_in` of `input_mint` within the active bin
+///
+/// `bin_amounts` are the active bin's `(amount_x, amount_y)` from its bin
+/// array. Direction comes from `swap_for_y_for`; the fee is taken from the
+/// input like the program does, and `fee_paid` is reported in output
+/// units. A bin trades at a constant price, so `price_impact_bps` is 0.
+/// Returns `None` for a foreign mint or when the output would exceed the
+/// bin's liquidity (the swap would cross into the next bin).
+pub fn quote_active_bin(
+    lb_pair: &LbPair,
+    input_mint: &[u8; 32],
+    amount_in: u64,
+    bin_amounts: (u64, u64),
+    slippage_bps: u64,
+) -> Option<QuoteResult> {
+    let swap_for_y = swap_for_y_for(input_mint, lb_pair)?;
+    let price = price_from_id(lb_pair.active_id, lb_pair.bin_step)?;
+    let fee = (amount_in as u128 * lb_pair.total_fee_rate() as u128).div_ceil(FEE_PRECISION as u128) as u64;
+    let out = |amount: u64| {
+        if swap_for_y {
+            mul_div(amount as u128, price, ONE_X64, false)
+        } else {
+            mul_div(amount as u128, ONE_X64, price, false)
+        }
+    };
+    let gross_out = out(amount_in)?;
+    let amount_out = out(amount_in.checked_sub(fee)?)?;
+    let available = if swap_for_y { bin_amounts.1 } else { bin_amounts.0 };
+    if amount_out > available as u128 {
+        return None;
+    }
+    let amount_out = amount_out as u64;
+    Some(QuoteResult::new(amount_out, (gross_out as u64).saturating_sub(amount_out), 0, slippage_bps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    #[test]
+    fn test_lb_pair_parse() {
+        let mut data = [0u8; LbPair::SIZE];
+        data[0..8].copy_from_slice(&LB_PAIR_DISCRIMINATOR);
+        data[8..10].copy_from_slice(&10_000u16.to_le_bytes());
+        data[16..20].copy_from_slice(&7_500u32.to_le_bytes());
+        data[20..24].copy_from_slice(&150_000u32.to_le_bytes());
+        data[24..28].copy_from_slice(&(-21_950i32).to_le_bytes());
+        data[28..32].copy_from_slice(&21_950i32.to_le_bytes());
+        data[32..34].copy_from_slice(&500u16.to_le_bytes());
+        data[40..44].copy_from_slice(&12_000u32.to_le_bytes());
+        data[48..52].copy_from_slice(&(-1_234i32).to_le_bytes());
+        data[56..64].copy_from_slice(&1_700_000_000i64.to_le_bytes());
+        data[76..80].copy_from_slice(&(-1_230i32).to_le_bytes());
+        data[80..82].copy_from_slice(&20u16.to_le_bytes());
+        data[88..120].copy_from_slice(&[1u8; 32]);
+        data[120..152].copy_from_slice(&[2u8; 32]);
+        data[152..184].copy_from_slice(&[3u8; 32]);
+        data[184..216].copy_from_slice(&[4u8; 32]);
+        data[552..584].copy_from_slice(&[5u8; 32]);
+
+        let pair = LbPair::from_bytes(&data).unwrap();
+        assert_eq!(pair.parameters.base_factor, 10_000);
+        assert_eq!(pair.parameters.variable_fee_control, 7_500);
+        assert_eq!(pair.parameters.max_volatility_accumulator, 150_000);
+        assert_eq!(pair.parameters.min_bin_id, -21_950);
+        assert_eq!(pair.parameters.max_bin_id, 21_950);
+        assert_eq!(pair.parameters.protocol_share, 500);
+        assert_eq!(pair.v_parameters.volatility_accumulator, 12_000);
+        assert_eq!(pair.v_parameters.index_reference, -1_234);
+        assert_eq!(pair.v_parameters.last_update_timestamp, 1_700_000_000);
+        assert_eq!(pair.active_id, -1_230);
+        assert_eq!(pair.bin_step, 20);
+        assert_eq!(pair.token_x_mint, [1u8; 32]);
+        assert_eq!(pair.token_y_mint, [2u8; 32]);
+        assert_eq!(pair.reserve_x, [3u8; 32]);
+        assert_eq!(pair.reserve_y, [4u8; 32]);
+        assert_eq!(pair.oracle, [5u8; 32]);
+
+        data[0] ^= 1;
+        assert!(LbPair::from_bytes(&data).is_none());
+        assert!(LbPair::from_bytes(&data[..LbPair::SIZE - 1]).is_none());
+    }
+
+    fn pair_fixture(active_id: i32, bin_step: u16) -> LbPair {
+        let mut data = [0u8; LbPair::SIZE];
+        data[0..8].copy_from_slice(&LB_PAIR_DISCRIMINATOR);
+        // 0.1% base fee at bin step 10, no variable fee
+        data[8..10].copy_from_slice(&10_000u16.to_le_bytes());
+        data[76..80].copy_from_slice(&active_id.to_le_bytes());
+        data[80..82].copy_from_slice(&bin_step.to_le_bytes());
+        data[88..120].copy_from_slice(&[1u8; 32]);
+        data[120..152].copy_from_slice(&[2u8; 32]);
+        LbPair::from_bytes(&data).unwrap()
+    }
+
+    #[test]
+    fn test_swap_for_y_for() {
+        let pair = pair_fixture(0, 10);
+        assert_eq!(swap_for_y_for(&[1; 32], &pair), Some(true));
+        assert_eq!(swap_for_y_for(&[2; 32], &pair), Some(false));
+        assert_eq!(swap_for_y_for(&[3; 32], &pair), None);
+    }
+
+    #[test]
+    fn test_price_from_id() {
+        assert_eq!(price_from_id(0, 25), Some(ONE_X64));
+        assert_eq!(price_from_id(1, 10_000), Some(2 * ONE_X64));
+        assert_eq!(price_from_id(-1, 10_000), Some(ONE_X64 / 2));
+        // 1.001^693 ~= 1.999013
+        let price = price_from_id(693, 10).unwrap();
+        assert!(price > ONE_X64 * 1_999_013 / 1_000_000 && price < ONE_X64 * 1_999_014 / 1_000_000);
+    }
+
+    #[test]
+    fn test_quote_active_bin_direction() {
+        assert_eq!(pair_fixture(0, 10).total_fee_rate(), 1_000_000);
+        assert_eq!(pair_fixture(0, 10_000).total_fee_rate(), MAX_FEE_RATE);
+
+        // 1 Y per 2 X at bin -1 of a 100% step
+        let pair = pair_fixture(-1, 10_000);
+        let pair = LbPair { parameters: StaticParameters { base_factor: 1, ..pair.parameters }, ..pair };
+        // base fee: 1 * 10_000 * 10 = 100_000 / 1e9 = 0.01%
+        assert_eq!(pair.total_fee_rate(), 100_000);
+
+        // X in: 1_000_000 X, fee 100 X, out (1_000_000 - 100) / 2 Y
+        let quote = quote_active_bin(&pair, &[1; 32], 1_000_000, (0, 10_000_000), 100).unwrap();
+        assert_eq!((quote.amount_out, quote.fee_paid), (499_950, 50));
+        assert_eq!(quote.min_out_after_slippage, 494_950);
+
+        // Y in: 1_000_000 Y, fee 100 Y, out (1_000_000 - 100) * 2 X
+        let quote = quote_active_bin(&pair, &[2; 32], 1_000_000, (10_000_000, 0), 0).unwrap();
+        assert_eq!((quote.amount_out, quote.fee_paid), (1_999_800, 200));
+
+        // Output past the bin's liquidity, and a foreign mint
+        assert!(quote_active_bin(&pair, &[2; 32], 1_000_000, (1_999_799, 0), 0).is_none());
+        assert!(quote_active_bin(&pair, &[3; 32], 1_000_000, (10_000_000, 10_000_000), 0).is_none());
+    }
+
+    #[test]
+    fn test_remove_liquidity_by_range_serialization() {
+        assert_eq!(REMOVE_LIQUIDITY_BY_RANGE, [26, 82, 102, 152, 240, 74, 105, 26]);
+
+        let args = RemoveLiquidityByRangeArgs {
+            from_bin_id: -443_636,
+            to_bin_id: 443_636,
+            bps_to_remove: 10_000,
+        };
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 10);
+        assert_eq!(&bytes[0..4], &(-443_636i32).to_le_bytes());
+        assert_eq!(&bytes[4..8], &443_636i32.to_le_bytes());
+        assert_eq!(&bytes[8..10], &10_000u16.to_le_bytes());
+    }
+}
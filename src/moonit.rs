@@ -5,16 +5,19 @@
 //! Instructions: 6
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_bytes, read_pubkey, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("MoonCVVNZFSYkqNXP6bxHLPL6QQJiMagDL3qcqUQTrG"));
 
@@ -38,19 +41,28 @@ pub struct TokenmintArgs {
     pub mint_params: [u8; 32],
 }
 
-/// Arguments for `buy`
+/// Arguments for `buy` / `sell` (TradeParams)
+///
+/// `collateral_amount` is in the curve's collateral currency base units
+/// (see `CurveAccount::collateral_decimals`), not always lamports.
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
-pub struct BuyArgs {
-    pub data: [u8; 32],
+pub struct TradeArgs {
+    /// Token amount (curve token decimals)
+    pub token_amount: u64,
+    /// Collateral amount (collateral currency decimals)
+    pub collateral_amount: u64,
+    /// Fixed side: 0 = ExactIn, 1 = ExactOut
+    pub fixed_side: u8,
+    /// Slippage tolerance in basis points
+    pub slippage_bps: u64,
 }
 
-/// Arguments for `sell`
-#[derive(Clone, Copy)]
-#[repr(C, packed)]
-pub struct SellArgs {
-    pub data: [u8; 32],
-}
+/// Alias for buy
+pub type BuyArgs = TradeArgs;
+
+/// Alias for sell
+pub type SellArgs = TradeArgs;
 
 /// Arguments for `configInit`
 #[derive(Clone, Copy)]
@@ -546,3 +558,154 @@ pub fn config_update<'a>(
     invoke_signed::<2>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `CurveAccount`
+pub const CURVE_ACCOUNT_DISCRIMINATOR: [u8; 8] = [8, 91, 83, 28, 132, 216, 248, 22];
+
+/// Collateral currency a curve is priced in
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Currency {
+    /// Native SOL (9 decimals), the only variant in the IDL
+    Sol = 0,
+}
+
+impl Currency {
+    /// Parse from the on-chain enum byte
+    #[inline(always)]
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Sol),
+            _ => None,
+        }
+    }
+
+    /// Decimals of the collateral mint
+    #[inline(always)]
+    pub const fn decimals(self) -> u8 {
+        match self {
+            Self::Sol => 9,
+        }
+    }
+}
+
+/// Decoded `CurveAccount`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CurveAccount {
+    pub total_supply: u64,
+    pub curve_amount: u64,
+    pub mint: [u8; 32],
+    pub decimals: u8,
+    pub collateral_currency: Currency,
+    /// 0 = LinearV1, 1 = ConstantProductV1
+    pub curve_type: u8,
+    pub marketcap_threshold: u64,
+    pub marketcap_currency: Currency,
+    pub migration_fee: u64,
+    pub coef_b: u32,
+    pub bump: u8,
+    /// 0 = Raydium, 1 = Meteora
+    pub migration_target: u8,
+}
+
+impl CurveAccount {
+    /// Account size
+    pub const SIZE: usize = 82;
+
+    /// Parse a `CurveAccount`, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || data[0..8] != CURVE_ACCOUNT_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            total_supply: read_u64_le(data, 8)?,
+            curve_amount: read_u64_le(data, 16)?,
+            mint: read_pubkey(data, 24)?,
+            decimals: data[56],
+            collateral_currency: Currency::from_u8(data[57])?,
+            curve_type: data[58],
+            marketcap_threshold: read_u64_le(data, 59)?,
+            marketcap_currency: Currency::from_u8(data[67])?,
+            migration_fee: read_u64_le(data, 68)?,
+            coef_b: u32::from_le_bytes(read_bytes(data, 76)?),
+            bump: data[80],
+            migration_target: data[81],
+        })
+    }
+
+    /// Decimals `TradeArgs::collateral_amount` must be expressed in
+    #[inline(always)]
+    pub const fn collateral_decimals(&self) -> u8 {
+        self.collateral_currency.decimals()
+    }
+
+    /// Rescale a collateral amount quoted with `from_decimals` into this
+    /// curve's collateral decimals (rounding down)
+    pub fn collateral_for_curve(&self, amount: u64, from_decimals: u8) -> Option<u64> {
+        let to_decimals = self.collateral_decimals();
+        if from_decimals >= to_decimals {
+            Some(amount / 10u64.checked_pow((from_decimals - to_decimals) as u32)?)
+        } else {
+            amount.checked_mul(10u64.checked_pow((to_decimals - from_decimals) as u32)?)
+        }
+    }
+}
+
+impl TradeArgs {
+    /// Exact-in buy: spend `collateral_amount` for at least `token_amount`
+    #[inline(always)]
+    pub const fn buy_exact_in(collateral_amount: u64, token_amount: u64, slippage_bps: u64) -> Self {
+        Self { token_amount, collateral_amount, fixed_side: 0, slippage_bps }
+    }
+
+    /// Exact-in sell: sell `token_amount` for at least `collateral_amount`
+    #[inline(always)]
+    pub const fn sell_exact_in(token_amount: u64, collateral_amount: u64, slippage_bps: u64) -> Self {
+        Self { token_amount, collateral_amount, fixed_side: 0, slippage_bps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve_fixture(collateral: u8) -> [u8; CurveAccount::SIZE] {
+        let mut data = [0u8; CurveAccount::SIZE];
+        data[0..8].copy_from_slice(&CURVE_ACCOUNT_DISCRIMINATOR);
+        data[8..16].copy_from_slice(&1_000_000_000_000_000u64.to_le_bytes());
+        data[16..24].copy_from_slice(&800_000_000_000_000u64.to_le_bytes());
+        data[56] = 6;
+        data[57] = collateral;
+        data[59..67].copy_from_slice(&500_000_000_000u64.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_sol_collateral_curve() {
+        let curve = CurveAccount::from_bytes(&curve_fixture(0)).unwrap();
+        assert_eq!(curve.collateral_currency, Currency::Sol);
+        assert_eq!(curve.collateral_decimals(), 9);
+        assert_eq!(curve.decimals, 6);
+        assert_eq!(curve.collateral_for_curve(1_000_000_000, 9), Some(1_000_000_000));
+        // 2.5 SOL quoted with 6 decimals, in lamports
+        assert_eq!(curve.collateral_for_curve(2_500_000, 6), Some(2_500_000_000));
+        assert_eq!(curve.collateral_for_curve(u64::MAX, 6), None);
+
+        // Only `Sol` exists in the IDL
+        assert!(CurveAccount::from_bytes(&curve_fixture(1)).is_none());
+        let mut bad = curve_fixture(0);
+        bad[0] ^= 1;
+        assert!(CurveAccount::from_bytes(&bad).is_none());
+    }
+
+    #[test]
+    fn test_trade_args_layout() {
+        assert_eq!(core::mem::size_of::<TradeArgs>(), 25);
+        let args = TradeArgs::buy_exact_in(1_000_000, 42, 100);
+        assert_eq!({ args.collateral_amount }, 1_000_000);
+        assert_eq!({ args.token_amount }, 42);
+    }
+}
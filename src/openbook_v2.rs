@@ -5,16 +5,19 @@
 //! Instructions: 29
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_bytes};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb"));
 
@@ -81,13 +84,63 @@ pub struct PlaceorderArgs {
     pub args: [u8; 32],
 }
 
+/// Order side (`Side`)
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Bid = 0,
+    Ask = 1,
+}
+
+/// Order type (`PlaceOrderType`)
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaceOrderType {
+    Limit = 0,
+    ImmediateOrCancel = 1,
+    PostOnly = 2,
+    Market = 3,
+    PostOnlySlide = 4,
+}
+
+/// Self-trade handling (`SelfTradeBehavior`)
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelfTradeBehavior {
+    DecrementTake = 0,
+    CancelProvide = 1,
+    AbortTransaction = 2,
+}
+
+/// Order parameters (`PlaceOrderArgs`), Borsh layout (44 bytes)
+#[derive(Clone, Copy)]
+#[repr(C, packed)]
+pub struct PlaceOrderParams {
+    pub side: Side,
+    pub price_lots: i64,
+    pub max_base_lots: i64,
+    pub max_quote_lots_including_fees: i64,
+    pub client_order_id: u64,
+    pub order_type: PlaceOrderType,
+    /// Unix timestamp after which the order expires, 0 for none
+    pub expiry_timestamp: u64,
+    pub self_trade_behavior: SelfTradeBehavior,
+    /// Max book orders to match against
+    pub limit: u8,
+}
+
 /// Arguments for `editOrder`
+///
+/// Cancels the order with `client_order_id` and places `place_order` in one
+/// instruction. If the old order was partly filled since
+/// `expected_cancel_size` (base lots) was read, the new order's
+/// `max_base_lots` is reduced by the filled amount.
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct EditorderArgs {
     pub client_order_id: u64,
     pub expected_cancel_size: i64,
-    pub place_order: [u8; 32],
+    pub place_order: PlaceOrderParams,
 }
 
 /// Arguments for `editOrderPegged`
@@ -1716,7 +1769,7 @@ pub fn place_order<'a>(
     invoke_signed::<12>(&instruction, &account_views, signers)
 }
 
-/// CPI: editOrder
+/// CPI: editOrder (cancel by client order id, then place)
 #[inline(always)]
 pub fn edit_order<'a>(
     accounts: &EditorderAccounts<'a>, args: &EditorderArgs,
@@ -2416,3 +2469,175 @@ pub fn stub_oracle_set<'a>(
     invoke_signed::<2>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// PDA Derivation
+// ============================================
+/// Seed prefix for open-orders accounts
+pub const OPEN_ORDERS_SEED: &[u8] = b"OpenOrders";
+
+/// Seed prefix for the per-owner open-orders indexer
+pub const OPEN_ORDERS_INDEXER_SEED: &[u8] = b"OpenOrdersIndexer";
+
+/// Account discriminator for `OpenOrdersIndexer`
+pub const OPEN_ORDERS_INDEXER_DISCRIMINATOR: [u8; 8] = [195, 83, 128, 213, 204, 91, 19, 150];
+
+/// Seeds for an open-orders account: `["OpenOrders", owner, account_num (u32 LE)]`
+///
+/// The market is not part of the seeds; `account_num` is the owner's
+/// indexer `created_counter + 1` at creation time, so numbering starts at 1.
+#[inline(always)]
+pub fn open_orders_seeds<'a>(owner: &'a Address, account_num: &'a [u8; 4]) -> [&'a [u8]; 3] {
+    [OPEN_ORDERS_SEED, owner.as_ref(), account_num]
+}
+
+/// Seeds for the open-orders indexer: `["OpenOrdersIndexer", owner]`
+#[inline(always)]
+pub fn open_orders_indexer_seeds(owner: &Address) -> [&[u8]; 2] {
+    [OPEN_ORDERS_INDEXER_SEED, owner.as_ref()]
+}
+
+/// Derive the open-orders account for `owner` and `account_num`
+#[cfg(any(target_os = "solana", target_arch = "bpf"))]
+#[inline(always)]
+pub fn find_open_orders(owner: &Address, account_num: u32) -> (Address, u8) {
+    let account_num = account_num.to_le_bytes();
+    Address::find_program_address(&open_orders_seeds(owner, &account_num), &ID)
+}
+
+/// Derive the open-orders indexer for `owner`
+#[cfg(any(target_os = "solana", target_arch = "bpf"))]
+#[inline(always)]
+pub fn find_open_orders_indexer(owner: &Address) -> (Address, u8) {
+    Address::find_program_address(&open_orders_indexer_seeds(owner), &ID)
+}
+
+/// Decoded `OpenOrdersIndexer` header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpenOrdersIndexer {
+    pub bump: u8,
+    pub created_counter: u32,
+    /// Number of open-orders accounts currently listed
+    pub addresses_len: u32,
+}
+
+impl OpenOrdersIndexer {
+    /// Size of the fixed header (discriminator, bump, counter, vec length)
+    pub const HEADER_SIZE: usize = 17;
+
+    /// Parse the indexer header, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::HEADER_SIZE || data[0..8] != OPEN_ORDERS_INDEXER_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            bump: data[8],
+            created_counter: u32::from_le_bytes(read_bytes(data, 9)?),
+            addresses_len: u32::from_le_bytes(read_bytes(data, 13)?),
+        })
+    }
+
+    /// `account_num` the next `create_open_orders_account` will use
+    ///
+    /// `None` once the counter is exhausted.
+    #[inline(always)]
+    pub const fn next_account_num(&self) -> Option<u32> {
+        self.created_counter.checked_add(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    #[test]
+    fn test_open_orders_seeds() {
+        let owner = Address::new_from_array([7u8; 32]);
+        let account_num = 3u32.to_le_bytes();
+        let seeds = open_orders_seeds(&owner, &account_num);
+        assert_eq!(seeds[0], b"OpenOrders");
+        assert_eq!(seeds[1], &[7u8; 32]);
+        assert_eq!(seeds[2], &[3, 0, 0, 0]);
+
+        let seeds = open_orders_indexer_seeds(&owner);
+        assert_eq!(seeds[0], b"OpenOrdersIndexer");
+        assert_eq!(seeds[1], &[7u8; 32]);
+    }
+
+    #[test]
+    fn test_open_orders_addresses() {
+        // Addresses and bumps derived off-chain for this owner with the
+        // standard PDA search, then pinned; not read from mainnet
+        let owner = Address::new_from_array(five8_const::decode_32_const("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"));
+        let open_orders = [
+            (1u32, 253u8, "BZSb2am9ErYBkFXXCj6tx1zSbcaRXRoBFQPP4rb7M9kK"),
+            (2, 247, "Cpsp4DNJZwRtsJSBEYuKVyqnN9xSNmz7TtJ4RxeQURPj"),
+        ];
+        for (account_num, bump, expected) in open_orders {
+            let account_num = account_num.to_le_bytes();
+            let [prefix, owner, account_num] = open_orders_seeds(&owner, &account_num);
+            let address = Address::create_program_address(&[prefix, owner, account_num, &[bump]], &ID).unwrap();
+            assert_eq!(address.to_bytes(), five8_const::decode_32_const(expected));
+        }
+
+        let [prefix, owner] = open_orders_indexer_seeds(&owner);
+        let indexer = Address::create_program_address(&[prefix, owner, &[255]], &ID).unwrap();
+        assert_eq!(indexer.to_bytes(), five8_const::decode_32_const("GWYrbqLoPHk32ZhgfAWuWpXUDNCLyBBX24MABjKovdH3"));
+    }
+
+    #[test]
+    fn test_open_orders_indexer_parse() {
+        let mut data = [0u8; OpenOrdersIndexer::HEADER_SIZE + 32];
+        data[0..8].copy_from_slice(&OPEN_ORDERS_INDEXER_DISCRIMINATOR);
+        data[8] = 254;
+        data[9..13].copy_from_slice(&2u32.to_le_bytes());
+        data[13..17].copy_from_slice(&1u32.to_le_bytes());
+
+        let indexer = OpenOrdersIndexer::from_bytes(&data).unwrap();
+        assert_eq!(indexer.bump, 254);
+        assert_eq!(indexer.created_counter, 2);
+        assert_eq!(indexer.addresses_len, 1);
+        assert_eq!(indexer.next_account_num(), Some(3));
+        let exhausted = OpenOrdersIndexer { created_counter: u32::MAX, ..indexer };
+        assert_eq!(exhausted.next_account_num(), None);
+
+        data[0] ^= 1;
+        assert!(OpenOrdersIndexer::from_bytes(&data).is_none());
+    }
+
+    #[test]
+    fn test_edit_order_args_layout() {
+        assert_eq!(EDIT_ORDER, crate::common::hash::anchor_discriminator("global", "edit_order"));
+
+        let args = EditorderArgs {
+            client_order_id: 42,
+            expected_cancel_size: 5,
+            place_order: PlaceOrderParams {
+                side: Side::Ask,
+                price_lots: 1_000,
+                max_base_lots: 5,
+                max_quote_lots_including_fees: i64::MAX,
+                client_order_id: 43,
+                order_type: PlaceOrderType::PostOnly,
+                expiry_timestamp: 0,
+                self_trade_behavior: SelfTradeBehavior::CancelProvide,
+                limit: 10,
+            },
+        };
+        let bytes = args_bytes(&args);
+        assert_eq!(core::mem::size_of::<PlaceOrderParams>(), 44);
+        assert_eq!(bytes.len(), 60);
+        assert_eq!(&bytes[0..8], &42u64.to_le_bytes());
+        assert_eq!(&bytes[8..16], &5i64.to_le_bytes());
+        assert_eq!(bytes[16], 1);
+        assert_eq!(&bytes[17..25], &1_000i64.to_le_bytes());
+        assert_eq!(&bytes[25..33], &5i64.to_le_bytes());
+        assert_eq!(&bytes[33..41], &i64::MAX.to_le_bytes());
+        assert_eq!(&bytes[41..49], &43u64.to_le_bytes());
+        assert_eq!(bytes[49], 2);
+        assert_eq!(&bytes[50..58], &0u64.to_le_bytes());
+        assert_eq!(bytes[58], 1);
+        assert_eq!(bytes[59], 10);
+    }
+}
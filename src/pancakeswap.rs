@@ -5,16 +5,21 @@
 //! Instructions: 27
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::clmm_common::{self, SwapState, TickArrayLayout, TickArrayView, TickRange};
+use crate::raydium_clmm;
+use crate::common::{OptionBool, invoke_signed, read_bytes, read_pubkey, read_u128_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("HpNfyc2Saw7RKkQd8nEL4khUcuPhQ7WwY1B2qjx8jxFq"));
 
@@ -128,7 +133,7 @@ pub struct IncreaseLiquidityV2Args {
     pub liquidity: u128,
     pub amount_0_max: u64,
     pub amount_1_max: u64,
-    pub base_flag: Option<bool>,
+    pub base_flag: OptionBool,
 }
 
 /// Arguments for `initialize_reward`
@@ -138,18 +143,8 @@ pub struct InitializeRewardArgs {
     pub param: [u8; 32],
 }
 
-/// Arguments for `open_position`
-#[derive(Clone, Copy)]
-#[repr(C, packed)]
-pub struct OpenPositionArgs {
-    pub tick_lower_index: i32,
-    pub tick_upper_index: i32,
-    pub tick_array_lower_start_index: i32,
-    pub tick_array_upper_start_index: i32,
-    pub liquidity: u128,
-    pub amount_0_max: u64,
-    pub amount_1_max: u64,
-}
+/// Arguments for `open_position`; build with `clmm_common::open_position_args`
+pub use crate::clmm_common::OpenPositionArgs;
 
 /// Arguments for `open_position_v2`
 #[derive(Clone, Copy)]
@@ -163,7 +158,7 @@ pub struct OpenPositionV2Args {
     pub amount_0_max: u64,
     pub amount_1_max: u64,
     pub with_metadata: bool,
-    pub base_flag: Option<bool>,
+    pub base_flag: OptionBool,
 }
 
 /// Arguments for `open_position_with_token22_nft`
@@ -178,7 +173,7 @@ pub struct OpenPositionWithToken22NftArgs {
     pub amount_0_max: u64,
     pub amount_1_max: u64,
     pub with_metadata: bool,
-    pub base_flag: Option<bool>,
+    pub base_flag: OptionBool,
 }
 
 /// Arguments for `set_reward_params`
@@ -2420,3 +2415,185 @@ pub fn update_reward_infos<'a>(
     invoke_signed::<1>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `PoolState`
+pub const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+
+/// Account discriminator for `TickArrayState`
+pub const TICK_ARRAY_STATE_DISCRIMINATOR: [u8; 8] = [192, 155, 85, 205, 49, 249, 129, 42];
+
+/// Ticks per `TickArrayState`
+pub const TICK_ARRAY_SIZE: usize = 60;
+
+/// `TickArrayState` layout for `TickArrayView`
+///
+/// Same layout as Raydium CLMM, which amm_v3 is forked from.
+pub const TICK_ARRAY_LAYOUT: TickArrayLayout =
+    TickArrayLayout { discriminator: TICK_ARRAY_STATE_DISCRIMINATOR, ..raydium_clmm::TICK_ARRAY_LAYOUT };
+
+/// `PoolState` has Raydium CLMM's layout and discriminator
+pub use crate::raydium_clmm::{PoolState, zero_for_one_for};
+
+// ============================================
+// Quote
+// ============================================
+
+/// Exact-input output of a swap across the given `TickArrayState` accounts
+///
+/// `amm_config` has Raydium CLMM's `AmmConfig` layout. See
+/// `raydium_clmm::quote_exact_in`.
+pub fn quote_exact_in(
+    pool_state: &[u8],
+    amm_config: &[u8],
+    tick_arrays: &[&[u8]],
+    amount_in: u64,
+    zero_for_one: bool,
+) -> Option<u64> {
+    let pool = PoolState::from_bytes(pool_state)?;
+    let state = SwapState { sqrt_price_x64: pool.sqrt_price_x64, liquidity: pool.liquidity, tick_current: pool.tick_current };
+    let fee_rate = raydium_clmm::trade_fee_rate(amm_config)?;
+    clmm_common::quote_exact_in(state, fee_rate, tick_arrays, &TICK_ARRAY_LAYOUT, pool.tick_spacing, amount_in, zero_for_one)
+}
+
+// ============================================
+// Exact Out
+// ============================================
+
+impl SwapV2Args {
+    /// Sell exactly `amount_in`, receiving at least `minimum_amount_out`
+    ///
+    /// A zero `sqrt_price_limit_x64` lets the swap run to the price bound.
+    #[inline(always)]
+    pub const fn exact_in(amount_in: u64, minimum_amount_out: u64, sqrt_price_limit_x64: u128) -> Self {
+        Self { amount: amount_in, other_amount_threshold: minimum_amount_out, sqrt_price_limit_x64, is_base_input: true }
+    }
+
+    /// Buy exactly `amount_out`, paying at most `maximum_amount_in`
+    ///
+    /// A zero `sqrt_price_limit_x64` lets the swap run to the price bound.
+    #[inline(always)]
+    pub const fn exact_out(amount_out: u64, maximum_amount_in: u64, sqrt_price_limit_x64: u128) -> Self {
+        Self { amount: amount_out, other_amount_threshold: maximum_amount_in, sqrt_price_limit_x64, is_base_input: false }
+    }
+}
+
+/// CPI: swap_v2 in exact-out mode, without a price limit
+///
+/// Same 13 accounts as an exact-in `swap_v2`; `input_*` / `output_*` still
+/// name the sold and bought sides. Use `swap_v2` with
+/// `SwapV2Args::exact_out` to set a price limit.
+#[inline(always)]
+pub fn swap_exact_out<'a>(
+    accounts: &SwapV2Accounts<'a>,
+    amount_out: u64,
+    max_amount_in: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    swap_v2(accounts, &SwapV2Args::exact_out(amount_out, max_amount_in, 0), signers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    #[test]
+    fn test_liquidity_discriminators() {
+        assert_eq!(OPEN_POSITION, [135, 128, 47, 77, 15, 152, 240, 49]);
+        assert_eq!(INCREASE_LIQUIDITY, [46, 156, 243, 118, 13, 205, 251, 178]);
+        assert_eq!(DECREASE_LIQUIDITY, [160, 38, 208, 111, 104, 91, 44, 1]);
+    }
+
+    #[test]
+    fn test_open_position_args_serialization() {
+        let args = OpenPositionArgs {
+            tick_lower_index: -500,
+            tick_upper_index: 500,
+            tick_array_lower_start_index: -3000,
+            tick_array_upper_start_index: 0,
+            liquidity: u128::MAX - 1,
+            amount_0_max: 1_000,
+            amount_1_max: 2_000,
+        };
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 48);
+        assert_eq!(&bytes[0..4], &(-500i32).to_le_bytes());
+        assert_eq!(&bytes[8..12], &(-3000i32).to_le_bytes());
+        assert_eq!(&bytes[16..32], &(u128::MAX - 1).to_le_bytes());
+        assert_eq!(&bytes[32..40], &1_000u64.to_le_bytes());
+        assert_eq!(&bytes[40..48], &2_000u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_liquidity_args_serialization() {
+        let increase = IncreaseLiquidityArgs { liquidity: 1u128 << 96 | 3, amount_0_max: 10, amount_1_max: 20 };
+        let bytes = args_bytes(&increase);
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(&bytes[0..16], &(1u128 << 96 | 3).to_le_bytes());
+        assert_eq!(&bytes[16..24], &10u64.to_le_bytes());
+        assert_eq!(&bytes[24..32], &20u64.to_le_bytes());
+
+        let decrease = DecreaseLiquidityArgs { liquidity: 1u128 << 64, amount_0_min: 1, amount_1_min: 2 };
+        let bytes = args_bytes(&decrease);
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(&bytes[0..16], &(1u128 << 64).to_le_bytes());
+        assert_eq!(&bytes[16..24], &1u64.to_le_bytes());
+        assert_eq!(&bytes[24..32], &2u64.to_le_bytes());
+
+        let mut increase_v2 = IncreaseLiquidityV2Args {
+            liquidity: 7,
+            amount_0_max: 10,
+            amount_1_max: 20,
+            base_flag: OptionBool::NONE,
+        };
+        assert_eq!(args_bytes(&increase_v2).len(), 34);
+        assert_eq!(&args_bytes(&increase_v2)[32..34], &[0, 0]);
+        increase_v2.base_flag = Some(true).into();
+        assert_eq!(&args_bytes(&increase_v2)[32..34], &[1, 1]);
+    }
+
+    #[test]
+    fn test_tick_array_view() {
+        let mut data = [0u8; 10240];
+        data[0..8].copy_from_slice(&TICK_ARRAY_STATE_DISCRIMINATOR);
+        data[40..44].copy_from_slice(&(-600i32).to_le_bytes());
+        let o = 44 + 3 * 168;
+        data[o + 4..o + 20].copy_from_slice(&(-42i128).to_le_bytes());
+        data[o + 20..o + 36].copy_from_slice(&42u128.to_le_bytes());
+
+        let array = TickArrayView::new(&data, &TICK_ARRAY_LAYOUT, 10).unwrap();
+        let tick = array.next_initialized_tick(-500, true).unwrap();
+        assert_eq!((tick.index, tick.liquidity_net), (-570, -42));
+        assert!(array.next_initialized_tick(-560, false).is_none());
+
+        data[0] ^= 1;
+        assert!(TickArrayView::new(&data, &TICK_ARRAY_LAYOUT, 10).is_none());
+    }
+
+    #[test]
+    fn test_account_discriminators() {
+        assert_eq!(POOL_STATE_DISCRIMINATOR, crate::common::hash::anchor_discriminator("account", "PoolState"));
+        assert_eq!(POOL_STATE_DISCRIMINATOR, raydium_clmm::POOL_STATE_DISCRIMINATOR);
+        assert_eq!(TICK_ARRAY_STATE_DISCRIMINATOR, raydium_clmm::TICK_ARRAY_STATE_DISCRIMINATOR);
+    }
+
+    #[test]
+    fn test_swap_v2_exact_out_serialization() {
+        assert_eq!(SWAP_V2, crate::common::hash::anchor_discriminator("global", "swap_v2"));
+        let args = SwapV2Args::exact_out(1_000, 1_050, 0);
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 33);
+        assert_eq!(&bytes[0..8], &1_000u64.to_le_bytes());
+        assert_eq!(&bytes[8..16], &1_050u64.to_le_bytes());
+        assert_eq!(&bytes[16..32], &0u128.to_le_bytes());
+        assert_eq!(bytes[32], 0);
+
+        let args = SwapV2Args::exact_in(1_000, 950, 1u128 << 64);
+        let bytes = args_bytes(&args);
+        assert_eq!(&bytes[16..32], &(1u128 << 64).to_le_bytes());
+        assert_eq!(bytes[32], 1);
+    }
+}
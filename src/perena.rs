@@ -5,16 +5,19 @@
 //! Instructions: 22
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_bytes, read_pubkey, read_u128_le, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("NUMERUNsFCP3kuNmWZuXtm1AaQCPj9uw6Guv2Ekoi5P"));
 
@@ -1803,3 +1806,171 @@ pub fn swap_exact_out_hinted<'a>(
     invoke_signed::<11>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Account State
+// ============================================
+//
+// Parse-only: the IDL gives the `StablePool` layout but not the fixed-point
+// scaling of `curve_Amp`, `curve_a`, `curve_b` or `inv_L`, so there is no
+// off-chain quote built on these fields yet.
+
+/// Account discriminator for `StablePool`
+pub const STABLE_POOL_DISCRIMINATOR: [u8; 8] = [239, 91, 93, 162, 171, 14, 42, 66];
+
+/// Maximum stables in one Numeraire pool
+pub const MAX_STABLES: usize = 10;
+
+/// One stable's virtual pair against the pool numeraire
+///
+/// Curve: `x + y + a + b - A / (x + a) - A / (y + b) - D = 0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StablePair {
+    pub x_reserve_amount: u64,
+    pub y_reserve: u64,
+    /// Amplification coefficient `A`
+    pub curve_amp: u128,
+    pub curve_a: u128,
+    pub curve_b: u128,
+    pub inv_l: u128,
+    pub x_mint: [u8; 32],
+    pub x_vault: [u8; 32],
+    pub decimals: u8,
+}
+
+impl StablePair {
+    /// `#[repr(C)]` size, including trailing padding
+    const SIZE: usize = 368;
+
+    fn from_bytes(data: &[u8], offset: usize) -> Option<Self> {
+        // pair_authority (0), x_reserve_amount (32), y_reserve (40), curve_Amp (48),
+        // curve_a (64), curve_b (80), inv_L (96), owner (112), x_mint (144), x_vault (176),
+        // curve_alpha / curve_beta, newest rate, decimals (232)
+        Some(Self {
+            x_reserve_amount: read_u64_le(data, offset + 32)?,
+            y_reserve: read_u64_le(data, offset + 40)?,
+            curve_amp: read_u128_le(data, offset + 48)?,
+            curve_a: read_u128_le(data, offset + 64)?,
+            curve_b: read_u128_le(data, offset + 80)?,
+            inv_l: read_u128_le(data, offset + 96)?,
+            x_mint: read_pubkey(data, offset + 144)?,
+            x_vault: read_pubkey(data, offset + 176)?,
+            decimals: *data.get(offset + 232)?,
+        })
+    }
+}
+
+/// Decoded Numeraire `StablePool` (bytemuck, `#[repr(C)]`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StablePool {
+    pub lp_mint: [u8; 32],
+    pub inv_t: u64,
+    pub pairs: [StablePair; MAX_STABLES],
+    pub weights: [u32; MAX_STABLES],
+    pub total_weight: u64,
+    pub status: u32,
+    pub fee_num: u32,
+    pub fee_denom: u32,
+    pub decimals: u8,
+    pub num_stables: u8,
+}
+
+impl StablePool {
+    /// Account size
+    pub const SIZE: usize = 4024;
+
+    /// Parse a `StablePool` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || data[0..8] != STABLE_POOL_DISCRIMINATOR {
+            return None;
+        }
+        // pool_seed (8), lp_mint (40), whitelisted_adder, owner, inv_T (136), inv_T_max, pairs (152)
+        let mut pairs = [StablePair::default(); MAX_STABLES];
+        for (i, pair) in pairs.iter_mut().enumerate() {
+            *pair = StablePair::from_bytes(data, 152 + i * StablePair::SIZE)?;
+        }
+        let mut weights = [0u32; MAX_STABLES];
+        for (i, weight) in weights.iter_mut().enumerate() {
+            *weight = u32::from_le_bytes(read_bytes(data, 3832 + i * 4)?);
+        }
+        let num_stables = data[3893];
+        if num_stables as usize > MAX_STABLES {
+            return None;
+        }
+        Some(Self {
+            lp_mint: read_pubkey(data, 40)?,
+            inv_t: read_u64_le(data, 136)?,
+            pairs,
+            weights,
+            total_weight: read_u64_le(data, 3872)?,
+            status: u32::from_le_bytes(read_bytes(data, 3880)?),
+            fee_num: u32::from_le_bytes(read_bytes(data, 3884)?),
+            fee_denom: u32::from_le_bytes(read_bytes(data, 3888)?),
+            decimals: data[3892],
+            num_stables,
+        })
+    }
+
+    /// Pairs in use, in pool index order
+    #[inline(always)]
+    pub fn active_pairs(&self) -> &[StablePair] {
+        &self.pairs[..self.num_stables as usize]
+    }
+
+    /// Target weights of the pairs in use, out of `total_weight`
+    #[inline(always)]
+    pub fn active_weights(&self) -> &[u32] {
+        &self.weights[..self.num_stables as usize]
+    }
+
+    /// Index of `mint` in the pool
+    #[inline]
+    pub fn index_of(&self, mint: &[u8; 32]) -> Option<usize> {
+        self.active_pairs().iter().position(|pair| pair.x_mint == *mint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_three_asset_pool() {
+        let mut data = [0u8; StablePool::SIZE];
+        data[0..8].copy_from_slice(&STABLE_POOL_DISCRIMINATOR);
+        data[40..72].copy_from_slice(&[9; 32]);
+        for (i, (reserve, amp, weight)) in [(1_000_000u64, 500u128, 5_000u32), (2_000_000, 500, 3_000), (3_000_000, 800, 2_000)]
+            .into_iter()
+            .enumerate()
+        {
+            let o = 152 + i * 368;
+            data[o + 32..o + 40].copy_from_slice(&reserve.to_le_bytes());
+            data[o + 48..o + 64].copy_from_slice(&amp.to_le_bytes());
+            data[o + 144..o + 176].copy_from_slice(&[i as u8 + 1; 32]);
+            data[o + 232] = 6;
+            data[3832 + i * 4..3836 + i * 4].copy_from_slice(&weight.to_le_bytes());
+        }
+        data[3872..3880].copy_from_slice(&10_000u64.to_le_bytes());
+        data[3884..3888].copy_from_slice(&1u32.to_le_bytes());
+        data[3888..3892].copy_from_slice(&10_000u32.to_le_bytes());
+        data[3892] = 6;
+        data[3893] = 3;
+
+        let pool = StablePool::from_bytes(&data).unwrap();
+        assert_eq!(pool.lp_mint, [9; 32]);
+        assert_eq!(pool.active_pairs().len(), 3);
+        assert_eq!(pool.active_weights(), &[5_000, 3_000, 2_000]);
+        assert_eq!(pool.total_weight, 10_000);
+        assert_eq!((pool.fee_num, pool.fee_denom), (1, 10_000));
+        assert_eq!(pool.active_pairs()[2].curve_amp, 800);
+        assert_eq!(pool.active_pairs()[1].x_reserve_amount, 2_000_000);
+        assert_eq!(pool.index_of(&[3; 32]), Some(2));
+        assert_eq!(pool.index_of(&[4; 32]), None);
+
+        data[3893] = 11;
+        assert!(StablePool::from_bytes(&data).is_none());
+        data[3893] = 3;
+        data[0] ^= 1;
+        assert!(StablePool::from_bytes(&data).is_none());
+    }
+}
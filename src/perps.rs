@@ -5,16 +5,19 @@
 //! Instructions: 59
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_bytes, read_pubkey, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("PERPHjGBqRHArX4DySjwM6UJHiR3sWAatqfdBS2qQJu"));
 
@@ -5556,3 +5559,322 @@ pub fn close_borrow_position<'a>(
     invoke_signed::<5>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Collateral
+// ============================================
+// Jupiter Perps has no direct add/remove-collateral instruction. Margin is
+// changed through market position requests with `sizeUsdDelta = 0`, which a
+// keeper executes in a later transaction against the custody oracles (the
+// request itself takes no oracle accounts). The request PDA is
+// `["position_request", position, counter (u64 LE), [1 = increase | 2 = decrease]]`,
+// so `counter` must be unused for the position, e.g. a timestamp.
+//
+// Writable accounts on both requests: `owner` (signer, pays rent),
+// `funding_account` / `receiving_account`, `position_request` and
+// `position_request_ata`. `position` is writable when adding (the request
+// may create it) and read-only when removing; `custody`,
+// `collateral_custody`, `pool` and `perpetuals` are read-only.
+
+/// Position side (`Side`)
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    None = 0,
+    Long = 1,
+    Short = 2,
+}
+
+/// Accounts for `add_collateral` (`createIncreasePositionMarketRequest`)
+pub type AddCollateralAccounts<'a> = CreateincreasepositionmarketrequestAccounts<'a>;
+
+/// Accounts for `remove_collateral` (`createDecreasePositionMarketRequest`)
+pub type RemoveCollateralAccounts<'a> = CreatedecreasepositionmarketrequestAccounts<'a>;
+
+/// Length of the collateral request instruction data
+pub const COLLATERAL_REQUEST_DATA_LEN: usize = 42;
+
+/// Instruction data depositing `collateral_token_delta` into a position
+///
+/// `CreateIncreasePositionMarketRequestParams`: sizeUsdDelta (0),
+/// collateralTokenDelta, side, priceSlippage, jupiterMinimumOut (`None`),
+/// counter. `collateral_token_delta` is in the input mint's base units.
+pub fn add_collateral_data(
+    collateral_token_delta: u64,
+    side: Side,
+    price_slippage: u64,
+    counter: u64,
+) -> [u8; COLLATERAL_REQUEST_DATA_LEN] {
+    let mut data = [0u8; COLLATERAL_REQUEST_DATA_LEN];
+    data[0..8].copy_from_slice(&CREATE_INCREASE_POSITION_MARKET_REQUEST);
+    data[16..24].copy_from_slice(&collateral_token_delta.to_le_bytes());
+    data[24] = side as u8;
+    data[25..33].copy_from_slice(&price_slippage.to_le_bytes());
+    data[34..42].copy_from_slice(&counter.to_le_bytes());
+    data
+}
+
+/// Instruction data withdrawing `collateral_usd_delta` from a position
+///
+/// `CreateDecreasePositionMarketRequestParams`: collateralUsdDelta,
+/// sizeUsdDelta (0), priceSlippage, jupiterMinimumOut (`None`),
+/// entirePosition (`None`), counter. The amount is in USD with 6 decimals;
+/// the keeper pays it out in `desired_mint`.
+pub fn remove_collateral_data(
+    collateral_usd_delta: u64,
+    price_slippage: u64,
+    counter: u64,
+) -> [u8; COLLATERAL_REQUEST_DATA_LEN] {
+    let mut data = [0u8; COLLATERAL_REQUEST_DATA_LEN];
+    data[0..8].copy_from_slice(&CREATE_DECREASE_POSITION_MARKET_REQUEST);
+    data[8..16].copy_from_slice(&collateral_usd_delta.to_le_bytes());
+    data[24..32].copy_from_slice(&price_slippage.to_le_bytes());
+    data[34..42].copy_from_slice(&counter.to_le_bytes());
+    data
+}
+
+/// Request a collateral deposit into an existing or new position
+#[inline(always)]
+pub fn add_collateral<'a>(
+    accounts: &AddCollateralAccounts<'a>,
+    collateral_token_delta: u64,
+    side: Side,
+    price_slippage: u64,
+    counter: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    let data = add_collateral_data(collateral_token_delta, side, price_slippage, counter);
+    let instruction_accounts = accounts.to_instruction_accounts();
+    let instruction = InstructionView {
+        program_id: &ID,
+        accounts: &instruction_accounts,
+        data: &data,
+    };
+    invoke_signed::<16>(&instruction, &accounts.to_views(), signers)
+}
+
+/// Request a collateral withdrawal, keeping the position size
+#[inline(always)]
+pub fn remove_collateral<'a>(
+    accounts: &RemoveCollateralAccounts<'a>,
+    collateral_usd_delta: u64,
+    price_slippage: u64,
+    counter: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    let data = remove_collateral_data(collateral_usd_delta, price_slippage, counter);
+    let instruction_accounts = accounts.to_instruction_accounts();
+    let instruction = InstructionView {
+        program_id: &ID,
+        accounts: &instruction_accounts,
+        data: &data,
+    };
+    invoke_signed::<16>(&instruction, &accounts.to_views(), signers)
+}
+
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `Custody`
+pub const CUSTODY_DISCRIMINATOR: [u8; 8] = [1, 184, 48, 81, 93, 131, 63, 145];
+
+/// Decoded `Custody` account (fields needed for sizing and fees)
+///
+/// A `Pool` holds one liquidity pool made of several custodies, listed in
+/// `Pool.custodies` (a Borsh vec of pubkeys). Each custody owns exactly one
+/// token: its mint, the vault `token_account`, its oracle and its
+/// per-position fees. Pool-wide swap and liquidity fees live in `Pool.fees`,
+/// not here. Positions reference two custodies: the traded asset and the
+/// collateral (the same one for longs, a stable custody for shorts).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Custody {
+    pub pool: [u8; 32],
+    pub mint: [u8; 32],
+    pub token_account: [u8; 32],
+    pub decimals: u8,
+    pub is_stable: bool,
+    /// `OracleParams.oracleAccount`
+    pub oracle: [u8; 32],
+    /// `OracleParams.oracleType` (0 = None, 1 = Test, 2 = Pyth)
+    pub oracle_type: u8,
+    pub max_price_age_sec: u32,
+    /// `PricingParams.tradeImpactFeeScalar`
+    pub trade_impact_fee_scalar: u64,
+    /// `PricingParams.swapSpread`
+    pub swap_spread: u64,
+    /// `PricingParams.maxLeverage` (in BPS)
+    pub max_leverage: u64,
+    /// `Assets.owned`
+    pub owned: u64,
+    /// `Assets.locked`
+    pub locked: u64,
+    pub increase_position_bps: u64,
+    pub decrease_position_bps: u64,
+    pub max_position_size_usd: u64,
+    pub doves_oracle: [u8; 32],
+}
+
+impl Custody {
+    /// Minimum account size up to and including `dovesOracle`
+    pub const MIN_SIZE: usize = 352;
+
+    /// Parse a `Custody` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::MIN_SIZE || data[0..8] != CUSTODY_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            pool: read_pubkey(data, 8)?,
+            mint: read_pubkey(data, 40)?,
+            token_account: read_pubkey(data, 72)?,
+            decimals: data[104],
+            is_stable: data[105] != 0,
+            // oracle: oracleAccount, oracleType, buffer (u64), maxPriceAgeSec
+            oracle: read_pubkey(data, 106)?,
+            oracle_type: data[138],
+            max_price_age_sec: u32::from_le_bytes(read_bytes(data, 147)?),
+            // pricing: tradeImpactFeeScalar, buffer, swapSpread, maxLeverage, max long/short sizes
+            trade_impact_fee_scalar: read_u64_le(data, 151)?,
+            swap_spread: read_u64_le(data, 167)?,
+            max_leverage: read_u64_le(data, 175)?,
+            // permissions (7 bools) at 199, targetRatioBps at 206, assets at 214
+            owned: read_u64_le(data, 222)?,
+            locked: read_u64_le(data, 230)?,
+            // fundingRateState (32 bytes) at 262, bump, tokenAccountBump
+            increase_position_bps: read_u64_le(data, 296)?,
+            decrease_position_bps: read_u64_le(data, 304)?,
+            max_position_size_usd: read_u64_le(data, 312)?,
+            doves_oracle: read_pubkey(data, 320)?,
+        })
+    }
+
+    /// Opening fee for a position of `size_usd`, rounded up
+    #[inline(always)]
+    pub fn increase_position_fee(&self, size_usd: u64) -> u64 {
+        (size_usd as u128 * self.increase_position_bps as u128).div_ceil(10_000).min(u64::MAX as u128) as u64
+    }
+
+    /// Closing fee for a position of `size_usd`, rounded up
+    #[inline(always)]
+    pub fn decrease_position_fee(&self, size_usd: u64) -> u64 {
+        (size_usd as u128 * self.decrease_position_bps as u128).div_ceil(10_000).min(u64::MAX as u128) as u64
+    }
+}
+
+// ============================================
+// PnL
+// ============================================
+
+/// Unrealized PnL of a position at `mark_price`, in USD base units
+///
+/// `size_usd * |mark - entry| / entry`, positive when a long's price rose
+/// or a short's fell. Prices and size use the program's USD scale (6
+/// decimals), so the result does too. Rounded toward zero and saturating;
+/// excludes open/close fees and borrow fees. Zero for `Side::None` or a
+/// zero `entry_price`.
+#[inline(always)]
+pub fn unrealized_pnl(entry_price: u64, mark_price: u64, size_usd: u64, side: Side) -> i64 {
+    if entry_price == 0 {
+        return 0;
+    }
+    let move_usd = size_usd as u128 * entry_price.abs_diff(mark_price) as u128 / entry_price as u128;
+    let move_usd = move_usd.min(i64::MAX as u128) as i64;
+    let price_rose = mark_price > entry_price;
+    match side {
+        Side::Long if price_rose => move_usd,
+        Side::Short if !price_rose => move_usd,
+        Side::Long | Side::Short => -move_usd,
+        Side::None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custody_parse() {
+        let mut data = [0u8; Custody::MIN_SIZE];
+        data[0..8].copy_from_slice(&CUSTODY_DISCRIMINATOR);
+        data[8..40].copy_from_slice(&[1u8; 32]);
+        data[40..72].copy_from_slice(&[2u8; 32]);
+        data[72..104].copy_from_slice(&[3u8; 32]);
+        data[104] = 9;
+        data[106..138].copy_from_slice(&[4u8; 32]);
+        data[138] = 2;
+        data[147..151].copy_from_slice(&60u32.to_le_bytes());
+        data[151..159].copy_from_slice(&1_250_000_000_000u64.to_le_bytes());
+        data[175..183].copy_from_slice(&1_000_000u64.to_le_bytes());
+        data[222..230].copy_from_slice(&5_000_000_000u64.to_le_bytes());
+        data[230..238].copy_from_slice(&1_000_000_000u64.to_le_bytes());
+        data[296..304].copy_from_slice(&6u64.to_le_bytes());
+        data[304..312].copy_from_slice(&6u64.to_le_bytes());
+        data[312..320].copy_from_slice(&2_500_000_000_000u64.to_le_bytes());
+        data[320..352].copy_from_slice(&[5u8; 32]);
+
+        let custody = Custody::from_bytes(&data).unwrap();
+        assert_eq!(custody.pool, [1u8; 32]);
+        assert_eq!(custody.mint, [2u8; 32]);
+        assert_eq!(custody.token_account, [3u8; 32]);
+        assert_eq!(custody.decimals, 9);
+        assert!(!custody.is_stable);
+        assert_eq!(custody.oracle, [4u8; 32]);
+        assert_eq!(custody.oracle_type, 2);
+        assert_eq!(custody.max_price_age_sec, 60);
+        assert_eq!(custody.trade_impact_fee_scalar, 1_250_000_000_000);
+        assert_eq!(custody.max_leverage, 1_000_000);
+        assert_eq!(custody.owned, 5_000_000_000);
+        assert_eq!(custody.locked, 1_000_000_000);
+        assert_eq!(custody.max_position_size_usd, 2_500_000_000_000);
+        assert_eq!(custody.doves_oracle, [5u8; 32]);
+
+        // 6 bps on $1,000 (6 decimals) = $0.60
+        assert_eq!(custody.increase_position_fee(1_000_000_000), 600_000);
+        assert_eq!(custody.decrease_position_fee(1), 1);
+
+        data[0] ^= 1;
+        assert!(Custody::from_bytes(&data).is_none());
+    }
+
+    #[test]
+    fn test_collateral_request_data() {
+        let add = add_collateral_data(2_500_000, Side::Long, 150_000_000, 7);
+        assert_eq!(&add[0..8], &CREATE_INCREASE_POSITION_MARKET_REQUEST);
+        assert_eq!(&add[8..16], &0u64.to_le_bytes());
+        assert_eq!(&add[16..24], &2_500_000u64.to_le_bytes());
+        assert_eq!(add[24], 1);
+        assert_eq!(&add[25..33], &150_000_000u64.to_le_bytes());
+        // jupiterMinimumOut: None
+        assert_eq!(add[33], 0);
+        assert_eq!(&add[34..42], &7u64.to_le_bytes());
+
+        let remove = remove_collateral_data(10_000_000, 140_000_000, 8);
+        assert_eq!(&remove[0..8], &CREATE_DECREASE_POSITION_MARKET_REQUEST);
+        assert_eq!(&remove[8..16], &10_000_000u64.to_le_bytes());
+        assert_eq!(&remove[16..24], &0u64.to_le_bytes());
+        assert_eq!(&remove[24..32], &140_000_000u64.to_le_bytes());
+        // jupiterMinimumOut and entirePosition: None
+        assert_eq!(&remove[32..34], &[0, 0]);
+        assert_eq!(&remove[34..42], &8u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_unrealized_pnl() {
+        // $10,000 position entered at $150
+        let size = 10_000_000_000;
+        let entry = 150_000_000;
+        // +10%: long gains $1,000, short loses it
+        assert_eq!(unrealized_pnl(entry, 165_000_000, size, Side::Long), 1_000_000_000);
+        assert_eq!(unrealized_pnl(entry, 165_000_000, size, Side::Short), -1_000_000_000);
+        // -20%: long loses $2,000, short gains it
+        assert_eq!(unrealized_pnl(entry, 120_000_000, size, Side::Long), -2_000_000_000);
+        assert_eq!(unrealized_pnl(entry, 120_000_000, size, Side::Short), 2_000_000_000);
+
+        // Flat, unknown side, rounding toward zero
+        assert_eq!(unrealized_pnl(entry, entry, size, Side::Long), 0);
+        assert_eq!(unrealized_pnl(entry, 165_000_000, size, Side::None), 0);
+        assert_eq!(unrealized_pnl(3, 2, 10, Side::Long), -3);
+        assert_eq!(unrealized_pnl(0, 2, 10, Side::Long), 0);
+        assert_eq!(unrealized_pnl(1, u64::MAX, u64::MAX, Side::Short), -i64::MAX);
+    }
+}
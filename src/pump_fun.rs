@@ -5,16 +5,19 @@
 //! Instructions: 27
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{Dex, NATIVE_MINT, invoke_signed, read_pubkey, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P"));
 
@@ -2210,3 +2213,161 @@ pub fn update_global_authority<'a>(
     invoke_signed::<5>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `BondingCurve`
+pub const BONDING_CURVE_DISCRIMINATOR: [u8; 8] = [23, 183, 248, 55, 96, 216, 172, 96];
+
+/// Decoded `BondingCurve` account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BondingCurve {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+    /// Zeroed for curves created before creator fees existed
+    pub creator: [u8; 32],
+    pub is_mayhem_mode: bool,
+}
+
+impl BondingCurve {
+    /// Size of the original layout (up to `complete`)
+    pub const MIN_SIZE: usize = 49;
+
+    /// Parse a `BondingCurve`, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::MIN_SIZE || data[0..8] != BONDING_CURVE_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            virtual_token_reserves: read_u64_le(data, 8)?,
+            virtual_sol_reserves: read_u64_le(data, 16)?,
+            real_token_reserves: read_u64_le(data, 24)?,
+            real_sol_reserves: read_u64_le(data, 32)?,
+            token_total_supply: read_u64_le(data, 40)?,
+            complete: data[48] != 0,
+            creator: read_pubkey(data, 49).unwrap_or([0u8; 32]),
+            is_mayhem_mode: data.get(81).is_some_and(|b| *b != 0),
+        })
+    }
+}
+
+/// Seed prefix of the pool authority `migrate` creates for a mint
+pub const POOL_AUTHORITY_SEED: &[u8] = b"pool-authority";
+
+/// Seed prefix of PumpSwap pools
+pub const PUMPSWAP_POOL_SEED: &[u8] = b"pool";
+
+/// Pool index `migrate` uses for the canonical pool (`u16`, LE)
+pub const CANONICAL_POOL_INDEX: [u8; 2] = [0, 0];
+
+/// Seeds for a mint's pool authority: `["pool-authority", mint]` under `ID`
+#[inline(always)]
+pub fn pool_authority_seeds(mint: &Address) -> [&[u8]; 2] {
+    [POOL_AUTHORITY_SEED, mint.as_ref()]
+}
+
+/// Seeds for the canonical PumpSwap pool `migrate` creates for `mint`
+///
+/// `["pool", 0u16, pool_authority, mint, wsol]` under `pump_fun_amm::ID`.
+#[inline(always)]
+pub fn canonical_pool_seeds<'a>(pool_authority: &'a Address, mint: &'a Address) -> [&'a [u8]; 5] {
+    [PUMPSWAP_POOL_SEED, &CANONICAL_POOL_INDEX, pool_authority.as_ref(), mint.as_ref(), NATIVE_MINT.as_ref()]
+}
+
+/// Derive the canonical PumpSwap pool for `mint`
+#[cfg(any(target_os = "solana", target_arch = "bpf"))]
+#[inline(always)]
+pub fn find_canonical_pool(mint: &Address) -> Address {
+    let (pool_authority, _) = Address::find_program_address(&pool_authority_seeds(mint), &ID);
+    Address::find_program_address(&canonical_pool_seeds(&pool_authority, mint), &crate::pump_fun_amm::ID).0
+}
+
+/// Venue a completed curve's liquidity moved to
+///
+/// The curve account does not record its destination, so this reads the
+/// curve's `complete` flag together with the account at the canonical
+/// PumpSwap pool address (`find_canonical_pool`); pass its data, or `None`
+/// if the account does not exist. Returns `Dex::PumpFunAmm` when the curve
+/// is complete and that account is a PumpSwap `Pool` for `mint` / WSOL.
+///
+/// `None` while the curve is still trading, and for a completed curve
+/// without the pool: it has not been migrated yet, or it graduated to
+/// Raydium AMM V4 before PumpSwap existed.
+pub fn migration_target(bonding_curve: &[u8], mint: &Address, canonical_pool: Option<&[u8]>) -> Option<Dex> {
+    if !BondingCurve::from_bytes(bonding_curve)?.complete {
+        return None;
+    }
+    let pool = crate::pump_fun_amm::Pool::from_bytes(canonical_pool?)?;
+    (pool.base_mint == mint.to_bytes() && pool.quote_mint == NATIVE_MINT.to_bytes()).then_some(Dex::PumpFunAmm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve_fixture(complete: bool) -> [u8; 82] {
+        let mut data = [0u8; 82];
+        data[0..8].copy_from_slice(&BONDING_CURVE_DISCRIMINATOR);
+        data[8..16].copy_from_slice(&279_900_000_000_000u64.to_le_bytes());
+        data[16..24].copy_from_slice(&115_005_359_056u64.to_le_bytes());
+        data[40..48].copy_from_slice(&1_000_000_000_000_000u64.to_le_bytes());
+        data[48] = complete as u8;
+        data[49..81].copy_from_slice(&[5u8; 32]);
+        data
+    }
+
+    #[test]
+    fn test_bonding_curve_parse() {
+        let curve = BondingCurve::from_bytes(&curve_fixture(true)).unwrap();
+        assert_eq!(curve.virtual_token_reserves, 279_900_000_000_000);
+        assert_eq!(curve.virtual_sol_reserves, 115_005_359_056);
+        assert!(curve.complete);
+        assert_eq!(curve.creator, [5u8; 32]);
+
+        // Legacy layout without creator
+        let legacy = BondingCurve::from_bytes(&curve_fixture(true)[..49]).unwrap();
+        assert_eq!(legacy.creator, [0u8; 32]);
+    }
+
+    fn pool_fixture(base_mint: &Address) -> [u8; 211] {
+        let mut data = [0u8; 211];
+        data[0..8].copy_from_slice(&crate::pump_fun_amm::POOL_DISCRIMINATOR);
+        data[43..75].copy_from_slice(base_mint.as_ref());
+        data[75..107].copy_from_slice(NATIVE_MINT.as_ref());
+        data
+    }
+
+    #[test]
+    fn test_migration_target() {
+        let mint = Address::new_from_array([7u8; 32]);
+        let pool = pool_fixture(&mint);
+        assert_eq!(migration_target(&curve_fixture(true), &mint, Some(&pool)), Some(Dex::PumpFunAmm));
+
+        // Still trading, even if a pool exists
+        assert_eq!(migration_target(&curve_fixture(false), &mint, Some(&pool)), None);
+        // Complete without the canonical pool: pending or pre-PumpSwap
+        assert_eq!(migration_target(&curve_fixture(true), &mint, None), None);
+        // Account at the address is not this mint's pool
+        let other = pool_fixture(&Address::new_from_array([8u8; 32]));
+        assert_eq!(migration_target(&curve_fixture(true), &mint, Some(&other)), None);
+        assert_eq!(migration_target(&curve_fixture(true), &mint, Some(&pool[..100])), None);
+
+        let mut bad = curve_fixture(true);
+        bad[0] ^= 1;
+        assert_eq!(migration_target(&bad, &mint, Some(&pool)), None);
+    }
+
+    #[test]
+    fn test_canonical_pool_seeds() {
+        let (authority, mint) = (Address::new_from_array([1u8; 32]), Address::new_from_array([2u8; 32]));
+        assert_eq!(pool_authority_seeds(&mint), [b"pool-authority".as_ref(), mint.as_ref()]);
+        let seeds = canonical_pool_seeds(&authority, &mint);
+        assert_eq!(seeds[0], b"pool");
+        assert_eq!(seeds[1], &[0, 0]);
+        assert_eq!(seeds[2..], [authority.as_ref(), mint.as_ref(), NATIVE_MINT.as_ref()]);
+    }
+}
@@ -5,16 +5,19 @@
 //! Instructions: 23
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, parse_token_account_balance, read_pubkey, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA"));
 
@@ -2006,3 +2009,231 @@ pub fn withdraw<'a>(
     invoke_signed::<15>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `GlobalConfig`
+pub const GLOBAL_CONFIG_DISCRIMINATOR: [u8; 8] = [149, 8, 156, 202, 160, 252, 176, 217];
+
+/// Account discriminator for `Pool`
+pub const POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+
+/// Fee rates from `GlobalConfig`, in basis points
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlobalConfig {
+    pub lp_fee_bps: u64,
+    pub protocol_fee_bps: u64,
+    pub disable_flags: u8,
+    pub coin_creator_fee_bps: u64,
+}
+
+impl GlobalConfig {
+    /// Minimum account size up to and including `coin_creator_fee_basis_points`
+    pub const MIN_SIZE: usize = 321;
+
+    /// Parse a `GlobalConfig` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        // discriminator, admin, lp/protocol fee, disable_flags, 8 fee recipients, creator fee, ...
+        if data.len() < Self::MIN_SIZE || data[0..8] != GLOBAL_CONFIG_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            lp_fee_bps: read_u64_le(data, 40)?,
+            protocol_fee_bps: read_u64_le(data, 48)?,
+            disable_flags: data[56],
+            coin_creator_fee_bps: read_u64_le(data, 313)?,
+        })
+    }
+}
+
+/// Decoded `Pool` account (fields needed for quoting)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pool {
+    pub base_mint: [u8; 32],
+    pub quote_mint: [u8; 32],
+    pub pool_base_token_account: [u8; 32],
+    pub pool_quote_token_account: [u8; 32],
+    pub lp_supply: u64,
+    /// All zeros for pools created before creator fees
+    pub coin_creator: [u8; 32],
+}
+
+impl Pool {
+    /// Size of pools created before `coin_creator` was added
+    pub const LEGACY_SIZE: usize = 211;
+
+    /// Parse a `Pool` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        // discriminator, pool_bump (u8), index (u16), creator, mints, token accounts, lp_supply, coin_creator
+        if data.len() < Self::LEGACY_SIZE || data[0..8] != POOL_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            base_mint: read_pubkey(data, 43)?,
+            quote_mint: read_pubkey(data, 75)?,
+            pool_base_token_account: read_pubkey(data, 139)?,
+            pool_quote_token_account: read_pubkey(data, 171)?,
+            lp_supply: read_u64_le(data, 203)?,
+            coin_creator: read_pubkey(data, 211).unwrap_or_default(),
+        })
+    }
+
+    /// Whether swaps on this pool pay the coin-creator fee
+    #[inline(always)]
+    pub fn has_coin_creator(&self) -> bool {
+        self.coin_creator != [0; 32]
+    }
+}
+
+// ============================================
+// Quote
+// ============================================
+//
+// Fees are charged on the quote side in both directions: added on top of
+// the quote spent when buying, deducted from the quote received when
+// selling. The coin-creator fee only applies to pools with a
+// `coin_creator`. Rates come from `GlobalConfig`; pools priced by the
+// separate fee program's market-cap tiers are not covered.
+
+/// Total fee rate a pool pays, in basis points
+#[inline(always)]
+fn total_fee_bps(pool: &Pool, config: &GlobalConfig) -> u64 {
+    let creator_fee_bps = if pool.has_coin_creator() { config.coin_creator_fee_bps } else { 0 };
+    config.lp_fee_bps + config.protocol_fee_bps + creator_fee_bps
+}
+
+/// Base tokens received for spending `quote_in` (`buy_exact_quote_in`)
+///
+/// Returns 0 on invalid input.
+pub fn quote_buy(
+    pool_data: &[u8],
+    global_config: &[u8],
+    base_vault_data: &[u8],
+    quote_vault_data: &[u8],
+    quote_in: u64,
+) -> u64 {
+    let (Some(pool), Some(config)) = (Pool::from_bytes(pool_data), GlobalConfig::from_bytes(global_config)) else {
+        return 0;
+    };
+    let (Some(base_reserve), Some(quote_reserve)) = (
+        parse_token_account_balance(base_vault_data),
+        parse_token_account_balance(quote_vault_data),
+    ) else {
+        return 0;
+    };
+    // Quote left for the curve once the fees on top of it are set aside
+    let effective_quote = quote_in as u128 * 10_000 / (10_000 + total_fee_bps(&pool, &config)) as u128;
+    let denominator = quote_reserve as u128 + effective_quote;
+    if denominator == 0 {
+        return 0;
+    }
+    (base_reserve as u128 * effective_quote / denominator) as u64
+}
+
+/// Quote tokens received for selling `base_in`, net of all fees
+///
+/// Each fee is rounded up separately, like the program. Returns 0 on
+/// invalid input.
+pub fn quote_sell(
+    pool_data: &[u8],
+    global_config: &[u8],
+    base_vault_data: &[u8],
+    quote_vault_data: &[u8],
+    base_in: u64,
+) -> u64 {
+    let (Some(pool), Some(config)) = (Pool::from_bytes(pool_data), GlobalConfig::from_bytes(global_config)) else {
+        return 0;
+    };
+    let (Some(base_reserve), Some(quote_reserve)) = (
+        parse_token_account_balance(base_vault_data),
+        parse_token_account_balance(quote_vault_data),
+    ) else {
+        return 0;
+    };
+    let denominator = base_reserve as u128 + base_in as u128;
+    if denominator == 0 {
+        return 0;
+    }
+    let quote_out = quote_reserve as u128 * base_in as u128 / denominator;
+    let fee = |bps: u64| (quote_out * bps as u128).div_ceil(10_000);
+    let creator_fee = if pool.has_coin_creator() { fee(config.coin_creator_fee_bps) } else { 0 };
+    quote_out.saturating_sub(fee(config.lp_fee_bps) + fee(config.protocol_fee_bps) + creator_fee) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_fixture() -> [u8; GlobalConfig::MIN_SIZE] {
+        let mut data = [0u8; GlobalConfig::MIN_SIZE];
+        data[0..8].copy_from_slice(&GLOBAL_CONFIG_DISCRIMINATOR);
+        data[40..48].copy_from_slice(&20u64.to_le_bytes());
+        data[48..56].copy_from_slice(&5u64.to_le_bytes());
+        data[313..321].copy_from_slice(&5u64.to_le_bytes());
+        data
+    }
+
+    fn pool_fixture(coin_creator: Option<[u8; 32]>) -> [u8; 244] {
+        let mut data = [0u8; 244];
+        data[0..8].copy_from_slice(&POOL_DISCRIMINATOR);
+        data[43..75].copy_from_slice(&[1u8; 32]);
+        data[75..107].copy_from_slice(&[2u8; 32]);
+        data[203..211].copy_from_slice(&1_000u64.to_le_bytes());
+        if let Some(creator) = coin_creator {
+            data[211..243].copy_from_slice(&creator);
+        }
+        data
+    }
+
+    fn vault(amount: u64) -> [u8; 165] {
+        let mut data = [0u8; 165];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_accounts() {
+        let config = GlobalConfig::from_bytes(&config_fixture()).unwrap();
+        assert_eq!((config.lp_fee_bps, config.protocol_fee_bps, config.coin_creator_fee_bps), (20, 5, 5));
+
+        let pool = Pool::from_bytes(&pool_fixture(Some([9; 32]))).unwrap();
+        assert_eq!((pool.base_mint, pool.quote_mint, pool.lp_supply), ([1; 32], [2; 32], 1_000));
+        assert!(pool.has_coin_creator());
+
+        // Legacy pools end before `coin_creator`
+        let legacy = Pool::from_bytes(&pool_fixture(None)[..Pool::LEGACY_SIZE]).unwrap();
+        assert!(!legacy.has_coin_creator());
+        assert!(Pool::from_bytes(&config_fixture()).is_none());
+    }
+
+    #[test]
+    fn test_quote_sell_fee_split() {
+        let config = config_fixture();
+        // 1B base / 30 SOL
+        let (base, quote) = (vault(1_000_000_000_000_000), vault(30_000_000_000));
+        // 1% of the base reserve
+        let gross: u64 = 297_029_702;
+
+        // lp 20 + protocol 5 bps, each rounded up
+        let out = quote_sell(&pool_fixture(None), &config, &base, &quote, 10_000_000_000_000);
+        assert_eq!(out, gross - 594_060 - 148_515);
+
+        // plus 5 bps creator fee
+        let out = quote_sell(&pool_fixture(Some([9; 32])), &config, &base, &quote, 10_000_000_000_000);
+        assert_eq!(out, gross - 594_060 - 148_515 - 148_515);
+    }
+
+    #[test]
+    fn test_quote_buy_fee_split() {
+        let config = config_fixture();
+        let (base, quote) = (vault(1_000_000_000_000_000), vault(30_000_000_000));
+
+        // 1 SOL in: 25 bps of fees on top without a creator, 30 bps with one
+        let out = quote_buy(&pool_fixture(None), &config, &base, &quote, 1_000_000_000);
+        assert_eq!(out, 32_180_209_158_434);
+        let out = quote_buy(&pool_fixture(Some([9; 32])), &config, &base, &quote, 1_000_000_000);
+        assert_eq!(out, 32_164_683_175_349);
+
+        assert_eq!(quote_buy(&pool_fixture(None), &config, &base, &[], 1_000_000_000), 0);
+    }
+}
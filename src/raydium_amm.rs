@@ -16,10 +16,12 @@
 
 use pinocchio::{
     AccountView, Address, ProgramResult,
-    cpi::{invoke_signed, Signer},
+    cpi::{Signer},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_pubkey, read_u64_le};
+
 // ============================================
 // Constants
 // ============================================
@@ -198,18 +200,33 @@ pub fn parse_swap_fee(data: &[u8]) -> Option<(u64, u64)> {
         return None;
     }
     
-    let numerator = u64::from_le_bytes(
-        data[AmmStateLayout::SWAP_FEE_NUM_OFFSET..AmmStateLayout::SWAP_FEE_NUM_OFFSET + 8]
-            .try_into().ok()?
-    );
-    let denominator = u64::from_le_bytes(
-        data[AmmStateLayout::SWAP_FEE_DEN_OFFSET..AmmStateLayout::SWAP_FEE_DEN_OFFSET + 8]
-            .try_into().ok()?
-    );
+    let numerator = read_u64_le(data, AmmStateLayout::SWAP_FEE_NUM_OFFSET)?;
+    let denominator = read_u64_le(data, AmmStateLayout::SWAP_FEE_DEN_OFFSET)?;
     
     Some((numerator, denominator))
 }
 
+/// AMM status values that let the pool trade against its OpenBook market
+pub const AMM_STATUS_INITIALIZED: u64 = 1;
+pub const AMM_STATUS_ORDER_BOOK_ONLY: u64 = 5;
+
+/// Check whether swaps on this AMM also touch its OpenBook market
+///
+/// True when the pool has a market set and its status still grants
+/// order book permission; such swaps cancel and re-place orders on the
+/// market and cost substantially more compute.
+#[inline(always)]
+pub fn is_market_linked(data: &[u8]) -> bool {
+    if data.len() < AmmStateLayout::MARKET_OFFSET + 32 {
+        return false;
+    }
+    let status = read_u64_le(data, AmmStateLayout::STATUS_OFFSET).unwrap_or(0);
+    let has_market = data[AmmStateLayout::MARKET_OFFSET..AmmStateLayout::MARKET_OFFSET + 32]
+        .iter()
+        .any(|b| *b != 0);
+    has_market && matches!(status, AMM_STATUS_INITIALIZED | AMM_STATUS_ORDER_BOOK_ONLY)
+}
+
 /// Parse vault addresses from AMM state account data
 /// 
 /// Returns (coin_vault, pc_vault) as 32-byte addresses
@@ -219,11 +236,8 @@ pub fn parse_vault_addresses(data: &[u8]) -> Option<([u8; 32], [u8; 32])> {
         return None;
     }
     
-    let mut coin_vault = [0u8; 32];
-    let mut pc_vault = [0u8; 32];
-    
-    coin_vault.copy_from_slice(&data[AmmStateLayout::COIN_VAULT_OFFSET..AmmStateLayout::COIN_VAULT_OFFSET + 32]);
-    pc_vault.copy_from_slice(&data[AmmStateLayout::PC_VAULT_OFFSET..AmmStateLayout::PC_VAULT_OFFSET + 32]);
+    let coin_vault = read_pubkey(data, AmmStateLayout::COIN_VAULT_OFFSET)?;
+    let pc_vault = read_pubkey(data, AmmStateLayout::PC_VAULT_OFFSET)?;
     
     Some((coin_vault, pc_vault))
 }
@@ -237,15 +251,7 @@ pub fn parse_vault_addresses(data: &[u8]) -> Option<([u8; 32], [u8; 32])> {
 /// - ...
 #[inline(always)]
 pub fn parse_token_account_balance(data: &[u8]) -> Option<u64> {
-    if data.len() < 72 {
-        return None;
-    }
-    
-    let amount = u64::from_le_bytes(
-        data[64..72].try_into().ok()?
-    );
-    
-    Some(amount)
+    read_u64_le(data, 64)
 }
 
 /// Get pool reserves from vault accounts
@@ -388,6 +394,42 @@ pub fn swap_base_in<'a>(
     invoke_signed::<17>(&instruction, &account_views, signers)
 }
 
+/// Recommended compute unit limit for a swap on a pool without OpenBook activity
+pub const SWAP_COMPUTE_UNITS: u32 = 40_000;
+
+/// Recommended compute unit limit for a swap that also cranks the OpenBook market
+pub const SWAP_WITH_MARKET_COMPUTE_UNITS: u32 = 100_000;
+
+/// Recommended compute unit limit for one swap against this AMM
+///
+/// Add this to the budget of the rest of the transaction when setting
+/// the `SetComputeUnitLimit` instruction.
+#[inline(always)]
+pub fn swap_compute_unit_hint(amm_data: &[u8]) -> u32 {
+    if is_market_linked(amm_data) {
+        SWAP_WITH_MARKET_COMPUTE_UNITS
+    } else {
+        SWAP_COMPUTE_UNITS
+    }
+}
+
+/// Execute SwapBaseIn and return the compute unit hint for this pool
+///
+/// `amm_data` is the data of `accounts.amm`. The hint is what the
+/// transaction should have budgeted for this swap, useful for logging or
+/// for callers that forward it to an off-chain planner.
+#[inline(always)]
+pub fn swap_with_cu_hint<'a>(
+    accounts: &SwapBaseInAccounts<'a>,
+    args: &SwapBaseInArgs,
+    amm_data: &[u8],
+    signers: &[Signer<'_, '_>],
+) -> Result<u32, pinocchio::error::ProgramError> {
+    let hint = swap_compute_unit_hint(amm_data);
+    swap_base_in(accounts, args, signers)?;
+    Ok(hint)
+}
+
 // ============================================
 // SwapBaseOut (Instruction 11)
 // Fixed output swap, requires OpenBook market accounts
@@ -858,10 +900,8 @@ pub fn calculate_swap_input(
     }
     
     let numerator = (reserve_in as u128)
-        .checked_mul(amount_out as u128)
-        .unwrap_or(u128::MAX)
-        .checked_mul(fee_denominator as u128)
-        .unwrap_or(u128::MAX);
+        .saturating_mul(amount_out as u128)
+        .saturating_mul(fee_denominator as u128);
     
     let denominator = (reserve_out.saturating_sub(amount_out) as u128)
         .checked_mul(fee_denominator.saturating_sub(fee_numerator) as u128)
@@ -875,3 +915,159 @@ pub fn calculate_swap_input(
 pub const fn default_swap_fee() -> (u64, u64) {
     (25, 10000) // 25 / 10000 = 0.25%
 }
+
+// ============================================
+// Quote
+// ============================================
+
+/// OpenBook `OpenOrders` offsets of the AMM's resting order totals
+///
+/// Layout: 5-byte `serum` header, account flags (u64), market, owner,
+/// then native coin free/total and native pc free/total (u64 each).
+pub struct OpenOrdersLayout;
+
+impl OpenOrdersLayout {
+    pub const NATIVE_COIN_TOTAL_OFFSET: usize = 85;
+    pub const NATIVE_PC_TOTAL_OFFSET: usize = 101;
+}
+
+/// Tradable (coin, pc) reserves, as the program computes them for a swap
+///
+/// Vault balances minus the PnL still owed to the protocol. While the pool
+/// is linked to its OpenBook market, funds resting in the AMM's open orders
+/// count as well and `open_orders_data` is required; for unlinked pools it
+/// is ignored.
+pub fn pool_reserves(
+    amm_data: &[u8],
+    coin_vault_data: &[u8],
+    pc_vault_data: &[u8],
+    open_orders_data: Option<&[u8]>,
+) -> Option<(u64, u64)> {
+    let (mut coin, mut pc) = parse_pool_reserves_from_vaults(coin_vault_data, pc_vault_data)?;
+    if is_market_linked(amm_data) {
+        let open_orders = open_orders_data?;
+        coin = coin.checked_add(read_u64_le(open_orders, OpenOrdersLayout::NATIVE_COIN_TOTAL_OFFSET)?)?;
+        pc = pc.checked_add(read_u64_le(open_orders, OpenOrdersLayout::NATIVE_PC_TOTAL_OFFSET)?)?;
+    }
+    let need_take_pnl_coin = read_u64_le(amm_data, AmmStateLayout::NEED_TAKE_PNL_COIN_OFFSET)?;
+    let need_take_pnl_pc = read_u64_le(amm_data, AmmStateLayout::NEED_TAKE_PNL_PC_OFFSET)?;
+    Some((coin.checked_sub(need_take_pnl_coin)?, pc.checked_sub(need_take_pnl_pc)?))
+}
+
+/// Quote a SwapBaseIn before the CPI
+///
+/// Reserves come from [`pool_reserves`]. `fee` is (numerator, denominator);
+/// `None` uses the pool's own swap fee (0.25% on standard pools). Like the
+/// program, the fee is taken from the input and rounded up. Returns `0`
+/// when the accounts cannot be parsed, the fee is invalid, or a
+/// market-linked pool is quoted without its open orders.
+pub fn quote_exact_in(
+    amm_data: &[u8],
+    coin_vault_data: &[u8],
+    pc_vault_data: &[u8],
+    open_orders_data: Option<&[u8]>,
+    fee: Option<(u64, u64)>,
+    amount_in: u64,
+    coin_to_pc: bool,
+) -> u64 {
+    let Some((coin, pc)) = pool_reserves(amm_data, coin_vault_data, pc_vault_data, open_orders_data) else {
+        return 0;
+    };
+    let Some((fee_numerator, fee_denominator)) = fee.or_else(|| parse_swap_fee(amm_data)) else {
+        return 0;
+    };
+    if fee_denominator == 0 || fee_numerator > fee_denominator {
+        return 0;
+    }
+    let (reserve_in, reserve_out) = if coin_to_pc { (coin, pc) } else { (pc, coin) };
+    if reserve_in == 0 || reserve_out == 0 {
+        return 0;
+    }
+
+    let swap_fee = (amount_in as u128 * fee_numerator as u128).div_ceil(fee_denominator as u128);
+    let amount_in_after_fee = amount_in as u128 - swap_fee;
+    (reserve_out as u128 * amount_in_after_fee / (reserve_in as u128 + amount_in_after_fee)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{dump_account_roles, mock_account_views};
+    use pinocchio::account::RuntimeAccount;
+
+    #[test]
+    fn test_swap_base_in_v2_account_roles() {
+        let mut raw: [RuntimeAccount; 8] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let accounts = SwapBaseInV2Accounts {
+            token_program: &v[0],
+            amm: &v[1],
+            amm_authority: &v[2],
+            amm_coin_vault: &v[3],
+            amm_pc_vault: &v[4],
+            user_source: &v[5],
+            user_destination: &v[6],
+            user_owner: &v[7],
+        };
+        assert_eq!(dump_account_roles(&accounts.to_instruction_accounts()), "r w r w w w w rs");
+    }
+
+    fn amm_fixture(status: u64, market: [u8; 32]) -> [u8; AmmStateLayout::SIZE] {
+        let mut data = [0u8; AmmStateLayout::SIZE];
+        data[AmmStateLayout::STATUS_OFFSET..AmmStateLayout::STATUS_OFFSET + 8]
+            .copy_from_slice(&status.to_le_bytes());
+        data[AmmStateLayout::MARKET_OFFSET..AmmStateLayout::MARKET_OFFSET + 32]
+            .copy_from_slice(&market);
+        data
+    }
+
+    #[test]
+    fn test_market_linked_pools_get_higher_cu_hint() {
+        let linked = amm_fixture(AMM_STATUS_INITIALIZED, [7u8; 32]);
+        let swap_only = amm_fixture(6, [7u8; 32]);
+        let no_market = amm_fixture(AMM_STATUS_INITIALIZED, [0u8; 32]);
+
+        assert!(is_market_linked(&linked));
+        assert!(!is_market_linked(&swap_only));
+        assert!(!is_market_linked(&no_market));
+        assert!(swap_compute_unit_hint(&linked) > swap_compute_unit_hint(&swap_only));
+        assert_eq!(swap_compute_unit_hint(&no_market), SWAP_COMPUTE_UNITS);
+        assert_eq!(swap_compute_unit_hint(&[]), SWAP_COMPUTE_UNITS);
+    }
+
+    fn put_u64(data: &mut [u8], offset: usize, value: u64) {
+        data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn vault(amount: u64) -> [u8; 165] {
+        let mut data = [0u8; 165];
+        put_u64(&mut data, 64, amount);
+        data
+    }
+
+    #[test]
+    fn test_quote_exact_in() {
+        let mut amm = amm_fixture(6, [0u8; 32]);
+        put_u64(&mut amm, AmmStateLayout::SWAP_FEE_NUM_OFFSET, 25);
+        put_u64(&mut amm, AmmStateLayout::SWAP_FEE_DEN_OFFSET, 10_000);
+        put_u64(&mut amm, AmmStateLayout::NEED_TAKE_PNL_COIN_OFFSET, 1_000);
+        put_u64(&mut amm, AmmStateLayout::NEED_TAKE_PNL_PC_OFFSET, 5_000);
+        let coin_vault = vault(1_000_000_000);
+        let pc_vault = vault(50_000_000_000);
+
+        assert_eq!(pool_reserves(&amm, &coin_vault, &pc_vault, None), Some((999_999_000, 49_999_995_000)));
+        assert_eq!(quote_exact_in(&amm, &coin_vault, &pc_vault, None, None, 1_000_000, true), 49_825_344);
+        assert_eq!(quote_exact_in(&amm, &coin_vault, &pc_vault, None, None, 100_000_000, false), 1_991_026);
+        assert_eq!(quote_exact_in(&amm, &coin_vault, &pc_vault, None, Some((30, 10_000)), 1_000_000, true), 49_800_393);
+        assert_eq!(quote_exact_in(&amm, &coin_vault, &pc_vault, None, Some((1, 0)), 1_000_000, true), 0);
+
+        // Market-linked pools also count their open orders, and need them to quote
+        put_u64(&mut amm, AmmStateLayout::STATUS_OFFSET, AMM_STATUS_INITIALIZED);
+        amm[AmmStateLayout::MARKET_OFFSET] = 1;
+        let mut open_orders = [0u8; 3228];
+        put_u64(&mut open_orders, OpenOrdersLayout::NATIVE_COIN_TOTAL_OFFSET, 200_000_000);
+        put_u64(&mut open_orders, OpenOrdersLayout::NATIVE_PC_TOTAL_OFFSET, 10_000_000_000);
+        assert_eq!(quote_exact_in(&amm, &coin_vault, &pc_vault, None, None, 1_000_000, true), 0);
+        assert_eq!(quote_exact_in(&amm, &coin_vault, &pc_vault, Some(&open_orders), None, 1_000_000, true), 49_833_613);
+    }
+}
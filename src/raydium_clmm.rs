@@ -5,16 +5,20 @@
 //! Instructions: 25
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::clmm_common::{self, SwapState, TickArrayLayout, TickArrayView, TickRange};
+use crate::common::{OptionBool, invoke_signed, read_bytes, read_pubkey, read_u128_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK"));
 
@@ -126,7 +130,7 @@ pub struct IncreaseLiquidityV2Args {
     pub liquidity: u128,
     pub amount_0_max: u64,
     pub amount_1_max: u64,
-    pub base_flag: Option<bool>,
+    pub base_flag: OptionBool,
 }
 
 /// Arguments for `initialize_reward`
@@ -136,18 +140,8 @@ pub struct InitializeRewardArgs {
     pub param: [u8; 32],
 }
 
-/// Arguments for `open_position`
-#[derive(Clone, Copy)]
-#[repr(C, packed)]
-pub struct OpenPositionArgs {
-    pub tick_lower_index: i32,
-    pub tick_upper_index: i32,
-    pub tick_array_lower_start_index: i32,
-    pub tick_array_upper_start_index: i32,
-    pub liquidity: u128,
-    pub amount_0_max: u64,
-    pub amount_1_max: u64,
-}
+/// Arguments for `open_position`; build with `clmm_common::open_position_args`
+pub use crate::clmm_common::OpenPositionArgs;
 
 /// Arguments for `open_position_v2`
 #[derive(Clone, Copy)]
@@ -161,7 +155,7 @@ pub struct OpenPositionV2Args {
     pub amount_0_max: u64,
     pub amount_1_max: u64,
     pub with_metadata: bool,
-    pub base_flag: Option<bool>,
+    pub base_flag: OptionBool,
 }
 
 /// Arguments for `open_position_with_token22_nft`
@@ -176,7 +170,7 @@ pub struct OpenPositionWithToken22NftArgs {
     pub amount_0_max: u64,
     pub amount_1_max: u64,
     pub with_metadata: bool,
-    pub base_flag: Option<bool>,
+    pub base_flag: OptionBool,
 }
 
 /// Arguments for `set_reward_params`
@@ -2288,3 +2282,279 @@ pub fn update_reward_infos<'a>(
     invoke_signed::<1>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `PoolState`
+pub const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+
+/// Account discriminator for `TickArrayState`
+pub const TICK_ARRAY_STATE_DISCRIMINATOR: [u8; 8] = [192, 155, 85, 205, 49, 249, 129, 42];
+
+/// Account discriminator for `AmmConfig`
+pub const AMM_CONFIG_DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+
+/// Ticks per `TickArrayState`
+pub const TICK_ARRAY_SIZE: usize = 60;
+
+/// Packed size of one `TickState`
+const TICK_STATE_SIZE: usize = 168;
+
+/// `TickArrayState` layout for `TickArrayView`
+///
+/// discriminator, pool_id (pubkey), start_tick_index (i32), then ticks of
+/// tick (i32), liquidity_net (i128), liquidity_gross (u128), fee/reward
+/// growths, padding. A tick counts as initialized when any position
+/// references it (`liquidity_gross != 0`).
+pub const TICK_ARRAY_LAYOUT: TickArrayLayout = TickArrayLayout {
+    discriminator: TICK_ARRAY_STATE_DISCRIMINATOR,
+    start_tick_index_offset: 40,
+    ticks_offset: 44,
+    tick_size: TICK_STATE_SIZE,
+    tick_count: TICK_ARRAY_SIZE,
+    liquidity_net_offset: 4,
+    liquidity_gross_offset: 20,
+    initialized_offset: None,
+};
+
+/// Decoded `PoolState` account (fields needed for routing and quoting)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolState {
+    pub amm_config: [u8; 32],
+    pub token_mint_0: [u8; 32],
+    pub token_mint_1: [u8; 32],
+    pub token_vault_0: [u8; 32],
+    pub token_vault_1: [u8; 32],
+    pub observation_key: [u8; 32],
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+}
+
+impl PoolState {
+    /// Minimum account size up to and including `tick_current`
+    pub const MIN_SIZE: usize = 273;
+
+    /// Parse a `PoolState` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        // discriminator, bump (u8), amm_config, owner, mints, vaults, observation_key, decimals, ...
+        if data.len() < Self::MIN_SIZE || data[0..8] != POOL_STATE_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            amm_config: read_pubkey(data, 9)?,
+            token_mint_0: read_pubkey(data, 73)?,
+            token_mint_1: read_pubkey(data, 105)?,
+            token_vault_0: read_pubkey(data, 137)?,
+            token_vault_1: read_pubkey(data, 169)?,
+            observation_key: read_pubkey(data, 201)?,
+            tick_spacing: u16::from_le_bytes(read_bytes(data, 235)?),
+            liquidity: read_u128_le(data, 237)?,
+            sqrt_price_x64: read_u128_le(data, 253)?,
+            tick_current: i32::from_le_bytes(read_bytes(data, 269)?),
+        })
+    }
+}
+
+/// `zero_for_one` for a swap selling `input_mint` in this pool
+///
+/// `true` when the input is `token_mint_0` (price moves down), `false`
+/// when it is `token_mint_1`, `None` if the pool does not hold the mint.
+#[inline(always)]
+pub fn zero_for_one_for(input_mint: &[u8; 32], pool_state: &PoolState) -> Option<bool> {
+    if input_mint == &pool_state.token_mint_0 {
+        Some(true)
+    } else if input_mint == &pool_state.token_mint_1 {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// `trade_fee_rate` of an `AmmConfig` account, in hundredths of a bip
+#[inline(always)]
+pub fn trade_fee_rate(amm_config: &[u8]) -> Option<u32> {
+    // discriminator, bump (u8), index (u16), owner (pubkey), protocol_fee_rate, trade_fee_rate, ...
+    if amm_config.len() < 51 || amm_config[0..8] != AMM_CONFIG_DISCRIMINATOR {
+        return None;
+    }
+    Some(u32::from_le_bytes(read_bytes(amm_config, 47)?))
+}
+
+// ============================================
+// Quote
+// ============================================
+
+/// Exact-input output of a swap across the given `TickArrayState` accounts
+///
+/// `tick_arrays` is the raw data of the arrays the swap would pass, in any
+/// order. Crosses initialized ticks with `clmm_common::quote_exact_in`;
+/// `None` on invalid accounts or when the arrays do not cover `amount_in`.
+pub fn quote_exact_in(
+    pool_state: &[u8],
+    amm_config: &[u8],
+    tick_arrays: &[&[u8]],
+    amount_in: u64,
+    zero_for_one: bool,
+) -> Option<u64> {
+    let pool = PoolState::from_bytes(pool_state)?;
+    let state = SwapState { sqrt_price_x64: pool.sqrt_price_x64, liquidity: pool.liquidity, tick_current: pool.tick_current };
+    let fee_rate = trade_fee_rate(amm_config)?;
+    clmm_common::quote_exact_in(state, fee_rate, tick_arrays, &TICK_ARRAY_LAYOUT, pool.tick_spacing, amount_in, zero_for_one)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    fn pool_fixture() -> [u8; PoolState::MIN_SIZE] {
+        let mut data = [0u8; PoolState::MIN_SIZE];
+        data[0..8].copy_from_slice(&POOL_STATE_DISCRIMINATOR);
+        data[9..41].copy_from_slice(&[3u8; 32]);
+        data[73..105].copy_from_slice(&[10u8; 32]);
+        data[105..137].copy_from_slice(&[11u8; 32]);
+        data[137..169].copy_from_slice(&[20u8; 32]);
+        data[169..201].copy_from_slice(&[21u8; 32]);
+        data[235..237].copy_from_slice(&60u16.to_le_bytes());
+        data[253..269].copy_from_slice(&(1u128 << 64).to_le_bytes());
+        data[269..273].copy_from_slice(&(-7i32).to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_pool_state() {
+        assert_eq!(POOL_STATE_DISCRIMINATOR, crate::common::hash::anchor_discriminator("account", "PoolState"));
+        let pool = PoolState::from_bytes(&pool_fixture()).unwrap();
+        assert_eq!(pool.amm_config, [3; 32]);
+        assert_eq!((pool.token_mint_0, pool.token_mint_1), ([10; 32], [11; 32]));
+        assert_eq!((pool.token_vault_0, pool.token_vault_1), ([20; 32], [21; 32]));
+        assert_eq!((pool.tick_spacing, pool.sqrt_price_x64, pool.tick_current), (60, 1 << 64, -7));
+        assert!(PoolState::from_bytes(&pool_fixture()[..PoolState::MIN_SIZE - 1]).is_none());
+    }
+
+    #[test]
+    fn test_zero_for_one_for() {
+        let pool = PoolState::from_bytes(&pool_fixture()).unwrap();
+        assert_eq!(zero_for_one_for(&[10; 32], &pool), Some(true));
+        assert_eq!(zero_for_one_for(&[11; 32], &pool), Some(false));
+        // A vault is not a mint
+        assert_eq!(zero_for_one_for(&[20; 32], &pool), None);
+    }
+
+    fn tick_array_fixture(start_tick_index: i32, ticks: &[(usize, i128)]) -> [u8; 10240] {
+        let mut data = [0u8; 10240];
+        data[0..8].copy_from_slice(&TICK_ARRAY_STATE_DISCRIMINATOR);
+        data[40..44].copy_from_slice(&start_tick_index.to_le_bytes());
+        for &(i, net) in ticks {
+            let o = 44 + i * TICK_STATE_SIZE;
+            data[o..o + 4].copy_from_slice(&(start_tick_index + i as i32 * 10).to_le_bytes());
+            data[o + 4..o + 20].copy_from_slice(&net.to_le_bytes());
+            data[o + 20..o + 36].copy_from_slice(&net.unsigned_abs().to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_tick_array_view() {
+        assert_eq!(TICK_ARRAY_STATE_DISCRIMINATOR, crate::common::hash::anchor_discriminator("account", "TickArrayState"));
+        let data = tick_array_fixture(600, &[(5, 1_000), (50, -1_000)]);
+
+        let array = TickArrayView::new(&data, &TICK_ARRAY_LAYOUT, 10).unwrap();
+        assert_eq!(array.start_tick_index, 600);
+        assert!(array.contains(1199) && !array.contains(1200));
+        // zero_for_one walks down, inclusive of the current tick
+        let down = array.next_initialized_tick(1100, true).unwrap();
+        assert_eq!((down.index, down.liquidity_net), (1100, -1_000));
+        let up = array.next_initialized_tick(600, false).unwrap();
+        assert_eq!((up.index, up.liquidity_net), (650, 1_000));
+        assert!(array.next_initialized_tick(1100, false).is_none());
+
+        assert!(TickArrayView::new(&data[..10000], &TICK_ARRAY_LAYOUT, 10).is_none());
+    }
+
+    fn config_fixture(trade_fee_rate: u32) -> [u8; 117] {
+        let mut data = [0u8; 117];
+        data[0..8].copy_from_slice(&AMM_CONFIG_DISCRIMINATOR);
+        data[47..51].copy_from_slice(&trade_fee_rate.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_quote_exact_in() {
+        assert_eq!(AMM_CONFIG_DISCRIMINATOR, crate::common::hash::anchor_discriminator("account", "AmmConfig"));
+        // Price 1.0 at tick -7, 1e15 liquidity over -600..600
+        let mut pool = pool_fixture();
+        pool[235..237].copy_from_slice(&10u16.to_le_bytes());
+        pool[237..253].copy_from_slice(&1_000_000_000_000_000u128.to_le_bytes());
+        pool[253..269].copy_from_slice(&crate::clmm_common::sqrt_price_at_tick(-7).unwrap().to_le_bytes());
+        let below = tick_array_fixture(-600, &[(0, 1_000_000_000_000_000)]);
+        let above = tick_array_fixture(0, &[(59, -1_000_000_000_000_000)]);
+        let arrays: [&[u8]; 2] = [&below, &above];
+
+        let config = config_fixture(2_500);
+        let out = quote_exact_in(&pool, &config, &arrays, 1_000_000, true).unwrap();
+        // 0.25% fee at a price of 1.0001^-7: 997_499 * 0.99930
+        assert_eq!(out, 996_801);
+        assert!(quote_exact_in(&pool, &config, &arrays, 1_000_000, false).unwrap() > out);
+        assert_eq!(quote_exact_in(&pool, &pool, &arrays, 1_000_000, true), None);
+        // The arrays end at -600
+        assert_eq!(quote_exact_in(&pool, &config, &arrays, 100_000_000_000_000, true), None);
+    }
+
+    #[test]
+    fn test_open_position_discriminators() {
+        assert_eq!(OPEN_POSITION, [135, 128, 47, 77, 15, 152, 240, 49]);
+        assert_eq!(OPEN_POSITION_WITH_TOKEN22_NFT, [77, 255, 174, 82, 125, 29, 201, 46]);
+    }
+
+    #[test]
+    fn test_open_position_args_serialization() {
+        let args = OpenPositionArgs {
+            tick_lower_index: -120,
+            tick_upper_index: 240,
+            tick_array_lower_start_index: -3600,
+            tick_array_upper_start_index: 0,
+            liquidity: 1u128 << 64 | 7,
+            amount_0_max: 1_000,
+            amount_1_max: 2_000,
+        };
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 48);
+        assert_eq!(&bytes[0..4], &(-120i32).to_le_bytes());
+        assert_eq!(&bytes[4..8], &240i32.to_le_bytes());
+        assert_eq!(&bytes[8..12], &(-3600i32).to_le_bytes());
+        assert_eq!(&bytes[12..16], &0i32.to_le_bytes());
+        assert_eq!(&bytes[16..32], &(1u128 << 64 | 7).to_le_bytes());
+        assert_eq!(&bytes[32..40], &1_000u64.to_le_bytes());
+        assert_eq!(&bytes[40..48], &2_000u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_open_position_token22_args_serialization() {
+        let mut args = OpenPositionWithToken22NftArgs {
+            tick_lower_index: -60,
+            tick_upper_index: 60,
+            tick_array_lower_start_index: -3600,
+            tick_array_upper_start_index: 0,
+            liquidity: 5_000_000,
+            amount_0_max: 10,
+            amount_1_max: 20,
+            with_metadata: true,
+            base_flag: OptionBool::NONE,
+        };
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 51);
+        assert_eq!(&bytes[16..32], &5_000_000u128.to_le_bytes());
+        assert_eq!(bytes[48], 1);
+        assert_eq!(&bytes[49..51], &[0, 0]);
+
+        args.base_flag = OptionBool::some(false);
+        assert_eq!(&args_bytes(&args)[49..51], &[1, 0]);
+        args.base_flag = Some(true).into();
+        assert_eq!(&args_bytes(&args)[49..51], &[1, 1]);
+    }
+
+}
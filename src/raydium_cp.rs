@@ -5,16 +5,19 @@
 //! Instructions: 14
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_bytes, read_pubkey, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C"));
 
@@ -1330,3 +1333,358 @@ pub fn withdraw<'a>(
     invoke_signed::<14>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `PoolState`
+pub const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+
+/// Account discriminator for `AmmConfig`
+pub const AMM_CONFIG_DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+
+/// Denominator of all `AmmConfig` fee rates (1e6 = 100%)
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+/// Decoded `PoolState` account (fields needed for quoting)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolState {
+    pub amm_config: [u8; 32],
+    pub token_0_vault: [u8; 32],
+    pub token_1_vault: [u8; 32],
+    pub token_0_mint: [u8; 32],
+    pub token_1_mint: [u8; 32],
+    pub status: u8,
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    /// 0 = both tokens, 1 = token 0 only, 2 = token 1 only
+    pub creator_fee_on: u8,
+    pub enable_creator_fee: bool,
+    pub creator_fees_token_0: u64,
+    pub creator_fees_token_1: u64,
+}
+
+impl PoolState {
+    /// Minimum account size up to and including `creator_fees_token_1`
+    pub const MIN_SIZE: usize = 413;
+
+    /// Parse a `PoolState` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::MIN_SIZE || data[0..8] != POOL_STATE_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            amm_config: read_pubkey(data, 8)?,
+            token_0_vault: read_pubkey(data, 72)?,
+            token_1_vault: read_pubkey(data, 104)?,
+            token_0_mint: read_pubkey(data, 168)?,
+            token_1_mint: read_pubkey(data, 200)?,
+            status: data[329],
+            protocol_fees_token_0: read_u64_le(data, 341)?,
+            protocol_fees_token_1: read_u64_le(data, 349)?,
+            fund_fees_token_0: read_u64_le(data, 357)?,
+            fund_fees_token_1: read_u64_le(data, 365)?,
+            creator_fee_on: data[389],
+            enable_creator_fee: data[390] != 0,
+            creator_fees_token_0: read_u64_le(data, 397)?,
+            creator_fees_token_1: read_u64_le(data, 405)?,
+        })
+    }
+
+    /// Vault balances net of accrued, unclaimed fees: `(token_0, token_1)`
+    #[inline(always)]
+    pub fn reserves(&self, vault_0_amount: u64, vault_1_amount: u64) -> (u64, u64) {
+        (
+            vault_0_amount
+                .saturating_sub(self.protocol_fees_token_0)
+                .saturating_sub(self.fund_fees_token_0)
+                .saturating_sub(self.creator_fees_token_0),
+            vault_1_amount
+                .saturating_sub(self.protocol_fees_token_1)
+                .saturating_sub(self.fund_fees_token_1)
+                .saturating_sub(self.creator_fees_token_1),
+        )
+    }
+
+    /// Whether the creator fee is charged on the input side of a swap
+    #[inline(always)]
+    pub fn creator_fee_on_input(&self, zero_for_one: bool) -> bool {
+        match self.creator_fee_on {
+            1 => zero_for_one,
+            2 => !zero_for_one,
+            _ => true,
+        }
+    }
+}
+
+/// Decoded `AmmConfig` account (fee rates, in `FEE_RATE_DENOMINATOR` units)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AmmConfig {
+    pub index: u16,
+    pub trade_fee_rate: u64,
+    pub protocol_fee_rate: u64,
+    pub fund_fee_rate: u64,
+    pub creator_fee_rate: u64,
+}
+
+impl AmmConfig {
+    /// Minimum account size up to and including `creator_fee_rate`
+    pub const MIN_SIZE: usize = 116;
+
+    /// Parse an `AmmConfig` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::MIN_SIZE || data[0..8] != AMM_CONFIG_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            index: u16::from_le_bytes(read_bytes(data, 10)?),
+            trade_fee_rate: read_u64_le(data, 12)?,
+            protocol_fee_rate: read_u64_le(data, 20)?,
+            fund_fee_rate: read_u64_le(data, 28)?,
+            // create_pool_fee, protocol_owner, fund_owner at 36..108
+            creator_fee_rate: read_u64_le(data, 108)?,
+        })
+    }
+}
+
+// ============================================
+// Quote
+// ============================================
+
+/// Expected output received by the user for an exact-input swap
+///
+/// Mirrors `swap_base_input`: the Token-2022 transfer fee is taken from
+/// `amount_in` on the way into the vault, the trade fee (and creator fee,
+/// on whichever side the pool charges it) is applied to the constant
+/// product, and the output mint's transfer fee is deducted from what the
+/// user receives. Reserves exclude accrued protocol/fund/creator fees, so
+/// the vault token accounts are needed alongside the pool state.
+///
+/// `base_to_quote` swaps token 0 for token 1. Transfer fees use the higher
+/// of the mint's older/newer rates (no clock is read). Returns 0 on invalid
+/// input.
+pub fn quote_exact_in(
+    pool_state: &[u8],
+    amm_config: &[u8],
+    vault_0_data: &[u8],
+    vault_1_data: &[u8],
+    in_mint_data: &[u8],
+    out_mint_data: &[u8],
+    amount_in: u64,
+    base_to_quote: bool,
+) -> u64 {
+    use crate::common::{parse_token_account_balance, transfer_fee};
+
+    let (Some(pool), Some(config)) = (PoolState::from_bytes(pool_state), AmmConfig::from_bytes(amm_config)) else {
+        return 0;
+    };
+    let (Some(vault_0), Some(vault_1)) = (
+        parse_token_account_balance(vault_0_data),
+        parse_token_account_balance(vault_1_data),
+    ) else {
+        return 0;
+    };
+    let (reserve_0, reserve_1) = pool.reserves(vault_0, vault_1);
+    let (reserve_in, reserve_out) = if base_to_quote { (reserve_0, reserve_1) } else { (reserve_1, reserve_0) };
+    if reserve_in == 0 || reserve_out == 0 {
+        return 0;
+    }
+
+    let actual_in = amount_in.saturating_sub(transfer_fee(in_mint_data, u64::MAX, amount_in)) as u128;
+    let creator_rate = if pool.enable_creator_fee { config.creator_fee_rate } else { 0 } as u128;
+    let creator_on_input = pool.creator_fee_on_input(base_to_quote);
+    let denominator = FEE_RATE_DENOMINATOR as u128;
+
+    let input_fee_rate = config.trade_fee_rate as u128 + if creator_on_input { creator_rate } else { 0 };
+    let input_fee = (actual_in * input_fee_rate).div_ceil(denominator);
+    let input_less_fees = actual_in.saturating_sub(input_fee);
+
+    let swapped = input_less_fees * reserve_out as u128 / (reserve_in as u128 + input_less_fees);
+    let output_fee = if creator_on_input { 0 } else { (swapped * creator_rate).div_ceil(denominator) };
+    let amount_out = swapped.saturating_sub(output_fee).min(u64::MAX as u128) as u64;
+
+    amount_out.saturating_sub(transfer_fee(out_mint_data, u64::MAX, amount_out))
+}
+
+// ============================================
+// Pool Creation
+// ============================================
+
+/// Seed of the vault and LP mint authority PDA (`authority` in `initialize`)
+pub const AUTH_SEED: &[u8] = b"vault_and_lp_mint_auth_seed";
+
+/// LP mint PDA seed prefix: `["pool_lp_mint", pool_state]`
+pub const POOL_LP_MINT_SEED: &[u8] = b"pool_lp_mint";
+
+/// Vault PDA seed prefix: `["pool_vault", pool_state, mint]`
+pub const POOL_VAULT_SEED: &[u8] = b"pool_vault";
+
+/// Observation PDA seed prefix: `["observation", pool_state]`
+pub const OBSERVATION_SEED: &[u8] = b"observation";
+
+/// Receiver of the pool creation fee (`create_pool_fee` in `initialize`)
+pub const CREATE_POOL_FEE_RECEIVER: Address = Address::new_from_array(five8_const::decode_32_const("DNXgeM9EiiaAbaWvwjHj9fQQLAX5ZsfHyvmYUNRAdNC8"));
+
+impl InitializeArgs {
+    /// Initial deposit of `amount_a` of `mint_a` and `amount_b` of `mint_b`
+    ///
+    /// `initialize` requires `token_0_mint < token_1_mint` (byte order) and
+    /// takes the amounts in that order; this sorts them so the caller can
+    /// pass the pair either way round. `open_time` is the unix timestamp
+    /// from which swaps are allowed. `None` when both mints are the same.
+    #[inline(always)]
+    pub fn for_mints(
+        mint_a: &[u8; 32],
+        amount_a: u64,
+        mint_b: &[u8; 32],
+        amount_b: u64,
+        open_time: u64,
+    ) -> Option<Self> {
+        let (init_amount_0, init_amount_1) = match mint_a.cmp(mint_b) {
+            core::cmp::Ordering::Less => (amount_a, amount_b),
+            core::cmp::Ordering::Greater => (amount_b, amount_a),
+            core::cmp::Ordering::Equal => return None,
+        };
+        Some(Self { init_amount_0, init_amount_1, open_time })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{args_bytes, dump_account_roles, last_cpi, mint_fixture, mock_account_views, transfer_fee_mint_fixture, TokenLayout};
+    use pinocchio::account::RuntimeAccount;
+
+    #[test]
+    fn test_initialize_args_serialization() {
+        assert_eq!(INITIALIZE, crate::common::hash::anchor_discriminator("global", "initialize"));
+        let args = InitializeArgs::for_mints(&[9; 32], 5_000, &[1; 32], 7_000, 1_700_000_000).unwrap();
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 24);
+        assert_eq!(&bytes[0..8], &7_000u64.to_le_bytes());
+        assert_eq!(&bytes[8..16], &5_000u64.to_le_bytes());
+        assert_eq!(&bytes[16..24], &1_700_000_000u64.to_le_bytes());
+
+        let args = InitializeArgs::for_mints(&[1; 32], 5_000, &[9; 32], 7_000, 0).unwrap();
+        assert_eq!(&args_bytes(&args)[0..8], &5_000u64.to_le_bytes());
+        assert!(InitializeArgs::for_mints(&[1; 32], 5_000, &[1; 32], 7_000, 0).is_none());
+    }
+
+    #[test]
+    fn test_initialize_accounts() {
+        let mut raw: [RuntimeAccount; 20] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let accounts = InitializeAccounts {
+            creator: &v[0],
+            amm_config: &v[1],
+            authority: &v[2],
+            pool_state: &v[3],
+            token_0_mint: &v[4],
+            token_1_mint: &v[5],
+            lp_mint: &v[6],
+            creator_token_0: &v[7],
+            creator_token_1: &v[8],
+            creator_lp_token: &v[9],
+            token_0_vault: &v[10],
+            token_1_vault: &v[11],
+            create_pool_fee: &v[12],
+            observation_state: &v[13],
+            token_program: &v[14],
+            token_0_program: &v[15],
+            token_1_program: &v[16],
+            associated_token_program: &v[17],
+            system_program: &v[18],
+            rent: &v[19],
+        };
+        // The creator funds the pool; mints and programs stay readonly
+        assert_eq!(
+            dump_account_roles(&accounts.to_instruction_accounts()),
+            "ws r r w r r w w w w w w w w r r r r r r"
+        );
+        let args = InitializeArgs { init_amount_0: 1_000, init_amount_1: 2_000, open_time: 0 };
+        initialize(&accounts, &args, &[]).unwrap();
+        let (program, data) = last_cpi();
+        assert_eq!(program, ID);
+        assert_eq!(&data[..8], &INITIALIZE);
+        assert_eq!(&data[8..16], &1_000u64.to_le_bytes());
+        assert_eq!(&data[16..24], &2_000u64.to_le_bytes());
+        assert_eq!(&data[24..], &0u64.to_le_bytes());
+    }
+
+    fn pool_fixture(creator_fee_on: u8, enable_creator_fee: bool) -> [u8; PoolState::MIN_SIZE] {
+        let mut data = [0u8; PoolState::MIN_SIZE];
+        data[0..8].copy_from_slice(&POOL_STATE_DISCRIMINATOR);
+        data[341..349].copy_from_slice(&1_000u64.to_le_bytes());
+        data[365..373].copy_from_slice(&2_000u64.to_le_bytes());
+        data[389] = creator_fee_on;
+        data[390] = enable_creator_fee as u8;
+        data
+    }
+
+    fn config_fixture() -> [u8; AmmConfig::MIN_SIZE] {
+        let mut data = [0u8; AmmConfig::MIN_SIZE];
+        data[0..8].copy_from_slice(&AMM_CONFIG_DISCRIMINATOR);
+        data[12..20].copy_from_slice(&2_500u64.to_le_bytes());
+        data[20..28].copy_from_slice(&120_000u64.to_le_bytes());
+        data[108..116].copy_from_slice(&1_000u64.to_le_bytes());
+        data
+    }
+
+    fn vault_fixture(amount: u64) -> [u8; TokenLayout::ACCOUNT_SIZE] {
+        let mut data = [0u8; TokenLayout::ACCOUNT_SIZE];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_pool_state_and_config() {
+        let pool = PoolState::from_bytes(&pool_fixture(1, true)).unwrap();
+        assert_eq!(pool.reserves(1_000_000_001_000, 2_002_000), (1_000_000_000_000, 2_000_000));
+        assert!(pool.creator_fee_on_input(true));
+        assert!(!pool.creator_fee_on_input(false));
+
+        let config = AmmConfig::from_bytes(&config_fixture()).unwrap();
+        assert_eq!(config.trade_fee_rate, 2_500);
+        assert_eq!(config.protocol_fee_rate, 120_000);
+        assert_eq!(config.creator_fee_rate, 1_000);
+
+        assert!(AmmConfig::from_bytes(&pool_fixture(0, false)[..AmmConfig::MIN_SIZE]).is_none());
+    }
+
+    #[test]
+    fn test_quote_exact_in_without_transfer_fees() {
+        let (pool, config) = (pool_fixture(0, false), config_fixture());
+        let (vault_0, vault_1) = (vault_fixture(1_000_000_001_000), vault_fixture(2_002_000));
+        let mint = mint_fixture();
+
+        // 0.25% trade fee on 1_000_000, then x*y=k against (1e12, 2e6)
+        let out = quote_exact_in(&pool, &config, &vault_0, &vault_1, &mint, &mint, 1_000_000, true);
+        assert_eq!(out, 1);
+        let out = quote_exact_in(&pool, &config, &vault_0, &vault_1, &mint, &mint, 1_000, false);
+        assert_eq!(out, 498_251_621);
+
+        // Creator fee on the output side
+        let pool = pool_fixture(1, true);
+        let out = quote_exact_in(&pool, &config, &vault_0, &vault_1, &mint, &mint, 1_000, false);
+        assert_eq!(out, 497_753_369);
+    }
+
+    #[test]
+    fn test_quote_exact_in_with_transfer_fees() {
+        let (pool, config) = (pool_fixture(0, false), config_fixture());
+        let (vault_0, vault_1) = (vault_fixture(1_000_000_001_000), vault_fixture(2_002_000));
+        let mint = mint_fixture();
+        let fee_mint = transfer_fee_mint_fixture(100, u64::MAX);
+
+        // 1% withheld on the way in: 1_000 -> 990
+        let out = quote_exact_in(&pool, &config, &vault_0, &vault_1, &fee_mint, &mint, 1_000, false);
+        assert_eq!(out, 493_256_577);
+        // 1% withheld on the way out
+        let out = quote_exact_in(&pool, &config, &vault_0, &vault_1, &mint, &fee_mint, 1_000, false);
+        assert_eq!(out, 493_269_104);
+
+        assert_eq!(quote_exact_in(&[], &config, &vault_0, &vault_1, &mint, &mint, 1_000, false), 0);
+    }
+}
@@ -5,16 +5,23 @@
 //! Instructions: 23
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
+    error::ProgramError,
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, parse_token_account_balance};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("LanMV9sAd7wArD4vJFi2qDdfnVhFxYSUg6eADduJ3uj"));
 
@@ -2169,3 +2176,280 @@ pub fn update_platform_curve_param<'a>(
     invoke_signed::<4>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Launch
+// ============================================
+//
+// `initialize` takes Borsh `MintParams` (decimals + name/symbol/uri
+// strings), a `CurveParams` enum and `VestingParams`. The generated
+// `InitializeArgs` above cannot express the strings, so launches go through
+// `launch`, which builds the data on the heap behind the `alloc` feature.
+// The curve and vesting part is fixed-size and encoded by `LaunchParams`.
+
+/// Accounts for `launch` (`initialize`)
+pub type LaunchAccounts<'a> = InitializeAccounts<'a>;
+
+/// Pool the curve migrates to once `total_quote_fund_raising` is reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MigrateType {
+    /// Raydium AMM v4
+    Amm = 0,
+    /// Raydium CPMM (CP-Swap)
+    CpSwap = 1,
+}
+
+/// Bonding curve shape (`CurveParams` variant)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveType {
+    /// Constant product; `total_base_sell` of the supply is sold on the curve
+    Constant { total_base_sell: u64 },
+    /// Fixed price
+    Fixed,
+    /// Linearly increasing price
+    Linear,
+}
+
+/// Fixed-size launch parameters: curve and creator vesting
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LaunchParams {
+    /// Total base token supply, in base units
+    pub supply: u64,
+    /// Quote raised before migration, in quote units
+    pub total_quote_fund_raising: u64,
+    pub curve: CurveType,
+    pub migrate_type: MigrateType,
+    /// Base tokens locked for the creator
+    pub total_locked_amount: u64,
+    /// Seconds after migration before unlocking starts
+    pub cliff_period: u64,
+    /// Seconds over which locked tokens unlock linearly
+    pub unlock_period: u64,
+}
+
+/// Longest Borsh encoding of `LaunchParams`: Constant curve (1 + 25) + vesting (24)
+pub const LAUNCH_PARAMS_MAX_LEN: usize = 50;
+
+impl LaunchParams {
+    /// Borsh-encode `CurveParams` then `VestingParams`; returns the buffer and its used length
+    pub fn encode(&self) -> ([u8; LAUNCH_PARAMS_MAX_LEN], usize) {
+        let mut buf = [0u8; LAUNCH_PARAMS_MAX_LEN];
+        let mut len = 0;
+        let mut put = |bytes: &[u8]| {
+            buf[len..len + bytes.len()].copy_from_slice(bytes);
+            len += bytes.len();
+        };
+        match self.curve {
+            CurveType::Constant { total_base_sell } => {
+                put(&[0]);
+                put(&self.supply.to_le_bytes());
+                put(&total_base_sell.to_le_bytes());
+            }
+            CurveType::Fixed => {
+                put(&[1]);
+                put(&self.supply.to_le_bytes());
+            }
+            CurveType::Linear => {
+                put(&[2]);
+                put(&self.supply.to_le_bytes());
+            }
+        }
+        put(&self.total_quote_fund_raising.to_le_bytes());
+        put(&[self.migrate_type as u8]);
+        put(&self.total_locked_amount.to_le_bytes());
+        put(&self.cliff_period.to_le_bytes());
+        put(&self.unlock_period.to_le_bytes());
+        (buf, len)
+    }
+}
+
+/// Base token metadata (`MintParams`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LaunchMetadata<'s> {
+    pub decimals: u8,
+    pub name: &'s str,
+    pub symbol: &'s str,
+    pub uri: &'s str,
+}
+
+/// Instruction data for `initialize`
+#[cfg(feature = "alloc")]
+pub fn launch_instruction_data(metadata: &LaunchMetadata<'_>, params: &LaunchParams) -> Vec<u8> {
+    let (fixed, fixed_len) = params.encode();
+    let strings = metadata.name.len() + metadata.symbol.len() + metadata.uri.len();
+    let mut data = Vec::with_capacity(8 + 1 + 12 + strings + fixed_len);
+    data.extend_from_slice(&INITIALIZE);
+    data.push(metadata.decimals);
+    for s in [metadata.name, metadata.symbol, metadata.uri] {
+        data.extend_from_slice(&(s.len() as u32).to_le_bytes());
+        data.extend_from_slice(s.as_bytes());
+    }
+    data.extend_from_slice(&fixed[..fixed_len]);
+    data
+}
+
+/// CPI: create the base mint and its bonding-curve pool
+#[cfg(feature = "alloc")]
+pub fn launch<'a>(
+    accounts: &LaunchAccounts<'a>,
+    metadata: &LaunchMetadata<'_>,
+    params: &LaunchParams,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    let data = launch_instruction_data(metadata, params);
+    let instruction_accounts = accounts.to_instruction_accounts();
+    let instruction = InstructionView {
+        program_id: &ID,
+        accounts: &instruction_accounts,
+        data: &data,
+    };
+    let account_views = accounts.to_views();
+    invoke_signed::<18>(&instruction, &account_views, signers)
+}
+
+// ============================================
+// Swap Helpers
+// ============================================
+
+/// Whether spending `input_mint` on the curve is a buy
+///
+/// `true` for the quote mint (`buy_exact_in` / `buy_exact_out`), `false`
+/// for the base mint (`sell_exact_in` / `sell_exact_out`), `None` if the
+/// mint is neither.
+#[inline(always)]
+pub fn buy_direction(input_mint: &[u8; 32], base_mint: &[u8; 32], quote_mint: &[u8; 32]) -> Option<bool> {
+    if input_mint == quote_mint {
+        Some(true)
+    } else if input_mint == base_mint {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// CPI: sell_exact_in of the user's whole base balance
+///
+/// Reads `user_base_token` and sells all of it for at least
+/// `minimum_amount_out` quote, without a share fee. Returns the amount
+/// sold; an empty account sells nothing and skips the CPI.
+#[inline(always)]
+pub fn sell_all<'a>(
+    accounts: &SellExactInAccounts<'a>,
+    minimum_amount_out: u64,
+    signers: &[Signer<'_, '_>],
+) -> Result<u64, ProgramError> {
+    let amount_in = base_balance(accounts.user_base_token)?;
+    if amount_in == 0 {
+        return Ok(0);
+    }
+    let args = SellExactInArgs { amount_in, minimum_amount_out, share_fee_rate: 0 };
+    sell_exact_in(accounts, &args, signers)?;
+    Ok(amount_in)
+}
+
+/// Balance of the user's base token account
+#[inline(always)]
+fn base_balance(account: &AccountView) -> Result<u64, ProgramError> {
+    let data = account.try_borrow()?;
+    parse_token_account_balance(&data).ok_or(ProgramError::InvalidAccountData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(curve: CurveType) -> LaunchParams {
+        LaunchParams {
+            supply: 1_000_000_000_000_000,
+            total_quote_fund_raising: 85_000_000_000,
+            curve,
+            migrate_type: MigrateType::CpSwap,
+            total_locked_amount: 5,
+            cliff_period: 6,
+            unlock_period: 7,
+        }
+    }
+
+    #[test]
+    fn test_initialize_discriminator() {
+        assert_eq!(INITIALIZE, [175, 175, 109, 31, 13, 152, 155, 237]);
+    }
+
+    #[test]
+    fn test_launch_params_serialization() {
+        let (buf, len) = params(CurveType::Constant { total_base_sell: 793_100_000_000_000 }).encode();
+        assert_eq!(len, LAUNCH_PARAMS_MAX_LEN);
+        assert_eq!(buf[0], 0);
+        assert_eq!(&buf[1..9], &1_000_000_000_000_000u64.to_le_bytes());
+        assert_eq!(&buf[9..17], &793_100_000_000_000u64.to_le_bytes());
+        assert_eq!(&buf[17..25], &85_000_000_000u64.to_le_bytes());
+        assert_eq!(buf[25], 1);
+        assert_eq!(&buf[26..34], &5u64.to_le_bytes());
+        assert_eq!(&buf[34..42], &6u64.to_le_bytes());
+        assert_eq!(&buf[42..50], &7u64.to_le_bytes());
+
+        // Fixed and Linear carry no total_base_sell
+        for (curve, tag) in [(CurveType::Fixed, 1), (CurveType::Linear, 2)] {
+            let mut p = params(curve);
+            p.migrate_type = MigrateType::Amm;
+            let (buf, len) = p.encode();
+            assert_eq!(len, 42);
+            assert_eq!(buf[0], tag);
+            assert_eq!(&buf[1..9], &1_000_000_000_000_000u64.to_le_bytes());
+            assert_eq!(&buf[9..17], &85_000_000_000u64.to_le_bytes());
+            assert_eq!(buf[17], 0);
+            assert_eq!(&buf[18..26], &5u64.to_le_bytes());
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_launch_instruction_data() {
+        let metadata = LaunchMetadata { decimals: 6, name: "Token", symbol: "TKN", uri: "u" };
+        let data = launch_instruction_data(&metadata, &params(CurveType::Fixed));
+        assert_eq!(&data[0..8], &INITIALIZE);
+        assert_eq!(data[8], 6);
+        assert_eq!(&data[9..18], b"\x05\0\0\0Token");
+        assert_eq!(&data[18..25], b"\x03\0\0\0TKN");
+        assert_eq!(&data[25..30], b"\x01\0\0\0u");
+        assert_eq!(&data[30..], &params(CurveType::Fixed).encode().0[..42]);
+    }
+
+    #[test]
+    fn test_buy_direction() {
+        let (base, quote) = ([1u8; 32], [2u8; 32]);
+        assert_eq!(buy_direction(&quote, &base, &quote), Some(true));
+        assert_eq!(buy_direction(&base, &base, &quote), Some(false));
+        assert_eq!(buy_direction(&[3; 32], &base, &quote), None);
+    }
+
+    #[test]
+    fn test_sell_all_uses_balance() {
+        use crate::common::{MockAccount, TokenLayout, mock_account_views};
+        use pinocchio::account::RuntimeAccount;
+
+        let mut raw: [RuntimeAccount; 15] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let mut user_base = MockAccount::<165>::new([20; 32]);
+        let offset = TokenLayout::AMOUNT_OFFSET;
+        user_base.data[offset..offset + 8].copy_from_slice(&123_456_789u64.to_le_bytes());
+        let user_base = user_base.view();
+        let mut accounts = SellExactInAccounts {
+            payer: &v[0], authority: &v[1], global_config: &v[2], platform_config: &v[3],
+            pool_state: &v[4], user_base_token: &user_base, user_quote_token: &v[6],
+            base_vault: &v[7], quote_vault: &v[8], base_token_mint: &v[9], quote_token_mint: &v[10],
+            base_token_program: &v[11], quote_token_program: &v[12], event_authority: &v[13], program: &v[14],
+        };
+        assert_eq!(base_balance(&user_base), Ok(123_456_789));
+        assert_eq!(sell_all(&accounts, 1, &[]), Ok(123_456_789));
+
+        let mut empty = MockAccount::<165>::new([21; 32]);
+        let empty = empty.view();
+        accounts.user_base_token = &empty;
+        assert_eq!(sell_all(&accounts, 1, &[]), Ok(0));
+
+        // Not a token account
+        accounts.user_base_token = &v[5];
+        assert_eq!(sell_all(&accounts, 1, &[]), Err(ProgramError::InvalidAccountData));
+    }
+}
@@ -0,0 +1,593 @@
+//! Multi-DEX routing helpers
+//!
+//! Building blocks for a simple on-chain aggregator: compare quotes from
+//! several venues, pick the best one, cache per-pool quotes within a slot,
+//! simulate a multi-leg route off the quote helpers, run it with a
+//! `min_out` per leg checked against balance deltas (`swap_checked`), and
+//! wrap native SOL around a swap that needs WSOL.
+//! Everything works on slices so it stays `no_std` and allocation-free.
+
+use pinocchio::{
+    AccountView,
+    ProgramResult,
+    cpi::{Signer},
+    error::ProgramError,
+    instruction::{InstructionView, InstructionAccount},
+};
+
+use crate::common::log::debug_log;
+use crate::common::{CpiError, Dex, Lamports, QuoteResult, TokenAmount, TokenLayout, SYSTEM_PROGRAM_ID, TOKEN_PROGRAM_ID, invoke_signed, parse_token_account_balance};
+
+// ============================================
+// Quote Comparison
+// ============================================
+
+/// Quote function for a single venue: `(amount_in, input_mint, output_mint) -> amount_out`
+///
+/// Typically a closure over already-loaded pool state that calls the
+/// module's quote helper (e.g. `vertigo::Pool::quote_buy`). Returns `None`
+/// when the venue cannot serve the pair.
+pub type QuoteFn<'a> = &'a dyn Fn(u64, &[u8; 32], &[u8; 32]) -> Option<u64>;
+
+/// Pick the venue with the highest output
+///
+/// Ties keep the earliest entry, so callers can order `quotes` by
+/// preference (e.g. cheapest CU first). Returns `None` for an empty slice.
+#[inline]
+pub fn best_quote(quotes: &[(Dex, u64)]) -> Option<(Dex, u64)> {
+    let mut best: Option<(Dex, u64)> = None;
+    for &(dex, amount_out) in quotes {
+        match best {
+            Some((_, best_out)) if best_out >= amount_out => {}
+            _ => best = Some((dex, amount_out)),
+        }
+    }
+    best
+}
+
+/// Quote every venue for the same trade and return the best one
+///
+/// Venues returning `None` or a zero output are skipped.
+pub fn best_venue(
+    venues: &[(Dex, QuoteFn<'_>)],
+    amount_in: u64,
+    input_mint: &[u8; 32],
+    output_mint: &[u8; 32],
+) -> Option<(Dex, u64)> {
+    let mut best: Option<(Dex, u64)> = None;
+    for (dex, quote) in venues {
+        let amount_out = match quote(amount_in, input_mint, output_mint) {
+            Some(out) if out > 0 => out,
+            _ => continue,
+        };
+        if best.is_none_or(|(_, best_out)| amount_out > best_out) {
+            best = Some((*dex, amount_out));
+        }
+    }
+    best
+}
+
+// ============================================
+// Quote Cache
+// ============================================
+
+/// Cached value for one pool
+#[derive(Clone, Copy, Debug)]
+struct QuoteCacheEntry<T> {
+    dex: Dex,
+    pool: [u8; 32],
+    slot: u64,
+    value: T,
+}
+
+/// Fixed-capacity cache of per-pool quotes, tagged with their slot
+///
+/// Decoding pool state and quoting it is the expensive part of comparing
+/// venues; a router that quotes the same pool several times in one
+/// instruction can reuse the result while the pool cannot have changed.
+/// `T` is whatever the caller recomputes per pool: reserves, a decoded
+/// state, or a `QuoteResult` for a fixed amount. Entries are keyed by
+/// `(Dex, pool)`; inserting an existing key overwrites it, otherwise the
+/// oldest entry is replaced when full. The current slot comes from
+/// `common::parse_clock`.
+#[derive(Clone, Debug)]
+pub struct QuoteCache<T: Copy, const N: usize> {
+    entries: [Option<QuoteCacheEntry<T>>; N],
+    next: usize,
+}
+
+impl<T: Copy, const N: usize> Default for QuoteCache<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy, const N: usize> QuoteCache<T, N> {
+    /// Empty cache
+    pub const fn new() -> Self {
+        Self { entries: [None; N], next: 0 }
+    }
+
+    /// Cached value for `pool` if it is at most `max_age` slots old
+    ///
+    /// `max_age = 0` only accepts values from `current_slot` itself.
+    /// Values stamped after `current_slot` are treated as stale.
+    pub fn get_if_fresh(&self, dex: Dex, pool: &[u8; 32], current_slot: u64, max_age: u64) -> Option<T> {
+        self.entries
+            .iter()
+            .flatten()
+            .find(|entry| entry.dex == dex && &entry.pool == pool)
+            .filter(|entry| current_slot.checked_sub(entry.slot).is_some_and(|age| age <= max_age))
+            .map(|entry| entry.value)
+    }
+
+    /// Store `value` for `pool`, computed at `slot`
+    pub fn insert(&mut self, dex: Dex, pool: &[u8; 32], slot: u64, value: T) {
+        if N == 0 {
+            return;
+        }
+        let entry = Some(QuoteCacheEntry { dex, pool: *pool, slot, value });
+        if let Some(existing) = self.entries.iter_mut().find(|e| e.is_some_and(|e| e.dex == dex && &e.pool == pool)) {
+            *existing = entry;
+            return;
+        }
+        self.entries[self.next] = entry;
+        self.next = (self.next + 1) % N;
+    }
+
+    /// Fresh cached value, or run `quote` and cache what it returns
+    ///
+    /// `None` from `quote` is not cached.
+    pub fn get_or_quote<F>(&mut self, dex: Dex, pool: &[u8; 32], current_slot: u64, max_age: u64, quote: F) -> Option<T>
+    where
+        F: FnOnce() -> Option<T>,
+    {
+        if let Some(hit) = self.get_if_fresh(dex, pool, current_slot, max_age) {
+            return Some(hit);
+        }
+        let value = quote()?;
+        self.insert(dex, pool, current_slot, value);
+        Some(value)
+    }
+}
+
+// ============================================
+// Multi-leg Execution
+// ============================================
+
+/// Swap CPI for one leg: `(accounts, amount_in, min_out)`
+///
+/// Typically a closure that binds `accounts` to the module's `*Accounts`
+/// struct and calls its swap; pass `min_out` through where the program
+/// takes one.
+pub type LegFn<'a> = &'a dyn Fn(&'a [AccountView], TokenAmount, TokenAmount) -> ProgramResult;
+
+/// One swap of a route
+pub struct Leg<'a> {
+    pub dex: Dex,
+    pub swap: LegFn<'a>,
+    /// Accounts handed to `swap`
+    pub accounts: &'a [AccountView],
+    /// User token account receiving this leg's output
+    pub destination: &'a AccountView,
+    pub amount_in: TokenAmount,
+    pub min_out: TokenAmount,
+}
+
+/// Read the amount of an SPL token account view
+#[inline(always)]
+fn token_balance(account: &AccountView) -> Result<TokenAmount, ProgramError> {
+    let data = account.try_borrow()?;
+    parse_token_account_balance(&data).map(TokenAmount).ok_or(ProgramError::InvalidAccountData)
+}
+
+/// Run `swap` and check that `destination` gained at least `min_out`
+///
+/// Reads the SPL token balance of `destination` before and after the CPI,
+/// so a program that under-delivers without erroring (or ignores its own
+/// `min_out`) fails with `CpiError::SlippageExceeded`. Returns the balance
+/// increase. `destination` must not be borrowed across the call.
+pub fn swap_checked<F>(destination: &AccountView, min_out: TokenAmount, swap: F) -> Result<TokenAmount, ProgramError>
+where
+    F: FnOnce() -> ProgramResult,
+{
+    let before = token_balance(destination)?;
+    swap()?;
+    let amount_out = token_balance(destination)?.saturating_sub(before);
+    if amount_out < min_out {
+        return Err(CpiError::SlippageExceeded.into());
+    }
+    Ok(amount_out)
+}
+
+/// Execute `legs` in order, checking each one's output
+///
+/// Each leg runs through `swap_checked` on its `destination`. Stops at the
+/// first failing CPI or the first leg that receives less than its `min_out`
+/// (`CpiError::SlippageExceeded`). Returns the last leg's output, or 0 for
+/// an empty route.
+pub fn execute_legs(legs: &[Leg<'_>]) -> Result<TokenAmount, ProgramError> {
+    let mut amount_out = TokenAmount(0);
+    for leg in legs {
+        debug_log!("leg {:?} amount_in={} min_out={}", leg.dex, leg.amount_in.get(), leg.min_out.get());
+        amount_out = swap_checked(leg.destination, leg.min_out, || (leg.swap)(leg.accounts, leg.amount_in, leg.min_out))?;
+        debug_log!("leg {:?} amount_out={}", leg.dex, amount_out.get());
+    }
+    Ok(amount_out)
+}
+
+// ============================================
+// Route Simulation
+// ============================================
+
+/// Quote for one leg of a simulated route: `amount_in -> QuoteResult`
+///
+/// A closure over already-loaded pool state that calls the module's quote
+/// helper (`solfi_v2::quote_with_latency`, `raydium_amm::quote_exact_in`
+/// wrapped in `QuoteResult::new`, ...). Returns `None` when the pool
+/// cannot fill the trade.
+pub type SimQuoteFn<'a> = &'a dyn Fn(u64) -> Option<QuoteResult>;
+
+/// One leg of a route to simulate
+pub struct SimLeg<'a> {
+    pub dex: Dex,
+    pub quote: SimQuoteFn<'a>,
+}
+
+/// Simulated outcome of one leg
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LegSim {
+    pub dex: Dex,
+    /// Previous leg's output, or the route input for the first leg
+    pub amount_in: u64,
+    pub quote: QuoteResult,
+}
+
+/// Simulated outcome of a whole route
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimResult<const N: usize> {
+    /// Output of the last leg
+    pub total_out: u64,
+    /// Highest `price_impact_bps` of any leg
+    pub worst_impact_bps: u64,
+    pub legs: [LegSim; N],
+}
+
+/// Quote `legs` in order without any CPI, feeding each output into the next
+///
+/// Use it to compare and size routes before building the `Leg`s for
+/// `execute_legs`; each leg's `quote.min_out_after_slippage` is a ready
+/// `min_out`. Returns `None` if any leg cannot quote or outputs zero.
+pub fn simulate_route<const N: usize>(legs: &[SimLeg<'_>; N], amount_in: u64) -> Option<SimResult<N>> {
+    let mut amount = amount_in;
+    let mut failed = false;
+    let sims = core::array::from_fn(|i| {
+        let leg = &legs[i];
+        let quote = if failed { None } else { (leg.quote)(amount).filter(|quote| quote.amount_out > 0) };
+        let sim = LegSim { dex: leg.dex, amount_in: amount, quote: quote.unwrap_or_default() };
+        match quote {
+            Some(quote) => amount = quote.amount_out,
+            None => failed = true,
+        }
+        sim
+    });
+    if failed {
+        return None;
+    }
+    Some(SimResult {
+        total_out: amount,
+        worst_impact_bps: sims.iter().map(|sim: &LegSim| sim.quote.price_impact_bps).max().unwrap_or(0),
+        legs: sims,
+    })
+}
+
+// ============================================
+// SOL Wrapping
+// ============================================
+
+/// Accounts for `swap_with_sol_wrapping`
+pub struct SolWrapAccounts<'a> {
+    /// Funds the wrap and receives every lamport back on close (writable, signer)
+    pub owner: &'a AccountView,
+    /// Uninitialized temporary WSOL account (writable, signer: keypair or PDA)
+    pub wsol_account: &'a AccountView,
+    /// `common::NATIVE_MINT`
+    pub native_mint: &'a AccountView,
+    /// `common::SYSTEM_PROGRAM_ID`
+    pub system_program: &'a AccountView,
+    /// `common::TOKEN_PROGRAM_ID`
+    pub token_program: &'a AccountView,
+}
+
+/// Wrap `amount` lamports, run `swap` with the WSOL account, then unwrap
+///
+/// Creates `wsol_account` holding `rent_lamports + amount` (`rent_lamports`
+/// being the rent-exempt minimum for a 165-byte account), initializes it
+/// for the native mint owned by `owner`, syncs it, and hands it to `swap`
+/// as the WSOL source (or destination, with `amount = Lamports(0)`); the
+/// WSOL balance it sees is `amount.as_wsol()`. Closing returns
+/// the rent, any unspent input and any WSOL received to `owner` as SOL.
+/// `signers` must cover both `owner` and `wsol_account` when they are PDAs.
+/// Fails with `IncorrectProgramId` if `system_program` or `token_program`
+/// is not the expected program.
+pub fn swap_with_sol_wrapping<'a, F>(
+    accounts: &SolWrapAccounts<'a>,
+    amount: Lamports,
+    rent_lamports: Lamports,
+    signers: &[Signer<'_, '_>],
+    swap: F,
+) -> ProgramResult
+where
+    F: FnOnce(&'a AccountView) -> ProgramResult,
+{
+    if accounts.system_program.address() != &SYSTEM_PROGRAM_ID
+        || accounts.token_program.address() != &TOKEN_PROGRAM_ID
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let lamports = rent_lamports.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?.get();
+
+    // System: CreateAccount { lamports, space, owner }
+    let mut data = [0u8; 52];
+    data[4..12].copy_from_slice(&lamports.to_le_bytes());
+    data[12..20].copy_from_slice(&(TokenLayout::ACCOUNT_SIZE as u64).to_le_bytes());
+    data[20..52].copy_from_slice(TOKEN_PROGRAM_ID.as_array());
+    let instruction = InstructionView {
+        program_id: accounts.system_program.address(),
+        accounts: &[
+            InstructionAccount::writable_signer(accounts.owner.address()),
+            InstructionAccount::writable_signer(accounts.wsol_account.address()),
+        ],
+        data: &data,
+    };
+    invoke_signed::<2>(&instruction, &[accounts.owner, accounts.wsol_account], signers)?;
+
+    // Token: InitializeAccount3 { owner }
+    let mut data = [0u8; 33];
+    data[0] = 18;
+    data[1..33].copy_from_slice(accounts.owner.address().as_array());
+    let instruction = InstructionView {
+        program_id: accounts.token_program.address(),
+        accounts: &[
+            InstructionAccount::writable(accounts.wsol_account.address()),
+            InstructionAccount::readonly(accounts.native_mint.address()),
+        ],
+        data: &data,
+    };
+    invoke_signed::<2>(&instruction, &[accounts.wsol_account, accounts.native_mint], signers)?;
+
+    // Token: SyncNative
+    let instruction = InstructionView {
+        program_id: accounts.token_program.address(),
+        accounts: &[InstructionAccount::writable(accounts.wsol_account.address())],
+        data: &[17],
+    };
+    invoke_signed::<1>(&instruction, &[accounts.wsol_account], signers)?;
+
+    swap(accounts.wsol_account)?;
+
+    // Token: CloseAccount, lamports back to the owner
+    let instruction = InstructionView {
+        program_id: accounts.token_program.address(),
+        accounts: &[
+            InstructionAccount::writable(accounts.wsol_account.address()),
+            InstructionAccount::writable(accounts.owner.address()),
+            InstructionAccount::readonly_signer(accounts.owner.address()),
+        ],
+        data: &[9],
+    };
+    invoke_signed::<3>(&instruction, &[accounts.wsol_account, accounts.owner, accounts.owner], signers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{MockAccount, mock_account_views};
+    use pinocchio::account::RuntimeAccount;
+
+    #[test]
+    fn test_best_quote() {
+        assert_eq!(best_quote(&[]), None);
+
+        let quotes = [
+            (Dex::RaydiumAmm, 980),
+            (Dex::Whirlpool, 1_010),
+            (Dex::SolfiV2, 1_010),
+            (Dex::MeteoraDlmm, 995),
+        ];
+        // Tie between Whirlpool and SolFi: first one wins
+        assert_eq!(best_quote(&quotes), Some((Dex::Whirlpool, 1_010)));
+    }
+
+    #[test]
+    fn test_best_venue() {
+        let usdc = [1u8; 32];
+        let sol = [2u8; 32];
+        let amm = |amount_in: u64, _: &[u8; 32], _: &[u8; 32]| Some(amount_in * 99 / 100);
+        let clmm = |amount_in: u64, _: &[u8; 32], _: &[u8; 32]| Some(amount_in * 995 / 1000);
+        let unsupported = |_: u64, _: &[u8; 32], _: &[u8; 32]| None;
+
+        let venues: [(Dex, QuoteFn); 3] = [
+            (Dex::RaydiumAmm, &amm),
+            (Dex::Vertigo, &unsupported),
+            (Dex::RaydiumClmm, &clmm),
+        ];
+        assert_eq!(
+            best_venue(&venues, 1_000_000, &usdc, &sol),
+            Some((Dex::RaydiumClmm, 995_000))
+        );
+        assert_eq!(best_venue(&venues[1..2], 1_000_000, &usdc, &sol), None);
+    }
+
+    #[test]
+    fn test_quote_cache_freshness() {
+        let pool_a = [1u8; 32];
+        let pool_b = [2u8; 32];
+        let mut cache = QuoteCache::<u64, 2>::new();
+        cache.insert(Dex::RaydiumAmm, &pool_a, 100, 1_000);
+
+        assert_eq!(cache.get_if_fresh(Dex::RaydiumAmm, &pool_a, 100, 0), Some(1_000));
+        assert_eq!(cache.get_if_fresh(Dex::RaydiumAmm, &pool_a, 101, 0), None);
+        assert_eq!(cache.get_if_fresh(Dex::RaydiumAmm, &pool_a, 102, 2), Some(1_000));
+        assert_eq!(cache.get_if_fresh(Dex::RaydiumAmm, &pool_a, 103, 2), None);
+        // Same pool under another program, and values from a later slot
+        assert_eq!(cache.get_if_fresh(Dex::RaydiumCp, &pool_a, 100, 0), None);
+        assert_eq!(cache.get_if_fresh(Dex::RaydiumAmm, &pool_a, 99, 10), None);
+
+        // Re-quoting a stale entry overwrites it in place
+        assert_eq!(cache.get_or_quote(Dex::RaydiumAmm, &pool_a, 105, 0, || Some(1_100)), Some(1_100));
+        assert_eq!(cache.get_or_quote(Dex::RaydiumAmm, &pool_a, 105, 0, || unreachable!()), Some(1_100));
+        assert_eq!(cache.get_or_quote(Dex::Whirlpool, &pool_b, 105, 0, || None), None);
+
+        // Full: the oldest entry is evicted
+        cache.insert(Dex::Whirlpool, &pool_b, 105, 7);
+        cache.insert(Dex::SolfiV2, &pool_b, 105, 8);
+        assert_eq!(cache.get_if_fresh(Dex::RaydiumAmm, &pool_a, 105, 0), None);
+        assert_eq!(cache.get_if_fresh(Dex::Whirlpool, &pool_b, 105, 0), Some(7));
+        assert_eq!(cache.get_if_fresh(Dex::SolfiV2, &pool_b, 105, 0), Some(8));
+    }
+
+    #[test]
+    fn test_simulate_route() {
+        use crate::solfi_v2::{self, LatencyDecay, MarketFees, SwapSide, MARKET_TYPE_FC, MARKET_TYPE_FF};
+
+        let fees = MarketFees { constant_product_bps: 30, pegged_bps: 1 };
+        let decay = LatencyDecay { half_life_slots: 10, max_penalty_bps: 500 };
+        let (mut volatile, mut pegged) = ([0u8; 200], [0u8; 200]);
+        volatile[0] = MARKET_TYPE_FF;
+        pegged[0] = MARKET_TYPE_FC;
+        // 1 SOL -> USDC on a 100 SOL / 15,000 USDC market, then USDC -> USDT at peg
+        let sol_usdc = |amount_in| {
+            solfi_v2::quote_with_latency(&volatile, fees, decay, (100_000_000_000, 15_000_000_000), amount_in, SwapSide::Sell, 0)
+        };
+        let usdc_usdt = |amount_in| {
+            solfi_v2::quote_with_latency(&pegged, fees, decay, (2_000_000_000, 2_000_000_000), amount_in, SwapSide::Buy, 0)
+        };
+        let legs = [SimLeg { dex: Dex::SolfiV2, quote: &sol_usdc }, SimLeg { dex: Dex::SolfiV2, quote: &usdc_usdt }];
+
+        let sim = simulate_route(&legs, 1_000_000_000).unwrap();
+        assert_eq!(sim.legs[0].amount_in, 1_000_000_000);
+        assert_eq!(sim.legs[0].quote.amount_out, 148_073_705);
+        assert_eq!(sim.legs[1].amount_in, 148_073_705);
+        // 1 bp on the pegged leg
+        assert_eq!(sim.total_out, 148_058_897);
+        assert_eq!(sim.legs[1].quote.amount_out, sim.total_out);
+        // The SOL leg moves its pool 1%, the stable leg not at all
+        assert_eq!(sim.worst_impact_bps, 100);
+
+        // A leg that cannot fill sinks the route
+        let empty = |_| None;
+        assert!(simulate_route(&[SimLeg { dex: Dex::SolfiV2, quote: &sol_usdc }, SimLeg { dex: Dex::Vertigo, quote: &empty }], 1_000).is_none());
+        assert!(simulate_route(&legs, 0).is_none());
+        assert_eq!(simulate_route(&[], 5).map(|sim| (sim.total_out, sim.worst_impact_bps)), Some((5, 0)));
+    }
+
+    /// Credits `amount_in * rate / 100` to the first account, like a swap into it
+    fn credit(accounts: &[AccountView], amount_in: TokenAmount, rate: u64) -> ProgramResult {
+        let mut data = accounts[0].try_borrow_mut()?;
+        let balance = u64::from_le_bytes(data[64..72].try_into().unwrap());
+        data[64..72].copy_from_slice(&(balance + amount_in.get() * rate / 100).to_le_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_legs() {
+        let mut usdc = MockAccount::<165>::new([1; 32]);
+        let mut sol = MockAccount::<165>::new([2; 32]);
+        usdc.data[64..72].copy_from_slice(&500u64.to_le_bytes());
+        let usdc = [usdc.view()];
+        let sol = [sol.view()];
+
+        let to_usdc = |accounts: &[AccountView], amount_in, _| credit(accounts, amount_in, 150);
+        let to_sol = |accounts: &[AccountView], amount_in, _| credit(accounts, amount_in, 1);
+        let first = || Leg {
+            dex: Dex::RaydiumAmm,
+            swap: &to_usdc,
+            accounts: &usdc,
+            destination: &usdc[0],
+            amount_in: TokenAmount(1_000),
+            min_out: TokenAmount(1_500),
+        };
+        let second = |min_out| Leg {
+            dex: Dex::Whirlpool,
+            swap: &to_sol,
+            accounts: &sol,
+            destination: &sol[0],
+            amount_in: TokenAmount(1_500),
+            min_out: TokenAmount(min_out),
+        };
+
+        // 1_000 SOL -> 1_500 USDC -> 15 SOL; the delta excludes the 500 USDC already held
+        assert_eq!(execute_legs(&[first(), second(15)]), Ok(TokenAmount(15)));
+        assert_eq!(execute_legs(&[]), Ok(TokenAmount(0)));
+
+        // Second leg under-delivers: the route aborts after the first leg ran
+        assert_eq!(execute_legs(&[first(), second(16)]), Err(CpiError::SlippageExceeded.into()));
+        assert_eq!(token_balance(&usdc[0]), Ok(TokenAmount(3_500)));
+    }
+
+    #[test]
+    fn test_swap_checked() {
+        let mut usdc = MockAccount::<165>::new([1; 32]);
+        usdc.data[64..72].copy_from_slice(&500u64.to_le_bytes());
+        let usdc = [usdc.view()];
+
+        // Delivers 1_500 on top of the 500 already held
+        assert_eq!(swap_checked(&usdc[0], TokenAmount(1_500), || credit(&usdc, TokenAmount(1_000), 150)), Ok(TokenAmount(1_500)));
+        assert_eq!(token_balance(&usdc[0]), Ok(TokenAmount(2_000)));
+
+        // The program reports success but delivers one unit short
+        let short = swap_checked(&usdc[0], TokenAmount(1_500), || credit(&usdc, TokenAmount(1_000), 149));
+        assert_eq!(short, Err(CpiError::SlippageExceeded.into()));
+        assert_eq!(swap_checked(&usdc[0], TokenAmount(1), || Ok(())), Err(CpiError::SlippageExceeded.into()));
+        assert_eq!(swap_checked(&usdc[0], TokenAmount(0), || Ok(())), Ok(TokenAmount(0)));
+
+        // CPI errors pass through; non-token destinations are rejected
+        assert_eq!(swap_checked(&usdc[0], TokenAmount(0), || Err(ProgramError::Custom(6001))), Err(ProgramError::Custom(6001)));
+        let mut raw: [RuntimeAccount; 1] = Default::default();
+        let v = mock_account_views(&mut raw);
+        assert_eq!(swap_checked(&v[0], TokenAmount(0), || unreachable!()), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_swap_with_sol_wrapping() {
+        let mut raw: [RuntimeAccount; 3] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let mut system_program = MockAccount::<0>::new(SYSTEM_PROGRAM_ID.to_bytes());
+        let mut token_program = MockAccount::<0>::new(TOKEN_PROGRAM_ID.to_bytes());
+        let (system_program, token_program) = (system_program.view(), token_program.view());
+        let accounts = SolWrapAccounts {
+            owner: &v[0],
+            wsol_account: &v[1],
+            native_mint: &v[2],
+            system_program: &system_program,
+            token_program: &token_program,
+        };
+
+        let mut source = None;
+        let result = swap_with_sol_wrapping(&accounts, Lamports(1_000_000), Lamports(2_039_280), &[], |wsol| {
+            source = Some(wsol.address().to_bytes());
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(source, Some([2; 32]));
+        // the close goes through the passed token program
+        let (program_id, data) = crate::common::last_cpi();
+        assert_eq!(program_id, TOKEN_PROGRAM_ID);
+        assert_eq!(data, [9]);
+
+        // a failing swap aborts before the close
+        let result = swap_with_sol_wrapping(&accounts, Lamports(1), Lamports(2_039_280), &[], |_| {
+            Err(ProgramError::Custom(6001))
+        });
+        assert_eq!(result, Err(ProgramError::Custom(6001)));
+
+        let result = swap_with_sol_wrapping(&accounts, Lamports(u64::MAX), Lamports(1), &[], |_| unreachable!());
+        assert_eq!(result, Err(ProgramError::ArithmeticOverflow));
+
+        // a program account that does not match is rejected before any CPI
+        let accounts = SolWrapAccounts { token_program: &v[2], ..accounts };
+        let result = swap_with_sol_wrapping(&accounts, Lamports(1), Lamports(2_039_280), &[], |_| unreachable!());
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+    }
+}
@@ -11,10 +11,12 @@ use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_pubkey, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("DecZY86MU5Gj7kppfUCEmd4LbXXuyZH1yHaP2NTqdiZB"));
 
@@ -285,3 +287,107 @@ pub fn withdraw<'a>(
     invoke_signed::<7>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Account State
+// ============================================
+
+/// Account discriminator for `WrappedToken`
+pub const WRAPPED_TOKEN_DISCRIMINATOR: [u8; 8] = [28, 41, 198, 163, 189, 149, 175, 142];
+
+/// Decoded `WrappedToken` (the `wrapper` account)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WrappedToken {
+    /// Decimals of `wrapper_mint`
+    pub decimals: u8,
+    /// `10^(decimals - underlying decimals)`
+    pub multiplier: u64,
+    pub wrapper_underlying_mint: [u8; 32],
+    pub wrapper_underlying_tokens: [u8; 32],
+    pub wrapper_mint: [u8; 32],
+}
+
+impl WrappedToken {
+    /// Size up to and including `wrapper_mint`
+    pub const MIN_SIZE: usize = 113;
+
+    /// Parse a `WrappedToken` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::MIN_SIZE || data[0..8] != WRAPPED_TOKEN_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            decimals: data[8],
+            multiplier: read_u64_le(data, 9)?,
+            wrapper_underlying_mint: read_pubkey(data, 17)?,
+            wrapper_underlying_tokens: read_pubkey(data, 49)?,
+            wrapper_mint: read_pubkey(data, 81)?,
+        })
+    }
+
+    /// Wrapped tokens minted by `deposit` of `amount` underlying
+    ///
+    /// Value is unchanged, only the decimals grow: 1 USDC (6 decimals) in a
+    /// 9-decimal wrapper is `1_000_000 * 1_000`. `None` on overflow.
+    #[inline(always)]
+    pub const fn quote_deposit(&self, amount: u64) -> Option<u64> {
+        amount.checked_mul(self.multiplier)
+    }
+
+    /// Underlying tokens returned by `withdraw` with `max_burn_amount`
+    ///
+    /// Rounds down to whole underlying units: a `max_burn_amount` that is not
+    /// a multiple of `multiplier` returns the floor, and anything below one
+    /// underlying unit returns zero. Zero for a zero `multiplier`.
+    #[inline(always)]
+    pub const fn quote_withdraw(&self, max_burn_amount: u64) -> u64 {
+        match max_burn_amount.checked_div(self.multiplier) {
+            Some(out) => out,
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrapper_fixture(multiplier: u64) -> WrappedToken {
+        let mut data = [0u8; WrappedToken::MIN_SIZE];
+        data[0..8].copy_from_slice(&WRAPPED_TOKEN_DISCRIMINATOR);
+        data[8] = 9;
+        data[9..17].copy_from_slice(&multiplier.to_le_bytes());
+        data[17..49].copy_from_slice(&[1; 32]);
+        data[81..113].copy_from_slice(&[3; 32]);
+        WrappedToken::from_bytes(&data).unwrap()
+    }
+
+    #[test]
+    fn test_parse_wrapped_token() {
+        assert_eq!(WRAPPED_TOKEN_DISCRIMINATOR, crate::common::hash::anchor_discriminator("account", "WrappedToken"));
+        let wrapper = wrapper_fixture(1_000);
+        assert_eq!((wrapper.decimals, wrapper.multiplier), (9, 1_000));
+        assert_eq!((wrapper.wrapper_underlying_mint, wrapper.wrapper_mint), ([1; 32], [3; 32]));
+        assert!(WrappedToken::from_bytes(&[0u8; WrappedToken::MIN_SIZE]).is_none());
+    }
+
+    #[test]
+    fn test_quote_deposit_withdraw() {
+        // 6 -> 9 decimals
+        let wrapper = wrapper_fixture(1_000);
+        assert_eq!(wrapper.quote_deposit(1_500_000), Some(1_500_000_000));
+        assert_eq!(wrapper.quote_withdraw(1_500_000_000), 1_500_000);
+        assert_eq!(wrapper.quote_withdraw(1_500_000_999), 1_500_000);
+        assert_eq!(wrapper.quote_withdraw(999), 0);
+        assert_eq!(wrapper.quote_deposit(u64::MAX), None);
+
+        // 8 -> 9 decimals
+        let wrapper = wrapper_fixture(10);
+        assert_eq!(wrapper.quote_deposit(12_345), Some(123_450));
+        assert_eq!(wrapper.quote_withdraw(123_459), 12_345);
+
+        // Same decimals
+        let wrapper = wrapper_fixture(1);
+        assert_eq!(wrapper.quote_deposit(7), Some(7));
+        assert_eq!(wrapper.quote_withdraw(7), 7);
+    }
+}
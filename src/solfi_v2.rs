@@ -16,7 +16,8 @@
 //! ### Slot Validation
 //! - Error 0x17: Triggered when account data is stale
 //! - Custom(23): Triggered when slot delay exceeds threshold (oracle expired)
-//! - Pricing is slot-dependent with non-linear decay (quadratic/exponential)
+//! - Pricing is slot-dependent with a non-linear decay; `quote_with_latency`
+//!   models it with `common::decay::exp_penalty_bps`
 //! - Higher latency = higher slippage penalty
 //!
 //! ### CU Consumption
@@ -33,10 +34,13 @@
 
 use pinocchio::{
     AccountView, Address, ProgramResult,
-    cpi::{invoke_signed, Signer},
+    cpi::{Signer},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{AccountRole, QuoteResult, NATIVE_MINT, invoke_signed, price_impact_bps, read_u64_le};
+use crate::common::decay::exp_penalty_bps;
+
 // ============================================
 // Constants
 // ============================================
@@ -130,6 +134,46 @@ pub const POOL_ZENZEC_USDC: Address = Address::new_from_array(
     five8_const::decode_32_const("7TKsqWxU9QkPYVLdjjR1V67ky3FnYogjntUpNLexib4E")
 );
 
+// ============================================
+// Pool Discovery
+// ============================================
+
+/// USDC mint, quote of every known pool
+pub const USDC_MINT: Address = Address::new_from_array(
+    five8_const::decode_32_const("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")
+);
+
+/// USDT mint
+pub const USDT_MINT: Address = Address::new_from_array(
+    five8_const::decode_32_const("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB")
+);
+
+/// PUMP mint
+pub const PUMP_MINT: Address = Address::new_from_array(
+    five8_const::decode_32_const("pumpCmXqMfrsAkQ5r49WcJnRayYRqmXz6ae8H7H9Dfn")
+);
+
+/// Known pool for a `(base_mint, quote_mint)` pair
+///
+/// Mints must be given in pool order (base first). Only pairs whose mints
+/// are verified here are mapped; the ZEC, MON, HYPE and zenZEC pools use
+/// bridged mints that are not recorded in this crate, so reference their
+/// `POOL_*` consts directly (or read the mints from `MarketStateLayout`).
+pub fn pool_for_pair(base_mint: &[u8; 32], quote_mint: &[u8; 32]) -> Option<Address> {
+    if *quote_mint != USDC_MINT.to_bytes() {
+        return None;
+    }
+    if *base_mint == NATIVE_MINT.to_bytes() {
+        Some(POOL_WSOL_USDC)
+    } else if *base_mint == PUMP_MINT.to_bytes() {
+        Some(POOL_PUMP_USDC)
+    } else if *base_mint == USDT_MINT.to_bytes() {
+        Some(POOL_USDT_USDC)
+    } else {
+        None
+    }
+}
+
 // ============================================
 // Swap Side Enum
 // ============================================
@@ -214,6 +258,23 @@ pub struct SwapAccounts<'a> {
     pub sysvar_instructions: &'a AccountView,
 }
 
+/// Account spec of `swap`, matching `SwapAccounts::to_instruction_accounts`
+pub const SWAP_ACCOUNT_SPEC: [AccountRole; SWAP_ACCOUNTS_COUNT] = [
+    AccountRole::writable("market_state"),
+    AccountRole::readonly("authority"),
+    AccountRole::writable("base_vault"),
+    AccountRole::writable("quote_vault"),
+    AccountRole::writable("user_base_account"),
+    AccountRole::writable("user_quote_account"),
+    AccountRole::writable("fee_receiver"),
+    AccountRole::readonly("referral_account"),
+    AccountRole::readonly("base_mint"),
+    AccountRole::readonly("quote_mint"),
+    AccountRole::readonly("token_program"),
+    AccountRole::readonly("token_program_2"),
+    AccountRole::readonly("sysvar_instructions"),
+];
+
 impl<'a> SwapAccounts<'a> {
     /// Convert to instruction accounts array
     #[inline(always)]
@@ -354,6 +415,11 @@ impl SwapArgs {
 /// ```ignore
 /// let args = SwapArgs::buy(1_000_000, 0); // Buy with 1 USDC, no slippage protection
 /// swap(&accounts, &args, &[])?;
+///
+/// // User token accounts owned by this program's PDA: the market authority
+/// // is SolFi's own PDA, so only the user side signs
+/// pda_signer!(signer = [b"vault", market.address().as_ref()], vault_bump);
+/// swap(&accounts, &args, &[signer])?;
 /// ```
 #[inline(always)]
 pub fn swap<'a>(
@@ -496,13 +562,7 @@ pub fn is_solfi_v2_program(program_id: &Address) -> bool {
 /// - [64..72] amount (u64)
 #[inline(always)]
 pub fn parse_token_account_balance(data: &[u8]) -> Option<u64> {
-    if data.len() < 72 {
-        return None;
-    }
-    
-    Some(u64::from_le_bytes(
-        data[64..72].try_into().ok()?
-    ))
+    read_u64_le(data, 64)
 }
 
 /// Get pool reserves from vault accounts
@@ -573,9 +633,214 @@ pub fn calculate_output_with_fee(
     (numerator / denominator) as u64
 }
 
+// ============================================
+// Quote
+// ============================================
+
+/// Pricing model and fee a quote assumes
+///
+/// Picked from the market type by `PricingModel::for_market`. `Pegged`
+/// approximates the same-decimal stablecoin market (0xFC); it is not
+/// documented program behaviour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PricingModel {
+    /// x * y = k on vault balances
+    ConstantProduct { fee_bps: u64 },
+    /// 1:1 peg between same-decimal stablecoins
+    Pegged { fee_bps: u64 },
+}
+
+/// Fees assumed per pricing model, in basis points
+///
+/// SolFi V2 does not publish its fees and the known market state layout has
+/// no fee field, so the caller supplies them, e.g. fitted to its own fills.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarketFees {
+    /// Fee on the constant-product markets (0xFF, 0xFE, 0xFD)
+    pub constant_product_bps: u64,
+    /// Fee on the pegged stable market (0xFC)
+    pub pegged_bps: u64,
+}
+
+impl PricingModel {
+    /// Model for a market state account, read from its type byte
+    ///
+    /// Returns `None` for an empty account or an unknown market type.
+    #[inline(always)]
+    pub fn for_market(market_state: &[u8], fees: MarketFees) -> Option<Self> {
+        match parse_market_type(market_state).filter(|t| is_valid_market_type(*t))? {
+            MARKET_TYPE_FC => Some(Self::Pegged { fee_bps: fees.pegged_bps }),
+            _ => Some(Self::ConstantProduct { fee_bps: fees.constant_product_bps }),
+        }
+    }
+}
+
+/// Estimate swap output, dispatching on the market type
+///
+/// Returns `None` for an unknown market type or unreadable vaults. Ignores
+/// the slot-dependent latency penalty, so treat the result as an upper
+/// bound (`quote_with_latency` includes it); `slippage_bps` only sets
+/// `min_out_after_slippage`.
+#[inline(always)]
+pub fn quote(
+    market_state: &[u8],
+    fees: MarketFees,
+    base_vault_data: &[u8],
+    quote_vault_data: &[u8],
+    amount_in: u64,
+    side: SwapSide,
+    slippage_bps: u64,
+) -> Option<QuoteResult> {
+    quote_with_referral_fee(market_state, fees, base_vault_data, quote_vault_data, amount_in, side, 0, slippage_bps)
+}
+
+/// Estimate swap output net of a referral fee taken from the output
+///
+/// The referral rate is not part of the verified market state layout
+/// (only the fields in `MarketStateLayout` are known), so the caller
+/// supplies the rate agreed for its `referral_account`. The fee is rounded
+/// up so the estimate never overstates what the user receives, and is
+/// included in `fee_paid`.
+pub fn quote_with_referral_fee(
+    market_state: &[u8],
+    fees: MarketFees,
+    base_vault_data: &[u8],
+    quote_vault_data: &[u8],
+    amount_in: u64,
+    side: SwapSide,
+    referral_fee_bps: u64,
+    slippage_bps: u64,
+) -> Option<QuoteResult> {
+    let model = PricingModel::for_market(market_state, fees)?;
+    let reserves = get_pool_reserves(base_vault_data, quote_vault_data)?;
+    Some(quote_reserves(model, reserves, amount_in, side, referral_fee_bps, slippage_bps))
+}
+
+/// Quote on `(base_reserve, quote_reserve)`, then take `output_cut_bps` of
+/// the output (rounded up) and count it in `fee_paid`
+fn quote_reserves(
+    model: PricingModel,
+    (base_reserve, quote_reserve): (u64, u64),
+    amount_in: u64,
+    side: SwapSide,
+    output_cut_bps: u64,
+    slippage_bps: u64,
+) -> QuoteResult {
+    let (reserve_in, reserve_out) = match side {
+        SwapSide::Buy => (quote_reserve, base_reserve),
+        SwapSide::Sell => (base_reserve, quote_reserve),
+    };
+
+    // (output before fees, output after pool fee, price impact)
+    let (gross, out, impact) = match model {
+        PricingModel::ConstantProduct { fee_bps } => (
+            calculate_output_amount(amount_in, reserve_in, reserve_out),
+            calculate_output_with_fee(amount_in, reserve_in, reserve_out, fee_bps),
+            price_impact_bps(amount_in, reserve_in),
+        ),
+        PricingModel::Pegged { fee_bps } => {
+            // 1:1 until the output vault runs dry
+            let gross = amount_in.min(reserve_out);
+            let out = amount_in as u128 * 10000u64.saturating_sub(fee_bps) as u128 / 10000;
+            let shortfall = (amount_in - gross) as u128;
+            let impact = if amount_in == 0 { 0 } else { (shortfall * 10000).div_ceil(amount_in as u128) as u64 };
+            (gross, (out as u64).min(reserve_out), impact)
+        }
+    };
+    let cut = (out as u128 * output_cut_bps.min(10000) as u128).div_ceil(10000) as u64;
+    let amount_out = out - cut;
+    QuoteResult::new(amount_out, gross - amount_out, impact, slippage_bps)
+}
+
+// ============================================
+// Latency
+// ============================================
+
+/// Slot-delay penalty, supplied by the caller
+///
+/// SolFi V2 worsens its price the older the market state is relative to the
+/// current slot, and fails with `ERROR_ORACLE_EXPIRED` past a threshold.
+/// Neither the curve nor the threshold is published, so the penalty is
+/// `common::decay::exp_penalty_bps` with parameters the caller fits to
+/// observed fills; a delay is never rejected on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LatencyDecay {
+    /// Delay, in slots, that costs half of `max_penalty_bps`
+    pub half_life_slots: u64,
+    /// Penalty approached as the delay grows
+    pub max_penalty_bps: u64,
+}
+
+impl LatencyDecay {
+    /// Penalty in basis points for market state `slot_delay` slots old
+    #[inline(always)]
+    pub fn penalty_bps(&self, slot_delay: u64) -> u64 {
+        exp_penalty_bps(slot_delay, self.half_life_slots, self.max_penalty_bps)
+    }
+}
+
+/// Estimate swap output including the slot-delay penalty
+///
+/// The model comes from `market_state` as in `quote`. `reserves` is
+/// `(base_reserve, quote_reserve)` as returned by `get_pool_reserves`, and
+/// `slot_delay` the current slot minus the slot the market state was last
+/// updated at. The penalty is taken from the pool's output and included in
+/// `fee_paid`; `min_out_after_slippage` equals `amount_out`. Returns `None`
+/// for an unknown market type.
+pub fn quote_with_latency(
+    market_state: &[u8],
+    fees: MarketFees,
+    decay: LatencyDecay,
+    reserves: (u64, u64),
+    amount_in: u64,
+    side: SwapSide,
+    slot_delay: u64,
+) -> Option<QuoteResult> {
+    let model = PricingModel::for_market(market_state, fees)?;
+    Some(quote_reserves(model, reserves, amount_in, side, decay.penalty_bps(slot_delay), 0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::{Dex, Op, account_spec, dump_account_roles, matches_account_spec, mock_account_views, signer_seeds};
+    use pinocchio::account::RuntimeAccount;
+    
+    #[test]
+    fn test_swap_account_roles() {
+        let mut raw: [RuntimeAccount; SWAP_ACCOUNTS_COUNT] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let accounts = SwapAccounts {
+            market_state: &v[0],
+            authority: &v[1],
+            base_vault: &v[2],
+            quote_vault: &v[3],
+            user_base_account: &v[4],
+            user_quote_account: &v[5],
+            fee_receiver: &v[6],
+            referral_account: &v[7],
+            base_mint: &v[8],
+            quote_mint: &v[9],
+            token_program: &v[10],
+            token_program_2: &v[11],
+            sysvar_instructions: &v[12],
+        };
+        // The market authority PDA must stay readonly
+        assert_eq!(
+            dump_account_roles(&accounts.to_instruction_accounts()),
+            "w r w w w w w r r r r r r"
+        );
+        assert!(matches_account_spec(&accounts.to_instruction_accounts(), &SWAP_ACCOUNT_SPEC));
+        assert_eq!(account_spec(Dex::SolfiV2, Op::Swap), &SWAP_ACCOUNT_SPEC);
+
+        // Program-owned user accounts sign with a PDA over the market
+        crate::common::pda_signer!(signer = [b"vault", accounts.market_state.address().as_ref()], 255);
+        let seeds = signer_seeds(&signer);
+        assert_eq!(seeds.len(), 3);
+        assert_eq!(&*seeds[0], b"vault");
+        assert_eq!(&*seeds[1], &[1u8; 32]);
+        assert_eq!(&*seeds[2], &[255]);
+    }
     
     #[test]
     fn test_swap_data_serialization() {
@@ -604,4 +869,177 @@ mod tests {
         let output = calculate_output_amount(1000, 10000, 10000);
         assert_eq!(output, 909);
     }
+
+    fn vault(amount: u64) -> [u8; 165] {
+        let mut data = [0u8; 165];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    fn market(market_type: u8) -> [u8; MarketStateLayout::MIN_SIZE] {
+        let mut data = [0u8; MarketStateLayout::MIN_SIZE];
+        data[MarketStateLayout::MARKET_TYPE_OFFSET] = market_type;
+        data
+    }
+
+    const FEES: MarketFees = MarketFees { constant_product_bps: 30, pegged_bps: 1 };
+    const DECAY: LatencyDecay = LatencyDecay { half_life_slots: 10, max_penalty_bps: 500 };
+
+    #[test]
+    fn test_pricing_model_for_market() {
+        for market_type in [MARKET_TYPE_FF, MARKET_TYPE_FE, MARKET_TYPE_FD] {
+            assert_eq!(
+                PricingModel::for_market(&market(market_type), FEES),
+                Some(PricingModel::ConstantProduct { fee_bps: 30 })
+            );
+        }
+        assert_eq!(PricingModel::for_market(&market(MARKET_TYPE_FC), FEES), Some(PricingModel::Pegged { fee_bps: 1 }));
+        assert_eq!(PricingModel::for_market(&market(0x00), FEES), None);
+        assert_eq!(PricingModel::for_market(&market(0xFB), FEES), None);
+        assert_eq!(PricingModel::for_market(&[], FEES), None);
+    }
+
+    fn assert_constant_product(market_type: u8) {
+        // 100 SOL base / 15,000 USDC quote
+        let (market, base, quote_vault) = (market(market_type), vault(100_000_000_000), vault(15_000_000_000));
+        for fee_bps in [0, 30, 100] {
+            let fees = MarketFees { constant_product_bps: fee_bps, pegged_bps: 1 };
+            let q = quote(&market, fees, &base, &quote_vault, 1_000_000_000, SwapSide::Sell, 0).unwrap();
+            assert_eq!(q.amount_out, calculate_output_with_fee(1_000_000_000, 100_000_000_000, 15_000_000_000, fee_bps));
+            let q = quote(&market, fees, &base, &quote_vault, 150_000_000, SwapSide::Buy, 0).unwrap();
+            assert_eq!(q.amount_out, calculate_output_with_fee(150_000_000, 15_000_000_000, 100_000_000_000, fee_bps));
+        }
+    }
+
+    #[test]
+    fn test_quote_market_type_ff() {
+        assert_constant_product(MARKET_TYPE_FF);
+    }
+
+    #[test]
+    fn test_quote_market_type_fe() {
+        assert_constant_product(MARKET_TYPE_FE);
+    }
+
+    #[test]
+    fn test_quote_market_type_fd() {
+        assert_constant_product(MARKET_TYPE_FD);
+    }
+
+    #[test]
+    fn test_quote_market_type_fc() {
+        let (market, usdt, usdc) = (market(MARKET_TYPE_FC), vault(2_000_000_000), vault(500_000));
+        // 1,000 USDC -> USDT at 1 bp
+        let q = quote(&market, FEES, &usdt, &usdc, 1_000_000_000, SwapSide::Buy, 10).unwrap();
+        assert_eq!(q, QuoteResult {
+            amount_out: 999_900_000,
+            fee_paid: 100_000,
+            price_impact_bps: 0,
+            min_out_after_slippage: 998_900_100,
+        });
+        // Output capped by the USDC vault
+        let q = quote(&market, FEES, &usdt, &usdc, 1_000_000_000, SwapSide::Sell, 0).unwrap();
+        assert_eq!((q.amount_out, q.fee_paid, q.price_impact_bps), (500_000, 0, 9_995));
+    }
+
+    #[test]
+    fn test_quote_rejects_unknown_market_type() {
+        let (base, quote_vault) = (vault(100_000_000_000), vault(15_000_000_000));
+        assert_eq!(quote(&market(0x00), FEES, &base, &quote_vault, 1_000, SwapSide::Sell, 0), None);
+        assert_eq!(quote(&[], FEES, &base, &quote_vault, 1_000, SwapSide::Sell, 0), None);
+        let reserves = (100_000_000_000, 15_000_000_000);
+        assert_eq!(quote_with_latency(&market(0x00), FEES, DECAY, reserves, 1_000, SwapSide::Sell, 0), None);
+    }
+
+    #[test]
+    fn test_quote_result_fields() {
+        let (market, base, quote_vault) = (market(MARKET_TYPE_FF), vault(100_000_000_000), vault(15_000_000_000));
+        // 1 SOL of 100: ~1% impact, 30 bps fee, 50 bps slippage
+        let q = quote(&market, FEES, &base, &quote_vault, 1_000_000_000, SwapSide::Sell, 50).unwrap();
+        assert_eq!(q.amount_out, 148_073_705);
+        assert_eq!(q.fee_paid, 148_514_851 - 148_073_705);
+        assert_eq!(q.price_impact_bps, 100);
+        assert_eq!(q.min_out_after_slippage, 147_333_336);
+    }
+
+    #[test]
+    fn test_quote_with_referral_fee() {
+        let (ff, base, quote_vault) = (market(MARKET_TYPE_FF), vault(100_000_000_000), vault(15_000_000_000));
+        let gross = quote(&ff, FEES, &base, &quote_vault, 1_000_000_000, SwapSide::Sell, 0).unwrap();
+        assert_eq!(gross.amount_out, 148_073_705);
+
+        // 10 bps referral, rounded against the user and counted as fee
+        let net = quote_with_referral_fee(&ff, FEES, &base, &quote_vault, 1_000_000_000, SwapSide::Sell, 10, 0).unwrap();
+        assert_eq!(net.amount_out, 148_073_705 - 148_074);
+        assert_eq!(net.fee_paid, gross.fee_paid + 148_074);
+        assert_eq!(quote_with_referral_fee(&ff, FEES, &base, &quote_vault, 1_000_000_000, SwapSide::Sell, 0, 0), Some(gross));
+        let all = quote_with_referral_fee(&ff, FEES, &base, &quote_vault, 1_000_000_000, SwapSide::Sell, 20_000, 0);
+        assert_eq!(all.unwrap().amount_out, 0);
+
+        // Pegged market: 1,000 USDC at 1 bp, then 5 bps referral
+        let stable = vault(2_000_000_000);
+        let fc = market(MARKET_TYPE_FC);
+        let net = quote_with_referral_fee(&fc, FEES, &stable, &stable, 1_000_000_000, SwapSide::Buy, 5, 0).unwrap();
+        assert_eq!(net.amount_out, 999_900_000 - 499_950);
+    }
+
+    #[test]
+    fn test_pool_for_pair() {
+        let usdc = USDC_MINT.to_bytes();
+        assert_eq!(pool_for_pair(&NATIVE_MINT.to_bytes(), &usdc), Some(POOL_WSOL_USDC));
+        assert_eq!(pool_for_pair(&PUMP_MINT.to_bytes(), &usdc), Some(POOL_PUMP_USDC));
+        assert_eq!(pool_for_pair(&USDT_MINT.to_bytes(), &usdc), Some(POOL_USDT_USDC));
+
+        // Reversed order and unknown mints are not mapped
+        assert_eq!(pool_for_pair(&usdc, &NATIVE_MINT.to_bytes()), None);
+        assert_eq!(pool_for_pair(&[7u8; 32], &usdc), None);
+        assert_eq!(pool_for_pair(&usdc, &usdc), None);
+    }
+
+    #[test]
+    fn test_quote_rejects_unreadable_vaults() {
+        assert_eq!(quote(&market(MARKET_TYPE_FF), FEES, &[], &vault(1_000), 10, SwapSide::Sell, 0), None);
+        assert_eq!(quote(&market(MARKET_TYPE_FC), FEES, &vault(1_000), &[0u8; 64], 10, SwapSide::Sell, 0), None);
+    }
+
+    #[test]
+    fn test_quote_with_latency() {
+        let (ff, fc) = (market(MARKET_TYPE_FF), market(MARKET_TYPE_FC));
+        let reserves = (100_000_000_000, 15_000_000_000);
+        // No delay: same as the plain quote, for every market type
+        for market_type in [MARKET_TYPE_FF, MARKET_TYPE_FE, MARKET_TYPE_FD, MARKET_TYPE_FC] {
+            let m = market(market_type);
+            let fresh = quote_with_latency(&m, FEES, DECAY, reserves, 1_000_000_000, SwapSide::Sell, 0);
+            let plain = quote(&m, FEES, &vault(reserves.0), &vault(reserves.1), 1_000_000_000, SwapSide::Sell, 0);
+            assert_eq!(fresh, plain);
+        }
+
+        // Penalty follows exp_penalty_bps and is counted as fee
+        let fresh = quote_with_latency(&ff, FEES, DECAY, reserves, 1_000_000_000, SwapSide::Sell, 0).unwrap();
+        let mut prev = fresh;
+        for delay in [1, 5, 10, 25, 100] {
+            let q = quote_with_latency(&ff, FEES, DECAY, reserves, 1_000_000_000, SwapSide::Sell, delay).unwrap();
+            let penalty_bps = DECAY.penalty_bps(delay);
+            assert_eq!(penalty_bps, exp_penalty_bps(delay, 10, 500));
+            let penalty = (148_073_705u128 * penalty_bps as u128).div_ceil(10_000) as u64;
+            assert_eq!(q.amount_out, 148_073_705 - penalty);
+            assert_eq!(q.fee_paid, fresh.fee_paid + penalty);
+            assert_eq!(q.min_out_after_slippage, q.amount_out);
+            assert!(q.amount_out < prev.amount_out);
+            prev = q;
+        }
+        // One half-life costs half the maximum
+        let q = quote_with_latency(&ff, FEES, DECAY, reserves, 1_000_000_000, SwapSide::Sell, 10).unwrap();
+        assert_eq!(q.amount_out, 148_073_705 - 3_701_843);
+
+        // Stale data is never rejected outright
+        let q = quote_with_latency(&ff, FEES, DECAY, reserves, 1_000_000_000, SwapSide::Sell, 10_000).unwrap();
+        assert_eq!(q.amount_out, 148_073_705 - 7_403_686);
+
+        // The caller picks a slower decay for the pegged market
+        let stable = (2_000_000_000, 2_000_000_000);
+        let slow = LatencyDecay { half_life_slots: 20, max_penalty_bps: 10 };
+        let q = quote_with_latency(&fc, FEES, slow, stable, 1_000_000_000, SwapSide::Buy, 10).unwrap();
+        assert_eq!(q.amount_out, 999_900_000 - 199_980);
+    }
 }
@@ -5,16 +5,20 @@
 //! Instructions: 21
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::clmm_common::{self, TickRange};
+use crate::common::{OptionBool, invoke_signed};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("6dMXqGZ3ga2dikrYS9ovDXgHGh5RUsb2RTUj6hrQXhk6"));
 
@@ -104,7 +108,7 @@ pub struct IncreaseLiquidityV2Args {
     pub liquidity: u128,
     pub amount_0_max: u64,
     pub amount_1_max: u64,
-    pub base_flag: Option<bool>,
+    pub base_flag: OptionBool,
 }
 
 /// Arguments for `initialize_reward`
@@ -126,7 +130,7 @@ pub struct OpenPositionWithToken22NftArgs {
     pub amount_0_max: u64,
     pub amount_1_max: u64,
     pub with_metadata: bool,
-    pub base_flag: Option<bool>,
+    pub base_flag: OptionBool,
 }
 
 /// Arguments for `set_reward_params`
@@ -1781,3 +1785,152 @@ pub fn update_reward_infos<'a>(
     invoke_signed::<1>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Liquidity
+// ============================================
+//
+// Stabble's amm_v3 fork only keeps the Token-2022 NFT open and the v2
+// increase / decrease paths, so these wrap those. Positions are opened
+// without NFT metadata, and `base_flag` is left unset so the program takes
+// `liquidity` as given.
+
+/// Ticks per `TickArrayState`
+pub const TICK_ARRAY_SIZE: usize = 60;
+
+/// Accounts for `open_position` (`open_position_with_token22_nft`)
+pub type OpenPositionAccounts<'a> = OpenPositionWithToken22NftAccounts<'a>;
+
+/// Accounts for `add_liquidity` (`increase_liquidity_v2`)
+pub type AddLiquidityAccounts<'a> = IncreaseLiquidityV2Accounts<'a>;
+
+/// Accounts for `remove_liquidity` (`decrease_liquidity_v2`)
+pub type RemoveLiquidityAccounts<'a> = DecreaseLiquidityV2Accounts<'a>;
+
+/// Instruction args for opening `range` with `liquidity`
+///
+/// Bounds and tick-array start indices come from
+/// `clmm_common::open_position_args`; they must match the
+/// `tick_array_lower` / `tick_array_upper` accounts. `None` for a zero
+/// `tick_spacing`.
+#[inline(always)]
+pub fn open_position_args(
+    range: TickRange,
+    tick_spacing: u16,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+) -> Option<OpenPositionWithToken22NftArgs> {
+    let args = clmm_common::open_position_args::<TICK_ARRAY_SIZE>(range, tick_spacing, liquidity, amount_0_max, amount_1_max)?;
+    Some(OpenPositionWithToken22NftArgs {
+        tick_lower_index: args.tick_lower_index,
+        tick_upper_index: args.tick_upper_index,
+        tick_array_lower_start_index: args.tick_array_lower_start_index,
+        tick_array_upper_start_index: args.tick_array_upper_start_index,
+        liquidity,
+        amount_0_max,
+        amount_1_max,
+        with_metadata: false,
+        base_flag: OptionBool::NONE,
+    })
+}
+
+/// CPI: open a position over `range`, depositing up to `amount_0_max` / `amount_1_max`
+///
+/// `ProgramError::InvalidArgument` for a zero `tick_spacing`.
+#[inline(always)]
+pub fn open_position<'a>(
+    accounts: &OpenPositionAccounts<'a>,
+    range: TickRange,
+    tick_spacing: u16,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    let args = open_position_args(range, tick_spacing, liquidity, amount_0_max, amount_1_max)
+        .ok_or(pinocchio::error::ProgramError::InvalidArgument)?;
+    open_position_with_token22_nft(accounts, &args, signers)
+}
+
+/// CPI: add `liquidity` to an existing position
+#[inline(always)]
+pub fn add_liquidity<'a>(
+    accounts: &AddLiquidityAccounts<'a>,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    let args = IncreaseLiquidityV2Args { liquidity, amount_0_max, amount_1_max, base_flag: OptionBool::NONE };
+    increase_liquidity_v2(accounts, &args, signers)
+}
+
+/// CPI: remove `liquidity` from a position, receiving at least `amount_0_min` / `amount_1_min`
+#[inline(always)]
+pub fn remove_liquidity<'a>(
+    accounts: &RemoveLiquidityAccounts<'a>,
+    liquidity: u128,
+    amount_0_min: u64,
+    amount_1_min: u64,
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    decrease_liquidity_v2(accounts, &DecreaseLiquidityV2Args { liquidity, amount_0_min, amount_1_min }, signers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    #[test]
+    fn test_liquidity_discriminators() {
+        assert_eq!(OPEN_POSITION_WITH_TOKEN22_NFT, [77, 255, 174, 82, 125, 29, 201, 46]);
+        assert_eq!(INCREASE_LIQUIDITY_V2, [133, 29, 89, 223, 69, 238, 176, 10]);
+        assert_eq!(DECREASE_LIQUIDITY_V2, [58, 127, 188, 62, 79, 82, 196, 96]);
+    }
+
+    #[test]
+    fn test_open_position_args_serialization() {
+        let args = open_position_args(TickRange { lower: -500, upper: 700 }, 10, u128::MAX - 1, 1_000, 2_000).unwrap();
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 51);
+        assert_eq!(&bytes[0..4], &(-500i32).to_le_bytes());
+        assert_eq!(&bytes[4..8], &700i32.to_le_bytes());
+        assert_eq!(&bytes[8..12], &(-600i32).to_le_bytes());
+        assert_eq!(&bytes[12..16], &600i32.to_le_bytes());
+        assert_eq!(&bytes[16..32], &(u128::MAX - 1).to_le_bytes());
+        assert_eq!(&bytes[32..40], &1_000u64.to_le_bytes());
+        assert_eq!(&bytes[40..48], &2_000u64.to_le_bytes());
+        // with_metadata = false, base_flag = None
+        assert_eq!(&bytes[48..51], &[0, 0, 0]);
+
+        // Off-spacing bounds widen outward
+        let args = open_position_args(TickRange { lower: -495, upper: 601 }, 10, 1, 0, 0).unwrap();
+        let bytes = args_bytes(&args);
+        assert_eq!(&bytes[0..4], &(-500i32).to_le_bytes());
+        assert_eq!(&bytes[4..8], &610i32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_liquidity_args_serialization() {
+        let increase = IncreaseLiquidityV2Args {
+            liquidity: 1u128 << 96 | 3,
+            amount_0_max: 10,
+            amount_1_max: 20,
+            base_flag: Some(false).into(),
+        };
+        let bytes = args_bytes(&increase);
+        assert_eq!(bytes.len(), 34);
+        assert_eq!(&bytes[0..16], &(1u128 << 96 | 3).to_le_bytes());
+        assert_eq!(&bytes[16..24], &10u64.to_le_bytes());
+        assert_eq!(&bytes[24..32], &20u64.to_le_bytes());
+        assert_eq!(&bytes[32..34], &[1, 0]);
+
+        let decrease = DecreaseLiquidityV2Args { liquidity: 1u128 << 64, amount_0_min: 1, amount_1_min: 2 };
+        let bytes = args_bytes(&decrease);
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(&bytes[0..16], &(1u128 << 64).to_le_bytes());
+        assert_eq!(&bytes[16..24], &1u64.to_le_bytes());
+        assert_eq!(&bytes[24..32], &2u64.to_le_bytes());
+    }
+}
@@ -5,16 +5,21 @@
 //! Instructions: 13
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
+    error::ProgramError,
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::log::debug_log;
+use crate::common::{checked_sum, invoke_signed};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("swapFpHZwjELNnjvThjajtiVmkz3yPQEHjLtka2fwHW"));
 
@@ -52,14 +57,6 @@ pub struct ChangeSwapFeeArgs {
     pub new_swap_fee: u64,
 }
 
-/// Arguments for `deposit`
-#[derive(Clone, Copy)]
-#[repr(C, packed)]
-pub struct DepositArgs {
-    pub amounts: [u8; 32],
-    pub minimum_amount_out: u64,
-}
-
 /// Arguments for `initialize`
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
@@ -92,14 +89,6 @@ pub struct TransferOwnerArgs {
     pub new_owner: [u8; 32],
 }
 
-/// Arguments for `withdraw`
-#[derive(Clone, Copy)]
-#[repr(C, packed)]
-pub struct WithdrawArgs {
-    pub amount: u64,
-    pub minimum_amounts_out: [u8; 32],
-}
-
 
 // ============================================
 // Instruction Accounts
@@ -680,37 +669,22 @@ pub fn change_swap_fee<'a>(
 }
 
 /// CPI: deposit
+///
+/// Adds `amounts[i]` of pool token `i` and mints at least
+/// `minimum_amount_out` LP tokens. `tokens` supplies each pool token's
+/// accounts in the pool's token order, one entry per amount.
 #[inline(always)]
 pub fn deposit<'a>(
-    accounts: &DepositAccounts<'a>, args: &DepositArgs,
+    accounts: &DepositAccounts<'a>, tokens: &[PoolTokenAccounts<'a>],
+    amounts: &[u64], minimum_amount_out: u64,
     signers: &[Signer<'_, '_>],
 ) -> ProgramResult {
-    // Build instruction data
-    let mut data = [0u8; 8 + core::mem::size_of::<DepositArgs>()];
-    data[0..8].copy_from_slice(&DEPOSIT);
-    
-        // Copy arguments (unsafe due to packed struct)
-        unsafe {
-            core::ptr::copy_nonoverlapping(
-                args as *const DepositArgs as *const u8,
-                data.as_mut_ptr().add(8),
-                core::mem::size_of::<DepositArgs>(),
-            );
-        }
-    
-    // Build InstructionAccount array
-    let instruction_accounts = accounts.to_instruction_accounts();
-    
-    // Build InstructionView
-    let instruction = InstructionView {
-        program_id: &ID,
-        accounts: &instruction_accounts,
-        data: &data,
-    };
-    
-    // Execute CPI
-    let account_views = accounts.to_views();
-    invoke_signed::<9>(&instruction, &account_views, signers)
+    if amounts.len() != tokens.len() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    debug_log!("deposit tokens={} total_in={:?} min_lp={}", amounts.len(), checked_sum(amounts), minimum_amount_out);
+    let (data, len) = deposit_data(amounts, minimum_amount_out).ok_or(ProgramError::InvalidArgument)?;
+    invoke_with_pool_tokens(&accounts.to_instruction_accounts(), &accounts.to_views(), tokens, &data[..len], signers)
 }
 
 /// CPI: initialize
@@ -954,36 +928,176 @@ pub fn unpause<'a>(
 }
 
 /// CPI: withdraw
+///
+/// Burns `amount` LP tokens for at least `minimum_amounts_out[i]` of pool
+/// token `i`. `tokens` supplies each pool token's accounts in the pool's
+/// token order, one entry per minimum.
 #[inline(always)]
 pub fn withdraw<'a>(
-    accounts: &WithdrawAccounts<'a>, args: &WithdrawArgs,
+    accounts: &WithdrawAccounts<'a>, tokens: &[PoolTokenAccounts<'a>],
+    amount: u64, minimum_amounts_out: &[u64],
     signers: &[Signer<'_, '_>],
 ) -> ProgramResult {
-    // Build instruction data
-    let mut data = [0u8; 8 + core::mem::size_of::<WithdrawArgs>()];
+    if minimum_amounts_out.len() != tokens.len() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (data, len) = withdraw_data(amount, minimum_amounts_out).ok_or(ProgramError::InvalidArgument)?;
+    invoke_with_pool_tokens(&accounts.to_instruction_accounts(), &accounts.to_views(), tokens, &data[..len], signers)
+}
+
+// ============================================
+// Liquidity
+// ============================================
+// `deposit` and `withdraw` take one `u64` per pool token (a Borsh
+// `Vec<u64>`: u32 length, then the values), so their data is variable
+// length. Each pool token also adds three remaining accounts after the
+// fixed ones: the user's token account and the vault's token account
+// (both writable), then the mint.
+
+/// Most pool tokens `deposit` / `withdraw` accept
+pub const MAX_POOL_TOKENS: usize = 8;
+
+/// Longest `deposit` / `withdraw` instruction data
+pub const LIQUIDITY_DATA_MAX_LEN: usize = 8 + 8 + 4 + 8 * MAX_POOL_TOKENS;
+
+/// Remaining accounts for one pool token
+pub struct PoolTokenAccounts<'a> {
+    /// user token account
+    pub user_token: &'a AccountView,
+    /// vault token account
+    pub vault_token: &'a AccountView,
+    /// mint
+    pub mint: &'a AccountView,
+}
+
+/// Append `values` as a Borsh `Vec<u64>` at `offset`, returning the new end
+fn write_u64_vec(data: &mut [u8; LIQUIDITY_DATA_MAX_LEN], offset: usize, values: &[u64]) -> usize {
+    data[offset..offset + 4].copy_from_slice(&(values.len() as u32).to_le_bytes());
+    let mut end = offset + 4;
+    for value in values {
+        data[end..end + 8].copy_from_slice(&value.to_le_bytes());
+        end += 8;
+    }
+    end
+}
+
+/// Instruction data for `deposit`: discriminator, amounts, minimum_amount_out
+///
+/// Returns the buffer and its used length, or `None` for zero or more than
+/// `MAX_POOL_TOKENS` amounts.
+pub fn deposit_data(amounts: &[u64], minimum_amount_out: u64) -> Option<([u8; LIQUIDITY_DATA_MAX_LEN], usize)> {
+    if amounts.is_empty() || amounts.len() > MAX_POOL_TOKENS {
+        return None;
+    }
+    let mut data = [0u8; LIQUIDITY_DATA_MAX_LEN];
+    data[0..8].copy_from_slice(&DEPOSIT);
+    let end = write_u64_vec(&mut data, 8, amounts);
+    data[end..end + 8].copy_from_slice(&minimum_amount_out.to_le_bytes());
+    Some((data, end + 8))
+}
+
+/// Instruction data for `withdraw`: discriminator, amount, minimum_amounts_out
+///
+/// Returns the buffer and its used length, or `None` for zero or more than
+/// `MAX_POOL_TOKENS` minimums.
+pub fn withdraw_data(amount: u64, minimum_amounts_out: &[u64]) -> Option<([u8; LIQUIDITY_DATA_MAX_LEN], usize)> {
+    if minimum_amounts_out.is_empty() || minimum_amounts_out.len() > MAX_POOL_TOKENS {
+        return None;
+    }
+    let mut data = [0u8; LIQUIDITY_DATA_MAX_LEN];
     data[0..8].copy_from_slice(&WITHDRAW);
-    
-        // Copy arguments (unsafe due to packed struct)
-        unsafe {
-            core::ptr::copy_nonoverlapping(
-                args as *const WithdrawArgs as *const u8,
-                data.as_mut_ptr().add(8),
-                core::mem::size_of::<WithdrawArgs>(),
-            );
+    data[8..16].copy_from_slice(&amount.to_le_bytes());
+    let end = write_u64_vec(&mut data, 16, minimum_amounts_out);
+    Some((data, end))
+}
+
+/// Invoke with the fixed accounts followed by each pool token's accounts
+fn invoke_with_pool_tokens<'a, const F: usize>(
+    fixed_accounts: &[InstructionAccount<'a>; F],
+    fixed_views: &[&'a AccountView; F],
+    tokens: &[PoolTokenAccounts<'a>],
+    data: &[u8],
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    const MAX_ACCOUNTS: usize = 10 + 3 * MAX_POOL_TOKENS;
+
+    if tokens.len() > MAX_POOL_TOKENS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let len = F + 3 * tokens.len();
+    let account_views: [&'a AccountView; MAX_ACCOUNTS] = core::array::from_fn(|i| match i {
+        _ if i < F => fixed_views[i],
+        _ if i < len => {
+            let token = &tokens[(i - F) / 3];
+            [token.user_token, token.vault_token, token.mint][(i - F) % 3]
         }
-    
-    // Build InstructionAccount array
-    let instruction_accounts = accounts.to_instruction_accounts();
-    
-    // Build InstructionView
+        _ => fixed_views[0],
+    });
+    let instruction_accounts: [InstructionAccount<'a>; MAX_ACCOUNTS] = core::array::from_fn(|i| match i {
+        _ if i < F => fixed_accounts[i].clone(),
+        _ if (i - F) % 3 == 2 => InstructionAccount::readonly(account_views[i].address()),
+        _ => InstructionAccount::writable(account_views[i].address()),
+    });
+
     let instruction = InstructionView {
         program_id: &ID,
-        accounts: &instruction_accounts,
-        data: &data,
+        accounts: &instruction_accounts[..len],
+        data,
     };
-    
-    // Execute CPI
-    let account_views = accounts.to_views();
-    invoke_signed::<10>(&instruction, &account_views, signers)
+    crate::common::invoke_signed_with_bounds::<MAX_ACCOUNTS>(&instruction, &account_views[..len], signers)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::mock_account_views;
+    use pinocchio::account::RuntimeAccount;
+
+    #[test]
+    fn test_deposit_data() {
+        let (data, len) = deposit_data(&[1_000, 2_000, 3_000], 42).unwrap();
+        assert_eq!(len, 8 + 4 + 3 * 8 + 8);
+        assert_eq!(&data[0..8], &DEPOSIT);
+        assert_eq!(&data[8..12], &3u32.to_le_bytes());
+        assert_eq!(&data[12..20], &1_000u64.to_le_bytes());
+        assert_eq!(&data[28..36], &3_000u64.to_le_bytes());
+        assert_eq!(&data[36..44], &42u64.to_le_bytes());
+
+        assert!(deposit_data(&[], 0).is_none());
+        assert!(deposit_data(&[1; MAX_POOL_TOKENS + 1], 0).is_none());
+        assert_eq!(deposit_data(&[1; MAX_POOL_TOKENS], 0).unwrap().1, LIQUIDITY_DATA_MAX_LEN);
+    }
+
+    #[test]
+    fn test_withdraw_data() {
+        let (data, len) = withdraw_data(5_000, &[10, 20]).unwrap();
+        assert_eq!(len, 8 + 8 + 4 + 2 * 8);
+        assert_eq!(&data[0..8], &WITHDRAW);
+        assert_eq!(&data[8..16], &5_000u64.to_le_bytes());
+        assert_eq!(&data[16..20], &2u32.to_le_bytes());
+        assert_eq!(&data[20..28], &10u64.to_le_bytes());
+        assert_eq!(&data[28..36], &20u64.to_le_bytes());
+        assert!(withdraw_data(5_000, &[]).is_none());
+    }
+
+    #[test]
+    fn test_deposit_requires_one_amount_per_token() {
+        let mut raw: [RuntimeAccount; 12] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let accounts = DepositAccounts {
+            user: &v[0],
+            user_pool_token: &v[1],
+            mint: &v[2],
+            pool: &v[3],
+            pool_authority: &v[4],
+            vault: &v[5],
+            vault_authority: &v[6],
+            token_program: &v[7],
+            token_program_2022: &v[8],
+        };
+        let tokens = [PoolTokenAccounts { user_token: &v[9], vault_token: &v[10], mint: &v[11] }];
+
+        assert_eq!(deposit(&accounts, &tokens, &[1, 2], 0, &[]), Err(ProgramError::InvalidArgument));
+        assert_eq!(deposit(&accounts, &tokens, &[1], 0, &[]), Ok(()));
+    }
+}
@@ -5,16 +5,19 @@
 //! Instructions: 6
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_bytes, read_pubkey, read_u128_le, read_u64_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("vrTGoBuy5rYSxAfV3jaRJWHH6nN9WK4NRExGxsk1bCJ"));
 
@@ -572,3 +575,139 @@ pub fn sell<'a>(
     invoke_signed::<13>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `Pool`
+pub const POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+
+/// Decoded `Pool` account (fields needed for quoting)
+///
+/// Token A is the quote side (SOL), token B is the launched token.
+/// The curve prices against `token_a_reserves + shift` (virtual SOL).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pool {
+    pub enabled: bool,
+    pub owner: [u8; 32],
+    pub mint_a: [u8; 32],
+    pub mint_b: [u8; 32],
+    pub token_a_reserves: u128,
+    pub token_b_reserves: u128,
+    pub shift: u128,
+    /// Accrued creator royalties (lamports)
+    pub royalties: u64,
+    /// Accrued protocol fees (lamports)
+    pub vertigo_fees: u64,
+    pub bump: u8,
+    /// Royalty rate charged on the SOL leg of every swap
+    pub royalties_bps: u16,
+}
+
+impl Pool {
+    /// Minimum account size up to and including `fee_params.royalties_bps`
+    pub const MIN_SIZE: usize = 196;
+
+    /// Parse a `Pool` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::MIN_SIZE || data[0..8] != POOL_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            enabled: data[8] != 0,
+            owner: read_pubkey(data, 9)?,
+            mint_a: read_pubkey(data, 41)?,
+            mint_b: read_pubkey(data, 73)?,
+            token_a_reserves: read_u128_le(data, 105)?,
+            token_b_reserves: read_u128_le(data, 121)?,
+            shift: read_u128_le(data, 137)?,
+            royalties: read_u64_le(data, 153)?,
+            vertigo_fees: read_u64_le(data, 161)?,
+            bump: data[169],
+            // fee_params: normalization_period (u64), decay (f64), reference (u64), royalties_bps (u16)
+            royalties_bps: u16::from_le_bytes(read_bytes(data, 194)?),
+        })
+    }
+
+    /// Royalty taken from a SOL amount (rounded up, as the program does)
+    #[inline(always)]
+    pub fn royalty_fee(&self, amount_a: u64) -> u64 {
+        let fee = (amount_a as u128 * self.royalties_bps as u128).div_ceil(10_000);
+        fee.min(amount_a as u128) as u64
+    }
+
+    /// Expected token B out for `amount_a` SOL in, net of royalties
+    ///
+    /// Does not model the launch-window fee that decays over
+    /// `normalization_period` slots; quotes inside that window are optimistic.
+    pub fn quote_buy(&self, amount_a: u64) -> u64 {
+        let amount_in = (amount_a - self.royalty_fee(amount_a)) as u128;
+        let virtual_a = self.token_a_reserves.saturating_add(self.shift);
+        let denominator = virtual_a.saturating_add(amount_in);
+        if amount_in == 0 || denominator == 0 {
+            return 0;
+        }
+        let out = self.token_b_reserves.saturating_mul(amount_in) / denominator;
+        out.min(self.token_b_reserves).min(u64::MAX as u128) as u64
+    }
+
+    /// Expected SOL out for `amount_b` tokens in, net of royalties
+    pub fn quote_sell(&self, amount_b: u64) -> u64 {
+        let virtual_a = self.token_a_reserves.saturating_add(self.shift);
+        let denominator = self.token_b_reserves.saturating_add(amount_b as u128);
+        if amount_b == 0 || denominator == 0 {
+            return 0;
+        }
+        // Only real SOL can leave the pool
+        let gross = (virtual_a.saturating_mul(amount_b as u128) / denominator)
+            .min(self.token_a_reserves)
+            .min(u64::MAX as u128) as u64;
+        gross - self.royalty_fee(gross)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_fixture(royalties_bps: u16) -> [u8; 229] {
+        let mut data = [0u8; 229];
+        data[0..8].copy_from_slice(&POOL_DISCRIMINATOR);
+        data[8] = 1;
+        data[105..121].copy_from_slice(&10_000_000_000u128.to_le_bytes());
+        data[121..137].copy_from_slice(&1_000_000_000_000u128.to_le_bytes());
+        data[137..153].copy_from_slice(&90_000_000_000u128.to_le_bytes());
+        data[194..196].copy_from_slice(&royalties_bps.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_pool_parse() {
+        let pool = Pool::from_bytes(&pool_fixture(100)).unwrap();
+        assert!(pool.enabled);
+        assert_eq!(pool.token_a_reserves, 10_000_000_000);
+        assert_eq!(pool.shift, 90_000_000_000);
+        assert_eq!(pool.royalties_bps, 100);
+
+        let mut bad = pool_fixture(100);
+        bad[0] ^= 1;
+        assert!(Pool::from_bytes(&bad).is_none());
+        assert!(Pool::from_bytes(&bad[..100]).is_none());
+    }
+
+    #[test]
+    fn test_quote_royalty_levels() {
+        let free = Pool::from_bytes(&pool_fixture(0)).unwrap();
+        let one_pct = Pool::from_bytes(&pool_fixture(100)).unwrap();
+        let five_pct = Pool::from_bytes(&pool_fixture(500)).unwrap();
+
+        // 1 SOL in against 100 SOL virtual / 1e12 tokens
+        assert_eq!(free.quote_buy(1_000_000_000), 9_900_990_099);
+        assert_eq!(one_pct.quote_buy(1_000_000_000), 9_802_950_787);
+        assert_eq!(five_pct.quote_buy(1_000_000_000), 9_410_599_306);
+
+        assert_eq!(free.quote_sell(10_000_000_000), 990_099_009);
+        assert_eq!(one_pct.quote_sell(10_000_000_000), 980_198_018);
+        assert_eq!(five_pct.quote_sell(10_000_000_000), 940_594_058);
+    }
+}
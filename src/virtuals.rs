@@ -5,16 +5,19 @@
 //! Instructions: 8
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_pubkey};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("5U3EU2ubXtK84QcRjWVmYt9RaDyA8gKxdUrPFXmZyaki"));
 
@@ -114,62 +117,69 @@ impl<'a> BuyAccounts<'a> {
 }
 
 /// Accounts for `claim_fees`
+///
+/// Claims the fees accrued on a vpool and splits them between the
+/// platform and the agent creator. Fees come from two places: the vpool's
+/// own ATAs (`vpool_*_ata`) and, once the pool has graduated to Meteora,
+/// the trading fees on the LP locked in `lock_escrow` / `escrow_vault`,
+/// which are withdrawn through the Meteora vault accounts. The split lands
+/// in `platform_*_ata` and `creator_*_ata`.
 pub struct ClaimFeesAccounts<'a> {
-    /// payer
+    /// Fee payer for any ATA created on the way; signs
     pub payer: &'a AccountView,
-    /// vpool
+    /// `VirtualsPool` PDA, seeds `["vpool", token_mint]`
     pub vpool: &'a AccountView,
-    /// virtuals_mint
+    /// VIRTUALS mint (`VIRTUALS_MINT`)
     pub virtuals_mint: &'a AccountView,
-    /// token_mint
+    /// Agent token mint
     pub token_mint: &'a AccountView,
-    /// vpool_virtuals_ata
+    /// vpool's VIRTUALS ATA; fees accrue here before they are split
     pub vpool_virtuals_ata: &'a AccountView,
-    /// vpool_token_ata
+    /// vpool's agent-token ATA; fees accrue here before they are split
     pub vpool_token_ata: &'a AccountView,
-    /// platform
+    /// Virtuals platform fee account (`PLATFORM`)
     pub platform: &'a AccountView,
-    /// platform_virtuals_ata
+    /// Platform's VIRTUALS ATA, receives the platform share
     pub platform_virtuals_ata: &'a AccountView,
-    /// platform_token_ata
+    /// Platform's agent-token ATA, receives the platform share
     pub platform_token_ata: &'a AccountView,
-    /// creator_virtuals_ata
+    /// Creator's VIRTUALS ATA, receives the creator share
     pub creator_virtuals_ata: &'a AccountView,
-    /// creator_token_ata
+    /// Creator's agent-token ATA (ATA of `vpool.creator`), receives the creator share
     pub creator_token_ata: &'a AccountView,
-    /// pool
+    /// Meteora dynamic AMM pool the vpool graduated into
     pub pool: &'a AccountView,
-    /// lp_mint
+    /// LP mint of `pool`
     pub lp_mint: &'a AccountView,
-    /// lock_escrow
+    /// Meteora lock escrow holding the vpool's locked LP
     pub lock_escrow: &'a AccountView,
-    /// escrow_vault
+    /// LP token account of `lock_escrow`; its trading fees are claimed from here
     pub escrow_vault: &'a AccountView,
-    /// token_program
+    /// SPL Token program
     pub token_program: &'a AccountView,
-    /// virtuals_vault
+    /// Meteora vault for the VIRTUALS side of `pool`
     pub virtuals_vault: &'a AccountView,
-    /// token_vault
+    /// Meteora vault for the agent-token side of `pool`
     pub token_vault: &'a AccountView,
-    /// virtuals_token_vault
+    /// Token account of `virtuals_vault`
     pub virtuals_token_vault: &'a AccountView,
-    /// token_token_vault
+    /// Token account of `token_vault`
     pub token_token_vault: &'a AccountView,
-    /// virtuals_vault_lp_mint
+    /// LP mint of `virtuals_vault`
     pub virtuals_vault_lp_mint: &'a AccountView,
-    /// token_vault_lp_mint
+    /// LP mint of `token_vault`
     pub token_vault_lp_mint: &'a AccountView,
-    /// virtuals_vault_lp
+    /// `pool`'s LP position in `virtuals_vault`
     pub virtuals_vault_lp: &'a AccountView,
-    /// token_vault_lp
+    /// `pool`'s LP position in `token_vault`
     pub token_vault_lp: &'a AccountView,
-    /// vault_program
+    /// Meteora vault program
     pub vault_program: &'a AccountView,
-    /// associated_token_program
+    /// Associated Token program
     pub associated_token_program: &'a AccountView,
-    /// system_program
+    /// System program
     pub system_program: &'a AccountView,
-    /// dynamic_amm_program
+    /// Meteora dynamic AMM program
     pub dynamic_amm_program: &'a AccountView,
 }
 
@@ -919,3 +929,99 @@ pub fn update_pool_creator<'a>(
     invoke_signed::<10>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Fee Claim
+// ============================================
+
+/// VIRTUALS mint, the quote side of every vpool
+pub const VIRTUALS_MINT: Address = Address::new_from_array(five8_const::decode_32_const("3iQL8BFS2vE7mww4ehAqQHAsbmRNCrPxizWAT2Zfyr9y"));
+
+/// Platform account whose ATAs receive the platform share of `claim_fees`
+pub const PLATFORM: Address = Address::new_from_array(five8_const::decode_32_const("FxKXqCKXdxPbfYau83ZkGUXgcZWhKBTfAjGg2KzYSopS"));
+
+/// vpool PDA seed prefix: `["vpool", token_mint]`
+pub const VPOOL_SEED: &[u8] = b"vpool";
+
+/// `VirtualsPool` account discriminator
+pub const VIRTUALS_POOL_DISCRIMINATOR: [u8; 8] = [71, 118, 5, 203, 5, 98, 135, 116];
+
+/// Creator of a `VirtualsPool`, the owner of `creator_token_ata` in `claim_fees`
+///
+/// Returns `None` on a wrong discriminator or short data.
+pub fn vpool_creator(data: &[u8]) -> Option<[u8; 32]> {
+    if data.get(..8)? != VIRTUALS_POOL_DISCRIMINATOR {
+        return None;
+    }
+    read_pubkey(data, 8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{dump_account_roles, hash::anchor_discriminator, last_cpi, mock_account_views};
+    use pinocchio::account::RuntimeAccount;
+
+    #[test]
+    fn test_claim_fees_discriminators() {
+        assert_eq!(CLAIM_FEES, anchor_discriminator("global", "claim_fees"));
+        assert_eq!(VIRTUALS_POOL_DISCRIMINATOR, anchor_discriminator("account", "VirtualsPool"));
+    }
+
+    #[test]
+    fn test_claim_fees_accounts() {
+        let mut raw: [RuntimeAccount; 28] = Default::default();
+        let v = mock_account_views(&mut raw);
+        let accounts = ClaimFeesAccounts {
+            payer: &v[0],
+            vpool: &v[1],
+            virtuals_mint: &v[2],
+            token_mint: &v[3],
+            vpool_virtuals_ata: &v[4],
+            vpool_token_ata: &v[5],
+            platform: &v[6],
+            platform_virtuals_ata: &v[7],
+            platform_token_ata: &v[8],
+            creator_virtuals_ata: &v[9],
+            creator_token_ata: &v[10],
+            pool: &v[11],
+            lp_mint: &v[12],
+            lock_escrow: &v[13],
+            escrow_vault: &v[14],
+            token_program: &v[15],
+            virtuals_vault: &v[16],
+            token_vault: &v[17],
+            virtuals_token_vault: &v[18],
+            token_token_vault: &v[19],
+            virtuals_vault_lp_mint: &v[20],
+            token_vault_lp_mint: &v[21],
+            virtuals_vault_lp: &v[22],
+            token_vault_lp: &v[23],
+            vault_program: &v[24],
+            associated_token_program: &v[25],
+            system_program: &v[26],
+            dynamic_amm_program: &v[27],
+        };
+        let instruction_accounts = accounts.to_instruction_accounts();
+        // Only the payer signs; mints and programs stay readonly
+        assert_eq!(
+            dump_account_roles(&instruction_accounts),
+            "ws w r r w w w w w w w w w w w r w w w w w w w w r r r r"
+        );
+        for (i, (account, view)) in instruction_accounts.iter().zip(accounts.to_views()).enumerate() {
+            assert_eq!(account.address, view.address(), "account {i}");
+        }
+        claim_fees(&accounts, &[]).unwrap();
+        assert_eq!(last_cpi(), (ID, CLAIM_FEES.to_vec()));
+    }
+
+    #[test]
+    fn test_vpool_creator() {
+        let mut data = [0u8; 8 + 32 + 32 + 8 + 8 + 1 + 1];
+        data[..8].copy_from_slice(&VIRTUALS_POOL_DISCRIMINATOR);
+        data[8..40].copy_from_slice(&[7; 32]);
+        assert_eq!(vpool_creator(&data), Some([7; 32]));
+        assert_eq!(vpool_creator(&data[..39]), None);
+        data[0] ^= 1;
+        assert_eq!(vpool_creator(&data), None);
+    }
+}
@@ -5,16 +5,20 @@
 //! Instructions: 58
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::clmm_common::{self, SwapState, TickArrayLayout, TickArrayView, TickRange};
+use crate::common::{invoke_signed, read_bytes, read_pubkey, read_u128_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"));
 
@@ -408,7 +412,48 @@ pub struct Swapv2Args {
     pub sqrt_price_limit: u128,
     pub amount_specified_is_input: bool,
     pub a_to_b: bool,
-    pub remaining_accounts_info: Option<[u8; 32]>,
+    pub remaining_accounts_info: RemainingAccountsInfo,
+}
+
+/// Maximum supplemental tick arrays accepted by `swapV2`
+pub const MAX_SUPPLEMENTAL_TICK_ARRAYS: usize = 3;
+
+/// `AccountsType::SupplementalTickArrays`
+pub const ACCOUNTS_TYPE_SUPPLEMENTAL_TICK_ARRAYS: u8 = 6;
+
+/// Borsh `Option<RemainingAccountsInfo>` holding at most one slice
+///
+/// Encoded as option tag, `u32` vec length, then one `RemainingAccountsSlice`.
+/// With `NONE` only the tag is meaningful; trailing bytes are ignored by
+/// Anchor's instruction deserializer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C, packed)]
+pub struct RemainingAccountsInfo {
+    tag: u8,
+    slices_len: u32,
+    accounts_type: u8,
+    length: u8,
+}
+
+impl RemainingAccountsInfo {
+    /// `None`
+    pub const NONE: Self = Self { tag: 0, slices_len: 0, accounts_type: 0, length: 0 };
+
+    /// A single slice of `length` accounts of `accounts_type`
+    #[inline(always)]
+    pub const fn slice(accounts_type: u8, length: u8) -> Self {
+        Self { tag: 1, slices_len: 1, accounts_type, length }
+    }
+
+    /// `count` supplemental tick arrays appended after the fixed accounts
+    #[inline(always)]
+    pub const fn supplemental_tick_arrays(count: u8) -> Self {
+        if count == 0 {
+            Self::NONE
+        } else {
+            Self::slice(ACCOUNTS_TYPE_SUPPLEMENTAL_TICK_ARRAYS, count)
+        }
+    }
 }
 
 /// Arguments for `twoHopSwapV2`
@@ -958,18 +1003,23 @@ impl<'a> CollectfeesAccounts<'a> {
 }
 
 /// Accounts for `collectReward`
+///
+/// Pays out the rewards accrued on `position` for one `reward_index`.
+/// Rewards only accrue up to the last `updateFeesAndRewards` (or any
+/// liquidity change) on the position; call that first to harvest the full
+/// amount.
 pub struct CollectrewardAccounts<'a> {
     /// whirlpool
     pub whirlpool: &'a AccountView,
-    /// positionAuthority
+    /// positionAuthority: owner or delegate of the position token
     pub position_authority: &'a AccountView,
     /// position
     pub position: &'a AccountView,
-    /// positionTokenAccount
+    /// positionTokenAccount: holds the position NFT
     pub position_token_account: &'a AccountView,
-    /// rewardOwnerAccount
+    /// rewardOwnerAccount: receives the reward, a token account of the reward mint
     pub reward_owner_account: &'a AccountView,
-    /// rewardVault
+    /// rewardVault: `reward_infos[reward_index].vault` of the whirlpool
     pub reward_vault: &'a AccountView,
     /// tokenProgram
     pub token_program: &'a AccountView,
@@ -4636,6 +4686,66 @@ pub fn swap_v2<'a>(
     invoke_signed::<15>(&instruction, &account_views, signers)
 }
 
+/// CPI: swapV2 with supplemental tick arrays
+///
+/// Swaps that cross more than the three fixed tick arrays fail with
+/// `TickArraySequenceInvalidIndex` unless the extra arrays are passed as
+/// remaining accounts. Up to `MAX_SUPPLEMENTAL_TICK_ARRAYS` are appended
+/// (writable) after `oracle`, and `args.remaining_accounts_info` is
+/// overwritten to describe them. Transfer hook slices are not supported here.
+#[inline(always)]
+pub fn swap_v2_with_supplemental_tick_arrays<'a>(
+    accounts: &Swapv2Accounts<'a>, args: &Swapv2Args,
+    supplemental_tick_arrays: &[&'a AccountView],
+    signers: &[Signer<'_, '_>],
+) -> ProgramResult {
+    const MAX_ACCOUNTS: usize = 15 + MAX_SUPPLEMENTAL_TICK_ARRAYS;
+
+    let count = supplemental_tick_arrays.len();
+    if count > MAX_SUPPLEMENTAL_TICK_ARRAYS {
+        return Err(pinocchio::error::ProgramError::InvalidArgument);
+    }
+
+    // Build instruction data
+    let mut args = *args;
+    args.remaining_accounts_info = RemainingAccountsInfo::supplemental_tick_arrays(count as u8);
+    let mut data = [0u8; 8 + core::mem::size_of::<Swapv2Args>()];
+    data[0..8].copy_from_slice(&SWAP_V2);
+    
+        // Copy arguments (unsafe due to packed struct)
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                &args as *const Swapv2Args as *const u8,
+                data.as_mut_ptr().add(8),
+                core::mem::size_of::<Swapv2Args>(),
+            );
+        }
+    
+    // Build InstructionAccount array: fixed accounts, then supplemental tick arrays
+    let fixed_accounts = accounts.to_instruction_accounts();
+    let fixed_views = accounts.to_views();
+    let len = 15 + count;
+    let account_views: [&'a AccountView; MAX_ACCOUNTS] = core::array::from_fn(|i| match i {
+        0..15 => fixed_views[i],
+        _ if i < len => supplemental_tick_arrays[i - 15],
+        _ => accounts.whirlpool,
+    });
+    let instruction_accounts: [InstructionAccount<'a>; MAX_ACCOUNTS] = core::array::from_fn(|i| match i {
+        0..15 => fixed_accounts[i].clone(),
+        _ => InstructionAccount::writable(account_views[i].address()),
+    });
+    
+    // Build InstructionView
+    let instruction = InstructionView {
+        program_id: &ID,
+        accounts: &instruction_accounts[..len],
+        data: &data,
+    };
+    
+    // Execute CPI
+    crate::common::invoke_signed_with_bounds::<MAX_ACCOUNTS>(&instruction, &account_views[..len], signers)
+}
+
 /// CPI: twoHopSwapV2
 #[inline(always)]
 pub fn two_hop_swap_v2<'a>(
@@ -4800,3 +4910,314 @@ pub fn delete_token_badge<'a>(
     invoke_signed::<6>(&instruction, &account_views, signers)
 }
 
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `TickArray`
+pub const TICK_ARRAY_DISCRIMINATOR: [u8; 8] = [69, 97, 189, 190, 110, 7, 66, 187];
+
+/// Ticks per `TickArray`
+pub const TICK_ARRAY_SIZE: usize = 88;
+
+/// Packed size of one `Tick`
+const TICK_SIZE: usize = 113;
+
+/// Fixed `TickArray` layout for `TickArrayView`
+///
+/// discriminator, start_tick_index (i32), then ticks of initialized (bool),
+/// liquidity_net (i128), liquidity_gross (u128), fee/reward growths, then
+/// the whirlpool (pubkey). Tick `i` sits at `start_tick_index + i *
+/// tick_spacing`. Dynamic tick arrays are not supported.
+pub const TICK_ARRAY_LAYOUT: TickArrayLayout = TickArrayLayout {
+    discriminator: TICK_ARRAY_DISCRIMINATOR,
+    start_tick_index_offset: 8,
+    ticks_offset: 12,
+    tick_size: TICK_SIZE,
+    tick_count: TICK_ARRAY_SIZE,
+    liquidity_net_offset: 1,
+    liquidity_gross_offset: 17,
+    initialized_offset: Some(0),
+};
+
+// ============================================
+// Position Bundles
+// ============================================
+/// Account discriminator for `PositionBundle`
+pub const POSITION_BUNDLE_DISCRIMINATOR: [u8; 8] = [129, 169, 175, 65, 185, 95, 32, 100];
+
+/// Bundled positions per `PositionBundle`; valid `bundle_index` is `0..256`
+pub const POSITION_BUNDLE_SIZE: u16 = 256;
+
+/// Decoded `PositionBundle` account
+///
+/// Bit `i` of `position_bitmap` is set while the bundled position at
+/// `bundle_index = i` is open.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionBundle {
+    pub position_bundle_mint: [u8; 32],
+    pub position_bitmap: [u8; 32],
+}
+
+impl PositionBundle {
+    pub const SIZE: usize = 72;
+
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        // discriminator, position_bundle_mint (pubkey), position_bitmap ([u8; 32])
+        if data.len() < Self::SIZE || data[0..8] != POSITION_BUNDLE_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            position_bundle_mint: read_pubkey(data, 8)?,
+            position_bitmap: read_bytes(data, 40)?,
+        })
+    }
+
+    /// Whether `bundle_index` currently holds an open position
+    pub fn is_occupied(&self, bundle_index: u16) -> bool {
+        bundle_index < POSITION_BUNDLE_SIZE
+            && self.position_bitmap[bundle_index as usize / 8] & (1 << (bundle_index % 8)) != 0
+    }
+
+    /// Lowest free `bundle_index`, or `None` when all 256 slots are in use
+    pub fn first_vacant(&self) -> Option<u16> {
+        (0..POSITION_BUNDLE_SIZE).find(|&i| !self.is_occupied(i))
+    }
+}
+
+// ============================================
+// Rewards
+// ============================================
+/// Account discriminator for `Whirlpool`
+pub const WHIRLPOOL_DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+
+/// Reward slots per whirlpool; valid `reward_index` is `0..NUM_REWARDS`
+pub const NUM_REWARDS: u8 = 3;
+
+/// Offset of `reward_infos` in a `Whirlpool` account
+const REWARD_INFOS_OFFSET: usize = 269;
+
+/// Packed size of one `WhirlpoolRewardInfo`
+const REWARD_INFO_SIZE: usize = 128;
+
+/// `reward_index` and vault of the reward slot paying `reward_mint`
+///
+/// Feeds `CollectrewardArgs` and `CollectrewardAccounts::reward_vault`.
+/// `None` when no slot emits that mint.
+pub fn reward_vault_for_mint(whirlpool_data: &[u8], reward_mint: &[u8; 32]) -> Option<(u8, [u8; 32])> {
+    // reward_infos: [mint, vault, authority, emissions_per_second_x64, growth_global_x64; 3]
+    if whirlpool_data.len() < REWARD_INFOS_OFFSET + NUM_REWARDS as usize * REWARD_INFO_SIZE
+        || whirlpool_data[0..8] != WHIRLPOOL_DISCRIMINATOR
+    {
+        return None;
+    }
+    (0..NUM_REWARDS).find_map(|i| {
+        let o = REWARD_INFOS_OFFSET + i as usize * REWARD_INFO_SIZE;
+        (read_pubkey(whirlpool_data, o)? == *reward_mint).then_some((i, read_pubkey(whirlpool_data, o + 32)?))
+    })
+}
+
+// ============================================
+// Quote
+// ============================================
+
+/// Exact-input output of a swap across the given `TickArray` accounts
+///
+/// Reads tick spacing, fee rate, liquidity, price and current tick from the
+/// `Whirlpool` account. `tick_arrays` is the raw data of the arrays the swap
+/// would pass, in any order. `None` on invalid accounts or when the arrays
+/// do not cover `amount_in`.
+pub fn quote_exact_in(whirlpool_data: &[u8], tick_arrays: &[&[u8]], amount_in: u64, a_to_b: bool) -> Option<u64> {
+    // discriminator, whirlpools_config, bump, tick_spacing, fee_tier_index_seed, fee_rate,
+    // protocol_fee_rate, liquidity, sqrt_price, tick_current_index, ...
+    if whirlpool_data.len() < 85 || whirlpool_data[0..8] != WHIRLPOOL_DISCRIMINATOR {
+        return None;
+    }
+    let tick_spacing = u16::from_le_bytes(read_bytes(whirlpool_data, 41)?);
+    let fee_rate = u16::from_le_bytes(read_bytes(whirlpool_data, 45)?);
+    let state = SwapState {
+        liquidity: read_u128_le(whirlpool_data, 49)?,
+        sqrt_price_x64: read_u128_le(whirlpool_data, 65)?,
+        tick_current: i32::from_le_bytes(read_bytes(whirlpool_data, 81)?),
+    };
+    clmm_common::quote_exact_in(state, fee_rate as u32, tick_arrays, &TICK_ARRAY_LAYOUT, tick_spacing, amount_in, a_to_b)
+}
+
+// ============================================
+// Position
+// ============================================
+
+/// Instruction args for `open_position_with_token_extensions` over `range`
+///
+/// `range` is widened to the pool's `tick_spacing` with `TickRange::snapped`.
+/// The position NFT is minted without the token metadata extension.
+#[inline(always)]
+pub fn open_position_args(range: TickRange, tick_spacing: u16) -> OpenpositionwithtokenextensionsArgs {
+    let range = TickRange::snapped(range.lower, range.upper, tick_spacing);
+    OpenpositionwithtokenextensionsArgs {
+        tick_lower_index: range.lower,
+        tick_upper_index: range.upper,
+        with_token_metadata_extension: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::args_bytes;
+
+    fn tick_array_fixture(start_tick_index: i32, ticks: &[(usize, i128)]) -> [u8; 9988] {
+        let mut data = [0u8; 9988];
+        data[0..8].copy_from_slice(&TICK_ARRAY_DISCRIMINATOR);
+        data[8..12].copy_from_slice(&start_tick_index.to_le_bytes());
+        for &(i, net) in ticks {
+            let o = 12 + i * TICK_SIZE;
+            data[o] = 1;
+            data[o + 1..o + 17].copy_from_slice(&net.to_le_bytes());
+            data[o + 17..o + 33].copy_from_slice(&net.unsigned_abs().to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_tick_array_view() {
+        let mut data = tick_array_fixture(-5632, &[(3, 500), (40, -500)]);
+
+        let array = TickArrayView::new(&data, &TICK_ARRAY_LAYOUT, 64).unwrap();
+        assert_eq!(array.start_tick_index, -5632);
+        assert_eq!(array.tick(3).map(|t| t.index), Some(-5632 + 3 * 64));
+        // Walk up from the array start, then back down
+        let up = array.next_initialized_tick(-5632, false).unwrap();
+        assert_eq!((up.index, up.liquidity_net), (-5440, 500));
+        let down = array.next_initialized_tick(0, true).unwrap();
+        assert_eq!((down.index, down.liquidity_net), (-5632 + 40 * 64, -500));
+
+        data[0] ^= 1;
+        assert!(TickArrayView::new(&data, &TICK_ARRAY_LAYOUT, 64).is_none());
+    }
+
+    #[test]
+    fn test_quote_exact_in() {
+        assert_eq!(WHIRLPOOL_DISCRIMINATOR, crate::common::hash::anchor_discriminator("account", "Whirlpool"));
+        // Price 1.0, spacing 64, 0.3% fee, 1e15 liquidity over -5632..5568
+        let mut pool = [0u8; 653];
+        pool[0..8].copy_from_slice(&WHIRLPOOL_DISCRIMINATOR);
+        pool[41..43].copy_from_slice(&64u16.to_le_bytes());
+        pool[45..47].copy_from_slice(&3_000u16.to_le_bytes());
+        pool[49..65].copy_from_slice(&1_000_000_000_000_000u128.to_le_bytes());
+        pool[65..81].copy_from_slice(&(1u128 << 64).to_le_bytes());
+        let below = tick_array_fixture(-5632, &[(0, 1_000_000_000_000_000)]);
+        let above = tick_array_fixture(0, &[(87, -1_000_000_000_000_000)]);
+        let arrays: [&[u8]; 2] = [&below, &above];
+
+        // Single range, so the closed form: 1e6 less 0.3%, less rounding
+        assert_eq!(quote_exact_in(&pool, &arrays, 1_000_000, true), Some(996_999));
+        assert_eq!(quote_exact_in(&pool, &arrays, 1_000_000, false), Some(996_999));
+        assert_eq!(quote_exact_in(&pool, &arrays[..1], 1_000_000, false), None);
+        assert_eq!(quote_exact_in(&below, &arrays, 1_000_000, true), None);
+    }
+
+    #[test]
+    fn test_swap_v2_args_serialization() {
+        let args = Swapv2Args {
+            amount: 1_000_000,
+            other_amount_threshold: 990_000,
+            sqrt_price_limit: 4_295_048_016,
+            amount_specified_is_input: true,
+            a_to_b: true,
+            remaining_accounts_info: RemainingAccountsInfo::supplemental_tick_arrays(2),
+        };
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 41);
+        assert_eq!(&bytes[0..8], &1_000_000u64.to_le_bytes());
+        assert_eq!(&bytes[8..16], &990_000u64.to_le_bytes());
+        assert_eq!(&bytes[16..32], &4_295_048_016u128.to_le_bytes());
+        assert_eq!(&bytes[32..34], &[1, 1]);
+        // Some(RemainingAccountsInfo { slices: vec![{ SupplementalTickArrays, 2 }] })
+        assert_eq!(&bytes[34..41], &[1, 1, 0, 0, 0, 6, 2]);
+    }
+
+    #[test]
+    fn test_remaining_accounts_info_none() {
+        assert_eq!(RemainingAccountsInfo::supplemental_tick_arrays(0), RemainingAccountsInfo::NONE);
+        assert_eq!(args_bytes(&RemainingAccountsInfo::NONE)[0], 0);
+    }
+
+    #[test]
+    fn test_bundled_position_args_serialization() {
+        assert_eq!(OPEN_BUNDLED_POSITION, crate::common::hash::anchor_discriminator("global", "open_bundled_position"));
+        assert_eq!(CLOSE_BUNDLED_POSITION, crate::common::hash::anchor_discriminator("global", "close_bundled_position"));
+
+        let open = OpenbundledpositionArgs { bundle_index: 255, tick_lower_index: -128, tick_upper_index: 64 };
+        let bytes = args_bytes(&open);
+        assert_eq!(bytes.len(), 10);
+        assert_eq!(&bytes[0..2], &[255, 0]);
+        assert_eq!(&bytes[2..6], &(-128i32).to_le_bytes());
+        assert_eq!(&bytes[6..10], &64i32.to_le_bytes());
+
+        let close = ClosebundledpositionArgs { bundle_index: 0x0102 };
+        assert_eq!(args_bytes(&close), [0x02, 0x01]);
+    }
+
+    #[test]
+    fn test_open_position_args_builder() {
+        let args = open_position_args(TickRange { lower: -100, upper: 100 }, 64);
+        let bytes = args_bytes(&args);
+        assert_eq!(bytes.len(), 9);
+        assert_eq!(&bytes[0..4], &(-128i32).to_le_bytes());
+        assert_eq!(&bytes[4..8], &128i32.to_le_bytes());
+        assert_eq!(bytes[8], 0);
+    }
+
+    #[test]
+    fn test_position_bundle() {
+        assert_eq!(POSITION_BUNDLE_DISCRIMINATOR, crate::common::hash::anchor_discriminator("account", "PositionBundle"));
+        let mut data = [0u8; PositionBundle::SIZE];
+        data[0..8].copy_from_slice(&POSITION_BUNDLE_DISCRIMINATOR);
+        data[8..40].copy_from_slice(&[7u8; 32]);
+        data[40] = 0b0000_0111;
+
+        let bundle = PositionBundle::from_bytes(&data).unwrap();
+        assert_eq!(bundle.position_bundle_mint, [7u8; 32]);
+        assert!(bundle.is_occupied(2));
+        assert!(!bundle.is_occupied(3));
+        assert!(!bundle.is_occupied(POSITION_BUNDLE_SIZE));
+        assert_eq!(bundle.first_vacant(), Some(3));
+
+        data[40..72].copy_from_slice(&[0xff; 32]);
+        assert_eq!(PositionBundle::from_bytes(&data).unwrap().first_vacant(), None);
+        assert!(PositionBundle::from_bytes(&data[..71]).is_none());
+    }
+
+    #[test]
+    fn test_collect_reward() {
+        assert_eq!(COLLECT_REWARD, crate::common::hash::anchor_discriminator("global", "collect_reward"));
+        assert_eq!(WHIRLPOOL_DISCRIMINATOR, crate::common::hash::anchor_discriminator("account", "Whirlpool"));
+        let args = CollectrewardArgs { reward_index: 2 };
+        assert_eq!(args_bytes(&args), [2]);
+
+        let mut raw: [pinocchio::account::RuntimeAccount; 7] = Default::default();
+        let v = crate::common::mock_account_views(&mut raw);
+        let accounts = CollectrewardAccounts {
+            whirlpool: &v[0], position_authority: &v[1], position: &v[2], position_token_account: &v[3],
+            reward_owner_account: &v[4], reward_vault: &v[5], token_program: &v[6],
+        };
+        assert_eq!(crate::common::dump_account_roles(&accounts.to_instruction_accounts()), "r rs w r w w r");
+        collect_reward(&accounts, &args, &[]).unwrap();
+        assert_eq!(crate::common::last_cpi(), (ID, [&COLLECT_REWARD[..], &[2]].concat()));
+    }
+
+    #[test]
+    fn test_reward_vault_for_mint() {
+        let mut data = [0u8; 653];
+        data[0..8].copy_from_slice(&WHIRLPOOL_DISCRIMINATOR);
+        for (i, mint) in [(0usize, 7u8), (1, 8)] {
+            let o = 269 + i * 128;
+            data[o..o + 32].copy_from_slice(&[mint; 32]);
+            data[o + 32..o + 64].copy_from_slice(&[mint + 10; 32]);
+        }
+        assert_eq!(reward_vault_for_mint(&data, &[7; 32]), Some((0, [17; 32])));
+        assert_eq!(reward_vault_for_mint(&data, &[8; 32]), Some((1, [18; 32])));
+        assert_eq!(reward_vault_for_mint(&data, &[9; 32]), None);
+        assert_eq!(reward_vault_for_mint(&data[..652], &[7; 32]), None);
+    }
+}
@@ -5,16 +5,19 @@
 //! Instructions: 41
 
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::identity_op)]
 #![allow(unused)]
 
 use pinocchio::{
     AccountView,
     Address,
     ProgramResult,
-    cpi::{invoke_signed, Signer, CpiAccount},
+    cpi::{Signer, CpiAccount},
     instruction::{InstructionView, InstructionAccount},
 };
 
+use crate::common::{invoke_signed, read_bytes, read_pubkey, read_u64_le, read_u128_le};
+
 /// Program ID
 pub const ID: Address = Address::new_from_array(five8_const::decode_32_const("WooFif76YGRNjk1pA8wCsN67aQsD9f9iLsz4NcJ1AVb"));
 
@@ -2990,3 +2993,397 @@ pub fn repay_by_lending_manager<'a>(
     invoke_signed::<6>(&instruction, &account_views, signers)
 }
 
+
+// ============================================
+// Account State
+// ============================================
+/// Account discriminator for `WooPool`
+pub const WOO_POOL_DISCRIMINATOR: [u8; 8] = [179, 77, 61, 217, 39, 85, 13, 227];
+
+/// `WooPool::fee_rate` denominator (10 = 1 bp)
+pub const FEE_RATE_DENOMINATOR: u64 = 100_000;
+
+/// One-token pool: holds a single base token, priced against the shared quote token
+///
+/// A swap touches the `from` and `to` pools plus the quote token's own
+/// pool, whose vault settles the quote leg.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WooPool {
+    pub wooconfig: [u8; 32],
+    pub authority: [u8; 32],
+    /// `Wooracle` account pricing `token_mint`
+    pub wooracle: [u8; 32],
+    /// Swap fee, in units of `FEE_RATE_DENOMINATOR`
+    pub fee_rate: u16,
+    pub max_gamma: u128,
+    pub max_notional_swap: u128,
+    pub cap_bal: u128,
+    pub min_swap_amount: u128,
+    pub unclaimed_fee: u128,
+    pub token_mint: [u8; 32],
+    pub token_vault: [u8; 32],
+    pub quote_token_mint: [u8; 32],
+    pub base_decimals: u8,
+}
+
+impl WooPool {
+    /// Account size
+    pub const SIZE: usize = 284;
+
+    /// Parse a `WooPool` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || data[0..8] != WOO_POOL_DISCRIMINATOR {
+            return None;
+        }
+        // wooconfig (8), woopool_bump (40), authority (41), wooracle (73), fee_rate (105)
+        Some(Self {
+            wooconfig: read_pubkey(data, 8)?,
+            authority: read_pubkey(data, 41)?,
+            wooracle: read_pubkey(data, 73)?,
+            fee_rate: u16::from_le_bytes(read_bytes(data, 105)?),
+            max_gamma: read_u128_le(data, 107)?,
+            max_notional_swap: read_u128_le(data, 123)?,
+            cap_bal: read_u128_le(data, 139)?,
+            min_swap_amount: read_u128_le(data, 155)?,
+            unclaimed_fee: read_u128_le(data, 171)?,
+            token_mint: read_pubkey(data, 187)?,
+            token_vault: read_pubkey(data, 219)?,
+            quote_token_mint: read_pubkey(data, 251)?,
+            base_decimals: data[283],
+        })
+    }
+
+    /// Whether this pool holds the quote token itself
+    #[inline(always)]
+    pub fn is_quote_pool(&self) -> bool {
+        self.token_mint == self.quote_token_mint
+    }
+}
+
+/// Account discriminator for `Wooracle`
+pub const WOORACLE_DISCRIMINATOR: [u8; 8] = [130, 213, 224, 3, 126, 58, 126, 73];
+
+/// Fixed-point one for `Wooracle::spread`, `coeff` and `bound` (1e18 = 100%)
+pub const WOORACLE_UNIT: u128 = 1_000_000_000_000_000_000;
+
+/// Oracle state of one base token, priced in the shared quote token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wooracle {
+    pub wooconfig: [u8; 32],
+    pub authority: [u8; 32],
+    pub token_mint: [u8; 32],
+    /// Pyth feed of `token_mint`
+    pub feed_account: [u8; 32],
+    pub price_update: [u8; 32],
+    pub maximum_age: u64,
+    pub price_decimals: u8,
+    pub quote_decimals: u8,
+    pub base_decimals: u8,
+    /// Unix timestamp of the last `price` update
+    pub updated_at: i64,
+    /// Seconds `price` stays usable after `updated_at`
+    pub stale_duration: i64,
+    /// Allowed deviation of `price` from the Pyth price, in `WOORACLE_UNIT`
+    pub bound: u64,
+    /// Quote tokens per base token, with `price_decimals` decimals
+    pub price: u128,
+    /// Price moved per unit of notional traded, in `WOORACLE_UNIT`
+    pub coeff: u64,
+    /// Half spread around `price`, in `WOORACLE_UNIT`
+    pub spread: u64,
+    pub range_min: u128,
+    pub range_max: u128,
+    pub quote_token_mint: [u8; 32],
+    pub quote_feed_account: [u8; 32],
+    pub quote_price_update: [u8; 32],
+}
+
+impl Wooracle {
+    /// Account size
+    pub const SIZE: usize = 363;
+
+    /// Parse a `Wooracle` account, validating the discriminator
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || data[0..8] != WOORACLE_DISCRIMINATOR {
+            return None;
+        }
+        Some(Self {
+            wooconfig: read_pubkey(data, 8)?,
+            authority: read_pubkey(data, 40)?,
+            token_mint: read_pubkey(data, 72)?,
+            feed_account: read_pubkey(data, 104)?,
+            price_update: read_pubkey(data, 136)?,
+            maximum_age: read_u64_le(data, 168)?,
+            price_decimals: data[176],
+            quote_decimals: data[177],
+            base_decimals: data[178],
+            updated_at: i64::from_le_bytes(read_bytes(data, 179)?),
+            stale_duration: i64::from_le_bytes(read_bytes(data, 187)?),
+            bound: read_u64_le(data, 195)?,
+            price: read_u128_le(data, 203)?,
+            coeff: read_u64_le(data, 219)?,
+            spread: read_u64_le(data, 227)?,
+            range_min: read_u128_le(data, 235)?,
+            range_max: read_u128_le(data, 251)?,
+            quote_token_mint: read_pubkey(data, 267)?,
+            quote_feed_account: read_pubkey(data, 299)?,
+            quote_price_update: read_pubkey(data, 331)?,
+        })
+    }
+
+    /// Whether `price` is non-zero and inside `[range_min, range_max]`
+    ///
+    /// A `range_max` of 0 leaves the range open. The program also checks
+    /// `price` against the Pyth feed within `bound`, which needs the price
+    /// update account and is not modelled here.
+    #[inline(always)]
+    pub fn is_feasible(&self) -> bool {
+        self.price != 0 && self.price >= self.range_min && (self.range_max == 0 || self.price <= self.range_max)
+    }
+
+    /// Whether `price` was updated within `stale_duration` of `now`
+    #[inline(always)]
+    pub fn is_fresh(&self, now: i64) -> bool {
+        now <= self.updated_at.saturating_add(self.stale_duration)
+    }
+}
+
+impl<'a> SwapAccounts<'a> {
+    /// Bind `accounts` (in `swap` order) after checking them against pool state
+    ///
+    /// `pool_quote` is the pool of the shared quote token. Returns `None` if
+    /// fewer than 17 accounts are given, the pools belong to different
+    /// configs or quote tokens, or a wooconfig / wooracle / vault account
+    /// does not match its pool.
+    pub fn from_pool_state(
+        accounts: &'a [AccountView],
+        pool_from: &WooPool,
+        pool_to: &WooPool,
+        pool_quote: &WooPool,
+    ) -> Option<Self> {
+        let [
+            wooconfig, token_program, payer, wooracle_from, woopool_from, token_owner_account_from,
+            token_vault_from, price_update_from, wooracle_to, woopool_to, token_owner_account_to,
+            token_vault_to, price_update_to, woopool_quote, quote_price_update, quote_token_vault, rebate_to,
+        ] = accounts.get(..17)? else {
+            return None;
+        };
+        let matches = |view: &AccountView, key: &[u8; 32]| view.address().as_array() == key;
+        let consistent = pool_to.wooconfig == pool_from.wooconfig
+            && pool_quote.wooconfig == pool_from.wooconfig
+            && pool_to.quote_token_mint == pool_from.quote_token_mint
+            && pool_quote.is_quote_pool()
+            && pool_quote.token_mint == pool_from.quote_token_mint
+            && matches(wooconfig, &pool_from.wooconfig)
+            && matches(wooracle_from, &pool_from.wooracle)
+            && matches(token_vault_from, &pool_from.token_vault)
+            && matches(wooracle_to, &pool_to.wooracle)
+            && matches(token_vault_to, &pool_to.token_vault)
+            && matches(quote_token_vault, &pool_quote.token_vault);
+        if !consistent {
+            return None;
+        }
+        Some(Self {
+            wooconfig,
+            token_program,
+            payer,
+            wooracle_from,
+            woopool_from,
+            token_owner_account_from,
+            token_vault_from,
+            price_update_from,
+            wooracle_to,
+            woopool_to,
+            token_owner_account_to,
+            token_vault_to,
+            price_update_to,
+            woopool_quote,
+            quote_price_update,
+            quote_token_vault,
+            rebate_to,
+        })
+    }
+}
+
+// ============================================
+// Quote
+// ============================================
+
+/// Output of swapping `amount_in` between a base token and the quote token
+///
+/// WooFi prices off its oracle rather than reserves: the trade fills at
+/// `price * (1 - spread - gamma)`, where `gamma = coeff * notional` grows
+/// with size, so large swaps get a worse rate. `wooracle` and `woopool`
+/// are the base token's. The pool fee is charged on the quote side, from
+/// the output when selling base and from the input when buying it.
+///
+/// Returns `None` when the oracle price is infeasible, the trade exceeds
+/// the pool's `max_gamma` or `max_notional_swap`, or the math overflows.
+pub fn quote(wooracle: &Wooracle, woopool: &WooPool, amount_in: u64, base_to_quote: bool) -> Option<u64> {
+    if !wooracle.is_feasible() {
+        return None;
+    }
+    let price = wooracle.price;
+    let price_dec = 10u128.checked_pow(wooracle.price_decimals as u32)?;
+    let quote_dec = 10u128.checked_pow(wooracle.quote_decimals as u32)?;
+    let base_dec = 10u128.checked_pow(wooracle.base_decimals as u32)?;
+    let fee = |quote_amount: u128| quote_amount * woopool.fee_rate as u128 / FEE_RATE_DENOMINATOR as u128;
+
+    let (gamma, notional, amount_out) = if base_to_quote {
+        let base_amount = amount_in as u128;
+        let value = base_amount.checked_mul(price)?;
+        let gamma = value.checked_mul(wooracle.coeff as u128)? / price_dec / base_dec;
+        let notional = value.checked_mul(quote_dec)? / price_dec / base_dec;
+        let factor = WOORACLE_UNIT.checked_sub(gamma)?.checked_sub(wooracle.spread as u128)?;
+        let quote_amount = (base_amount.checked_mul(quote_dec)?.checked_mul(price)? / price_dec).checked_mul(factor)?
+            / WOORACLE_UNIT
+            / base_dec;
+        (gamma, notional, quote_amount - fee(quote_amount))
+    } else {
+        let quote_amount = amount_in as u128 - fee(amount_in as u128);
+        let gamma = quote_amount.checked_mul(wooracle.coeff as u128)? / quote_dec;
+        let factor = WOORACLE_UNIT.checked_sub(gamma)?.checked_sub(wooracle.spread as u128)?;
+        let base_amount = (quote_amount.checked_mul(base_dec)?.checked_mul(price_dec)? / price).checked_mul(factor)?
+            / WOORACLE_UNIT
+            / quote_dec;
+        (gamma, quote_amount, base_amount)
+    };
+    if gamma > woopool.max_gamma || notional > woopool.max_notional_swap {
+        return None;
+    }
+    u64::try_from(amount_out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::mock_account_views;
+    use pinocchio::account::RuntimeAccount;
+
+    /// `WooPool` for `token_mint` priced against mint `[50; 32]` under config `[1; 32]`
+    fn woo_pool_fixture(token_mint: u8, wooracle: u8, token_vault: u8) -> [u8; WooPool::SIZE] {
+        let mut data = [0u8; WooPool::SIZE];
+        data[0..8].copy_from_slice(&WOO_POOL_DISCRIMINATOR);
+        data[8..40].copy_from_slice(&[1; 32]);
+        data[41..73].copy_from_slice(&[2; 32]);
+        data[73..105].copy_from_slice(&[wooracle; 32]);
+        data[105..107].copy_from_slice(&25u16.to_le_bytes());
+        data[107..123].copy_from_slice(&(5u128 * 10u128.pow(15)).to_le_bytes());
+        data[123..139].copy_from_slice(&(1u128 << 70).to_le_bytes());
+        data[155..171].copy_from_slice(&1_000u128.to_le_bytes());
+        data[187..219].copy_from_slice(&[token_mint; 32]);
+        data[219..251].copy_from_slice(&[token_vault; 32]);
+        data[251..283].copy_from_slice(&[50; 32]);
+        data[283] = 9;
+        data
+    }
+
+    #[test]
+    fn test_woo_pool_from_bytes() {
+        let data = woo_pool_fixture(30, 4, 7);
+        let pool = WooPool::from_bytes(&data).unwrap();
+        assert_eq!(pool.wooconfig, [1; 32]);
+        assert_eq!(pool.wooracle, [4; 32]);
+        assert_eq!(pool.fee_rate, 25);
+        assert_eq!(pool.max_gamma, 5 * 10u128.pow(15));
+        assert_eq!(pool.max_notional_swap, 1 << 70);
+        assert_eq!(pool.min_swap_amount, 1_000);
+        assert_eq!(pool.token_mint, [30; 32]);
+        assert_eq!(pool.token_vault, [7; 32]);
+        assert_eq!(pool.quote_token_mint, [50; 32]);
+        assert_eq!(pool.base_decimals, 9);
+        assert!(!pool.is_quote_pool());
+
+        assert!(WooPool::from_bytes(&data[..WooPool::SIZE - 1]).is_none());
+        let mut bad = data;
+        bad[0] ^= 1;
+        assert!(WooPool::from_bytes(&bad).is_none());
+    }
+
+    /// SOL oracle: 150 USDC, 8 price decimals, 0.1% spread, coeff 1e-6
+    fn wooracle_fixture(price: u128) -> [u8; Wooracle::SIZE] {
+        let mut data = [0u8; Wooracle::SIZE];
+        data[0..8].copy_from_slice(&WOORACLE_DISCRIMINATOR);
+        data[8..40].copy_from_slice(&[1; 32]);
+        data[72..104].copy_from_slice(&[30; 32]);
+        data[176] = 8;
+        data[177] = 6;
+        data[178] = 9;
+        data[179..187].copy_from_slice(&1_000i64.to_le_bytes());
+        data[187..195].copy_from_slice(&300i64.to_le_bytes());
+        data[203..219].copy_from_slice(&price.to_le_bytes());
+        data[219..227].copy_from_slice(&1_000_000_000_000u64.to_le_bytes());
+        data[227..235].copy_from_slice(&1_000_000_000_000_000u64.to_le_bytes());
+        data[251..267].copy_from_slice(&(200u128 * 10u128.pow(8)).to_le_bytes());
+        data[267..299].copy_from_slice(&[50; 32]);
+        data
+    }
+
+    #[test]
+    fn test_wooracle_from_bytes() {
+        assert_eq!(WOORACLE_DISCRIMINATOR, crate::common::hash::anchor_discriminator("account", "Wooracle"));
+        let data = wooracle_fixture(150 * 10u128.pow(8));
+        let oracle = Wooracle::from_bytes(&data).unwrap();
+        assert_eq!(oracle.token_mint, [30; 32]);
+        assert_eq!((oracle.price_decimals, oracle.quote_decimals, oracle.base_decimals), (8, 6, 9));
+        assert_eq!(oracle.price, 15_000_000_000);
+        assert_eq!(oracle.spread, 10u64.pow(15));
+        assert_eq!(oracle.quote_token_mint, [50; 32]);
+        assert!(oracle.is_feasible());
+        assert!(oracle.is_fresh(1_300));
+        assert!(!oracle.is_fresh(1_301));
+
+        assert!(Wooracle::from_bytes(&data[..Wooracle::SIZE - 1]).is_none());
+        let mut bad = data;
+        bad[0] ^= 1;
+        assert!(Wooracle::from_bytes(&bad).is_none());
+    }
+
+    #[test]
+    fn test_quote() {
+        let oracle = Wooracle::from_bytes(&wooracle_fixture(150 * 10u128.pow(8))).unwrap();
+        // 2.5 bps fee, max gamma 0.5%
+        let pool = WooPool::from_bytes(&woo_pool_fixture(30, 4, 7)).unwrap();
+
+        // 1 SOL: gamma 0.015%, spread 0.1%, then the fee
+        assert_eq!(quote(&oracle, &pool, 1_000_000_000, true), Some(149_790_044));
+        // 20 SOL: gamma 0.3%, so a worse rate per SOL
+        assert_eq!(quote(&oracle, &pool, 20_000_000_000, true), Some(2_987_253_000));
+        // 40 SOL: gamma 0.6% exceeds max_gamma
+        assert_eq!(quote(&oracle, &pool, 40_000_000_000, true), None);
+
+        // Buying SOL: fee on the USDC input first
+        assert_eq!(quote(&oracle, &pool, 150_000_000, false), Some(998_600_324));
+        assert_eq!(quote(&oracle, &pool, 3_000_000_000, false), Some(19_915_034_996));
+        assert_eq!(quote(&oracle, &pool, 6_000_000_000, false), None);
+        assert_eq!(quote(&oracle, &pool, 0, false), Some(0));
+
+        // Infeasible oracle: no price, or outside the admin range
+        let zero = Wooracle::from_bytes(&wooracle_fixture(0)).unwrap();
+        assert_eq!(quote(&zero, &pool, 1_000_000_000, true), None);
+        let out_of_range = Wooracle::from_bytes(&wooracle_fixture(250 * 10u128.pow(8))).unwrap();
+        assert!(!out_of_range.is_feasible());
+        assert_eq!(quote(&out_of_range, &pool, 1_000_000_000, true), None);
+    }
+
+    #[test]
+    fn test_swap_accounts_from_pool_state() {
+        let mut raw: [RuntimeAccount; 17] = Default::default();
+        let v = mock_account_views(&mut raw);
+        // mock addresses are [index + 1; 32]
+        let from = WooPool::from_bytes(&woo_pool_fixture(30, 4, 7)).unwrap();
+        let to = WooPool::from_bytes(&woo_pool_fixture(31, 9, 12)).unwrap();
+        let quote = WooPool::from_bytes(&woo_pool_fixture(50, 60, 16)).unwrap();
+
+        let accounts = SwapAccounts::from_pool_state(&v, &from, &to, &quote).unwrap();
+        assert_eq!(accounts.woopool_from.address().as_array(), &[5; 32]);
+        assert_eq!(accounts.quote_token_vault.address().as_array(), &[16; 32]);
+        assert_eq!(accounts.rebate_to.address().as_array(), &[17; 32]);
+
+        // accounts swapped between legs
+        assert!(SwapAccounts::from_pool_state(&v, &to, &from, &quote).is_none());
+        // quote pool must hold the quote token
+        assert!(SwapAccounts::from_pool_state(&v, &from, &to, &from).is_none());
+        assert!(SwapAccounts::from_pool_state(&v[..16], &from, &to, &quote).is_none());
+    }
+}